@@ -1,6 +1,7 @@
 //! Global asset handles
 
 use bevy::{
+    audio::{SpatialScale, Volume},
     ecs::system::EntityCommands,
     prelude::*,
     render::texture::{
@@ -48,10 +49,70 @@ fn repeat_texture(settings: &mut ImageLoaderSettings) {
     });
 }
 
+/// Which sounds should be played
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum AudioProfile {
+    /// All sounds
+    #[default]
+    Full,
+    /// Only the sounds which are critical to the game,
+    /// such as getting hurt
+    Essential,
+    /// No sound at all
+    Off,
+}
+
+impl AudioProfile {
+    /// The next profile, for cycling through them in the settings
+    pub fn next(self) -> Self {
+        match self {
+            AudioProfile::Full => AudioProfile::Essential,
+            AudioProfile::Essential => AudioProfile::Off,
+            AudioProfile::Off => AudioProfile::Full,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AudioProfile::Full => "Full",
+            AudioProfile::Essential => "Essential",
+            AudioProfile::Off => "Off",
+        }
+    }
+
+    /// whether sounds of the given category should be played
+    pub fn allows(self, category: SoundCategory) -> bool {
+        match self {
+            AudioProfile::Full => true,
+            AudioProfile::Essential => category == SoundCategory::Essential,
+            AudioProfile::Off => false,
+        }
+    }
+}
+
+/// The category of a sound effect,
+/// to decide whether it plays under the active audio profile
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SoundCategory {
+    /// critical cues, such as damage from a wrong answer
+    Essential,
+    /// everything else: clicks, pickups, ambience
+    Ambient,
+}
+
 /// Global resource for audio handles
 #[derive(Debug, Resource)]
 pub struct AudioHandles {
-    pub enabled: bool,
+    pub profile: AudioProfile,
+    /// the master volume, from 0 (silent) to 1 (full),
+    /// applied on top of the effects and music volumes
+    pub volume: f32,
+    /// the volume of sound effects, from 0 (silent) to 1 (full)
+    pub sfx_volume: f32,
+    /// the volume of background music, from 0 (silent) to 1 (full)
+    pub music_volume: f32,
+    /// whether some sounds come from where they happen in the world
+    pub spatial: bool,
     pub zipclick: Handle<AudioSource>,
     pub pickup: Handle<AudioSource>,
     pub equipmentclick1: Handle<AudioSource>,
@@ -59,6 +120,14 @@ pub struct AudioHandles {
     pub hit02: Handle<AudioSource>,
     pub hit37: Handle<AudioSource>,
     pub dread: Handle<AudioSource>,
+    /// the cue for a wave of mobs about to attack, if there is one
+    pub stinger: Option<Handle<AudioSource>>,
+    /// the chime for when the way ahead is clear, if there is one
+    pub onward: Option<Handle<AudioSource>>,
+    /// the music track of the menu, if there is one
+    pub menu_music: Option<Handle<AudioSource>>,
+    /// the music track of the live game, if there is one
+    pub live_music: Option<Handle<AudioSource>>,
 }
 
 impl FromWorld for AudioHandles {
@@ -71,9 +140,19 @@ impl FromWorld for AudioHandles {
         let hit02 = asset_server.load("audio/hit02.ogg");
         let hit37 = asset_server.load("audio/hit37.ogg");
         let dread = asset_server.load("audio/dread.ogg");
+        // no dedicated sound files yet
+        let stinger = None;
+        let onward = None;
+        // no dedicated music tracks yet
+        let menu_music = None;
+        let live_music = None;
 
         AudioHandles {
-            enabled: true,
+            profile: AudioProfile::default(),
+            volume: 1.,
+            sfx_volume: 1.,
+            music_volume: 1.,
+            spatial: false,
             zipclick,
             pickup,
             equipmentclick1,
@@ -81,50 +160,434 @@ impl FromWorld for AudioHandles {
             hit02,
             hit37,
             dread,
+            stinger,
+            onward,
+            menu_music,
+            live_music,
         }
     }
 }
 
+#[cfg(test)]
 impl AudioHandles {
+    /// audio handles which never play anything,
+    /// for testing systems which make sounds
+    pub fn muted() -> Self {
+        AudioHandles {
+            profile: AudioProfile::Off,
+            volume: 0.,
+            sfx_volume: 0.,
+            music_volume: 0.,
+            spatial: false,
+            zipclick: default(),
+            pickup: default(),
+            equipmentclick1: default(),
+            fireball: default(),
+            hit02: default(),
+            hit37: default(),
+            dread: default(),
+            stinger: None,
+            onward: None,
+            menu_music: None,
+            live_music: None,
+        }
+    }
+}
+
+impl AudioHandles {
+    /// how much world distances count for spatial sound,
+    /// so that far away sounds are quieter but still heard
+    const SPATIAL_SCALE: f32 = 0.25;
+
+    /// the volume levels to choose from in the settings
+    const VOLUME_STEPS: [f32; 5] = [0., 0.25, 0.5, 0.75, 1.];
+
+    /// the volume level after the given one,
+    /// going back to silence after the loudest
+    pub fn next_volume(volume: f32) -> f32 {
+        Self::VOLUME_STEPS
+            .into_iter()
+            .find(|step| *step > volume)
+            .unwrap_or(0.)
+    }
+
+    /// the volume at which sound effects play
+    fn effective_sfx_volume(&self) -> f32 {
+        self.volume * self.sfx_volume
+    }
+
+    /// the volume at which music should play right now
+    fn effective_music_volume(&self) -> f32 {
+        if self.profile.allows(SoundCategory::Ambient) {
+            self.volume * self.music_volume
+        } else {
+            0.
+        }
+    }
+
     pub fn play_zipclick<'a>(&self, cmd: &'a mut Commands) -> Option<EntityCommands<'a>> {
-        self.play_impl(cmd, &self.zipclick)
+        self.play_impl(cmd, &self.zipclick, SoundCategory::Ambient)
     }
 
     pub fn play_pickup<'a>(&self, cmd: &'a mut Commands) -> Option<EntityCommands<'a>> {
-        self.play_impl(cmd, &self.pickup)
+        self.play_impl(cmd, &self.pickup, SoundCategory::Ambient)
     }
 
     pub fn play_equipmentclick1<'a>(&self, cmd: &'a mut Commands) -> Option<EntityCommands<'a>> {
-        self.play_impl(cmd, &self.equipmentclick1)
+        self.play_impl(cmd, &self.equipmentclick1, SoundCategory::Ambient)
     }
 
-    pub fn play_fireball<'a>(&self, cmd: &'a mut Commands) -> Option<EntityCommands<'a>> {
-        self.play_impl(cmd, &self.fireball)
+    pub fn play_fireball<'a>(
+        &self,
+        cmd: &'a mut Commands,
+        position: Vec3,
+    ) -> Option<EntityCommands<'a>> {
+        self.play_spatial(cmd, &self.fireball, SoundCategory::Ambient, position)
     }
 
-    pub fn play_hit02<'a>(&self, cmd: &'a mut Commands) -> Option<EntityCommands<'a>> {
-        self.play_impl(cmd, &self.hit02)
+    pub fn play_hit02<'a>(
+        &self,
+        cmd: &'a mut Commands,
+        position: Vec3,
+    ) -> Option<EntityCommands<'a>> {
+        self.play_spatial(cmd, &self.hit02, SoundCategory::Ambient, position)
     }
 
     pub fn play_hit37<'a>(&self, cmd: &'a mut Commands) -> Option<EntityCommands<'a>> {
-        self.play_impl(cmd, &self.hit37)
+        self.play_impl(cmd, &self.hit37, SoundCategory::Essential)
     }
 
     pub fn play_dread<'a>(&self, cmd: &'a mut Commands) -> Option<EntityCommands<'a>> {
-        self.play_impl(cmd, &self.dread)
+        self.play_impl(cmd, &self.dread, SoundCategory::Ambient)
+    }
+
+    /// a short rising cue for when a wave of mobs is about to attack
+    /// (silent until there is a sound file for it)
+    pub fn play_stinger<'a>(&self, cmd: &'a mut Commands) -> Option<EntityCommands<'a>> {
+        self.play_impl_with_settings(
+            cmd,
+            self.stinger.as_ref()?,
+            SoundCategory::Essential,
+            PlaybackSettings::ONCE.with_volume(Volume::new(0.7)),
+        )
+    }
+
+    /// a soft chime for when the way ahead is clear
+    /// (silent until there is a sound file for it)
+    pub fn play_onward<'a>(&self, cmd: &'a mut Commands) -> Option<EntityCommands<'a>> {
+        self.play_impl_with_settings(
+            cmd,
+            self.onward.as_ref()?,
+            SoundCategory::Ambient,
+            PlaybackSettings::ONCE.with_volume(Volume::new(0.5)),
+        )
+    }
+
+    /// Play a sound over and over as background music,
+    /// at the given volume relative to the music volume,
+    /// fading in from silence.
+    ///
+    /// The music plays even if muted,
+    /// so that it can be heard once the volume is turned up.
+    pub fn play_music<'a>(
+        &self,
+        cmd: &'a mut Commands,
+        handle: &Handle<AudioSource>,
+        volume: f32,
+        speed: f32,
+    ) -> EntityCommands<'a> {
+        cmd.spawn((
+            BackgroundMusic {
+                volume,
+                fade: 0.,
+                fading_out: false,
+            },
+            AudioBundle {
+                source: handle.clone(),
+                settings: PlaybackSettings::LOOP
+                    .with_volume(Volume::new(0.))
+                    .with_speed(speed),
+            },
+        ))
+    }
+
+    /// Play a sound coming from the given position in the world,
+    /// or from nowhere in particular if spatial sound is disabled
+    fn play_spatial<'a>(
+        &self,
+        cmd: &'a mut Commands,
+        handle: &Handle<AudioSource>,
+        category: SoundCategory,
+        position: Vec3,
+    ) -> Option<EntityCommands<'a>> {
+        if !self.spatial {
+            return self.play_impl(cmd, handle, category);
+        }
+        let settings = PlaybackSettings::DESPAWN
+            .with_spatial(true)
+            .with_spatial_scale(SpatialScale::new(Self::SPATIAL_SCALE));
+        let mut entity = self.play_impl_with_settings(cmd, handle, category, settings)?;
+        entity.insert(TransformBundle::from_transform(
+            Transform::from_translation(position),
+        ));
+        Some(entity)
     }
 
     fn play_impl<'a>(
         &self,
         cmd: &'a mut Commands,
         handle: &Handle<AudioSource>,
+        category: SoundCategory,
+    ) -> Option<EntityCommands<'a>> {
+        self.play_impl_with_settings(cmd, handle, category, PlaybackSettings::default())
+    }
+
+    fn play_impl_with_settings<'a>(
+        &self,
+        cmd: &'a mut Commands,
+        handle: &Handle<AudioSource>,
+        category: SoundCategory,
+        settings: PlaybackSettings,
     ) -> Option<EntityCommands<'a>> {
-        if !self.enabled {
+        // nothing to play when muted
+        let sfx_volume = self.effective_sfx_volume();
+        if !self.profile.allows(category) || sfx_volume <= 0. {
             return None;
         }
+        let volume = Volume::new(settings.volume.get() * sfx_volume);
         Some(cmd.spawn(AudioBundle {
             source: handle.clone(),
-            ..default()
+            settings: settings.with_volume(volume),
         }))
     }
 }
+
+/// Component for looping background music,
+/// which follows the music volume
+/// and fades in and out between app states
+#[derive(Debug, Component)]
+pub struct BackgroundMusic {
+    /// the volume relative to the music volume
+    volume: f32,
+    /// how far the music has faded in, from 0 (silent) to 1 (full)
+    fade: f32,
+    /// whether the music is on its way out
+    fading_out: bool,
+}
+
+/// how long it takes for background music to fade in or out, in seconds
+const MUSIC_FADE_DURATION: f32 = 1.;
+
+/// start the background music of the menu
+pub fn start_menu_music(mut cmd: Commands, audio_handles: Res<AudioHandles>) {
+    if let Some(music) = &audio_handles.menu_music {
+        audio_handles.play_music(&mut cmd, music, 0.4, 1.);
+    }
+}
+
+/// start the background music of the live game
+pub fn start_live_music(mut cmd: Commands, audio_handles: Res<AudioHandles>) {
+    if let Some(music) = &audio_handles.live_music {
+        audio_handles.play_music(&mut cmd, music, 0.25, 1.);
+    }
+}
+
+/// let the background music playing so far fade out
+pub fn stop_music(mut music_q: Query<&mut BackgroundMusic>) {
+    for mut music in &mut music_q {
+        music.fading_out = true;
+    }
+}
+
+/// system that fades background music in and out
+/// and keeps it at the volume in the settings
+pub fn update_background_music(
+    mut cmd: Commands,
+    time: Res<Time<Real>>,
+    audio_handles: Res<AudioHandles>,
+    mut music_q: Query<(Entity, &mut BackgroundMusic, Option<&AudioSink>)>,
+) {
+    let music_volume = audio_handles.effective_music_volume();
+    let step = time.delta_seconds() / MUSIC_FADE_DURATION;
+    for (entity, mut music, sink) in &mut music_q {
+        if music.fading_out {
+            music.fade -= step;
+            if music.fade <= 0. {
+                cmd.entity(entity).despawn();
+                continue;
+            }
+        } else {
+            music.fade = (music.fade + step).min(1.);
+        }
+        // the sink is only there once the music starts playing
+        if let Some(sink) = sink {
+            sink.set_volume(music.volume * music.fade * music_volume);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{ecs::world::CommandQueue, state::app::StatesPlugin};
+
+    use crate::AppState;
+
+    use super::*;
+
+    fn handles(volume: f32, sfx_volume: f32, music_volume: f32) -> AudioHandles {
+        AudioHandles {
+            profile: AudioProfile::Full,
+            volume,
+            sfx_volume,
+            music_volume,
+            ..AudioHandles::muted()
+        }
+    }
+
+    #[test]
+    fn effects_and_music_volumes_are_independent() {
+        let quiet_music = handles(1., 1., 0.25);
+        assert_eq!(quiet_music.effective_sfx_volume(), 1.);
+        assert_eq!(quiet_music.effective_music_volume(), 0.25);
+
+        let quiet_effects = handles(1., 0.25, 1.);
+        assert_eq!(quiet_effects.effective_sfx_volume(), 0.25);
+        assert_eq!(quiet_effects.effective_music_volume(), 1.);
+    }
+
+    #[test]
+    fn master_volume_scales_effects_and_music() {
+        let audio = handles(0.5, 0.5, 0.75);
+        assert_eq!(audio.effective_sfx_volume(), 0.25);
+        assert_eq!(audio.effective_music_volume(), 0.375);
+
+        let silent = handles(0., 1., 1.);
+        assert_eq!(silent.effective_sfx_volume(), 0.);
+        assert_eq!(silent.effective_music_volume(), 0.);
+    }
+
+    /// play a sound effect,
+    /// returning the volume of each sound spawned
+    fn play_pickup(audio: &AudioHandles) -> Vec<f32> {
+        let mut world = World::new();
+        let mut queue = CommandQueue::default();
+        let mut cmd = Commands::new(&mut queue, &world);
+        let played = audio.play_pickup(&mut cmd).is_some();
+        queue.apply(&mut world);
+
+        let volumes: Vec<_> = world
+            .query::<&PlaybackSettings>()
+            .iter(&world)
+            .map(|settings| settings.volume.get())
+            .collect();
+        assert_eq!(played, !volumes.is_empty());
+        volumes
+    }
+
+    #[test]
+    fn silent_master_volume_plays_nothing() {
+        assert!(play_pickup(&handles(0., 1., 1.)).is_empty());
+        assert_eq!(play_pickup(&handles(0.5, 1., 1.)), vec![0.5]);
+        assert_eq!(play_pickup(&handles(1., 1., 1.)), vec![1.]);
+    }
+
+    /// play a fireball sound at the given position,
+    /// returning where each sound spawned comes from, if anywhere
+    fn play_fireball_at(audio: &AudioHandles, position: Vec3) -> Vec<Option<Vec3>> {
+        let mut world = World::new();
+        let mut queue = CommandQueue::default();
+        let mut cmd = Commands::new(&mut queue, &world);
+        audio.play_fireball(&mut cmd, position);
+        queue.apply(&mut world);
+
+        world
+            .query::<(&PlaybackSettings, Option<&Transform>)>()
+            .iter(&world)
+            .map(|(settings, transform)| {
+                assert_eq!(settings.spatial, transform.is_some());
+                transform.map(|transform| transform.translation)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn spatial_sounds_come_from_where_they_happen() {
+        let position = Vec3::new(1., 2., -30.);
+        let spatial = AudioHandles {
+            spatial: true,
+            ..handles(1., 1., 1.)
+        };
+        assert_eq!(play_fireball_at(&spatial, position), vec![Some(position)]);
+
+        // without spatial sound they come from nowhere in particular
+        assert_eq!(play_fireball_at(&handles(1., 1., 1.), position), vec![None]);
+    }
+
+    #[test]
+    fn volume_steps_cycle_back_to_silence() {
+        let mut volume = 0.;
+        let mut steps = vec![];
+        for _ in 0..5 {
+            volume = AudioHandles::next_volume(volume);
+            steps.push(volume);
+        }
+        assert_eq!(steps, vec![0.25, 0.5, 0.75, 1., 0.]);
+    }
+
+    fn music_app(live_music: Option<Handle<AudioSource>>) -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin))
+            .init_state::<AppState>()
+            .insert_resource(AudioHandles {
+                live_music,
+                ..AudioHandles::muted()
+            })
+            .add_systems(Update, update_background_music)
+            .add_systems(OnEnter(AppState::Menu), start_menu_music)
+            .add_systems(OnExit(AppState::Menu), stop_music)
+            .add_systems(OnEnter(AppState::Live), start_live_music)
+            .add_systems(OnExit(AppState::Live), stop_music);
+        app.update();
+        app
+    }
+
+    fn playing_music(app: &mut App) -> usize {
+        app.world_mut()
+            .query::<&BackgroundMusic>()
+            .iter(app.world())
+            .filter(|music| !music.fading_out)
+            .count()
+    }
+
+    #[test]
+    fn entering_live_plays_one_music() {
+        let mut app = music_app(Some(Handle::default()));
+        // no menu music to play yet
+        assert_eq!(playing_music(&mut app), 0);
+
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Live);
+        app.update();
+        assert_eq!(playing_music(&mut app), 1);
+
+        // going back and forth does not pile them up
+        for state in [AppState::Menu, AppState::Live] {
+            app.world_mut()
+                .resource_mut::<NextState<AppState>>()
+                .set(state);
+            app.update();
+        }
+        assert_eq!(playing_music(&mut app), 1);
+    }
+
+    #[test]
+    fn no_music_plays_without_a_track() {
+        let mut app = music_app(None);
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Live);
+        app.update();
+        assert_eq!(playing_music(&mut app), 0);
+    }
+}
@@ -90,30 +90,103 @@ fn check_cheat(
     if text_buffer.has_typed("iddqd") {
         cheats.invulnerability = !cheats.invulnerability;
         if cheats.invulnerability {
-            println!("Cheat code activated: invulnerability");
+            info!("Cheat code activated: invulnerability");
         } else {
-            println!("Cheat code deactivated: invulnerability");
+            info!("Cheat code deactivated: invulnerability");
         }
         cheats.used_cheats = true;
         text_buffer.clear();
     } else if text_buffer.has_typed("nothingleftforme") {
         if *app_state.get() == AppState::Live {
-            println!("Cheat code activated: next level by going left");
-            if current_level.advance(Decision::Left) {
-                next_state.set(LiveState::LoadingLevel);
-            }
-            cheats.used_cheats = true;
-            text_buffer.clear();
+            info!("Cheat code activated: next level by going left");
+            cheat_advance(
+                &mut current_level,
+                &mut cheats,
+                &mut next_state,
+                Decision::Left,
+            );
         }
         text_buffer.clear();
     } else if text_buffer.has_typed("thisisdownrightridiculous") {
-        println!("Cheat code activated: next level by going right");
+        info!("Cheat code activated: next level by going right");
         if *app_state.get() == AppState::Live {
-            if current_level.advance(Decision::Right) {
-                next_state.set(LiveState::LoadingLevel);
-            }
-            cheats.used_cheats = true;
+            cheat_advance(
+                &mut current_level,
+                &mut cheats,
+                &mut next_state,
+                Decision::Right,
+            );
             text_buffer.clear();
         }
+    } else if text_buffer.has_typed("whereami") {
+        // a diagnostic rather than a cheat,
+        // so it does not count as cheating
+        if *app_state.get() == AppState::Live {
+            info!(
+                "Current level {}: {}",
+                current_level.id,
+                current_level.spec.summary()
+            );
+        }
+        text_buffer.clear();
+    }
+}
+
+/// advance to the next level through cheating,
+/// staying on the same level if there are no more levels ahead
+fn cheat_advance(
+    current_level: &mut CurrentLevel,
+    cheats: &mut Cheats,
+    next_state: &mut NextState<LiveState>,
+    decision: Decision,
+) {
+    if current_level.advance(decision) {
+        next_state.set(LiveState::LoadingLevel);
+    } else {
+        info!("No more levels ahead");
+    }
+    cheats.used_cheats = true;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use crate::live::LevelId;
+
+    use super::*;
+
+    fn type_text(world: &mut World, text: &str) {
+        for c in text.chars() {
+            world.send_event(KeyboardInput {
+                key_code: KeyCode::KeyA,
+                logical_key: Key::Character(c.to_string().into()),
+                state: ButtonState::Pressed,
+                window: Entity::PLACEHOLDER,
+            });
+        }
+        world.run_system_once(cheat_input);
+    }
+
+    #[test]
+    fn cheating_past_the_last_level_does_not_panic() {
+        let mut world = World::new();
+        world.init_resource::<TextBuffer>();
+        world.init_resource::<CurrentLevel>();
+        world.init_resource::<Cheats>();
+        world.init_resource::<NextState<LiveState>>();
+        world.init_resource::<Events<KeyboardInput>>();
+        world.insert_resource(State::new(AppState::Live));
+
+        for _ in 0..10 {
+            type_text(&mut world, "thisisdownrightridiculous");
+            type_text(&mut world, "nothingleftforme");
+        }
+
+        let level = world.resource::<CurrentLevel>().id;
+        // stuck at the last stage, which has no stage after it
+        assert!(level.next(Decision::Left).is_none());
+        assert!(LevelId::new(level.stage + 1, 0).is_none());
+        assert!(world.resource::<Cheats>().used_cheats);
     }
 }
@@ -0,0 +1,95 @@
+//! Module for restricting the numbers which appear in the game,
+//! so that educators can focus on specific skills.
+//!
+//! Filters are applied on top of the built-in levels:
+//! spawner target options which are not allowed are left out,
+//! and so are weapon cubes.
+//! A wave left without any allowed number does not take place.
+use bevy::prelude::*;
+
+use crate::logic::Num;
+
+/// A preset of allowed numbers
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum NumberPreset {
+    /// All numbers in the levels
+    #[default]
+    All,
+    /// Only whole numbers
+    IntegersOnly,
+    /// Only targets which are fractions,
+    /// with any weapon available
+    Fractions,
+    /// Small whole numbers and simple fractions
+    SmallNumbers,
+}
+
+impl NumberPreset {
+    /// the maximum numerator allowed with the small numbers preset
+    const SMALL_MAX: i16 = 20;
+    /// the maximum denominator allowed with the small numbers preset
+    const SMALL_MAX_DENOM: i16 = 4;
+
+    /// The next preset, for cycling through them in the settings
+    pub fn next(self) -> Self {
+        match self {
+            NumberPreset::All => NumberPreset::IntegersOnly,
+            NumberPreset::IntegersOnly => NumberPreset::Fractions,
+            NumberPreset::Fractions => NumberPreset::SmallNumbers,
+            NumberPreset::SmallNumbers => NumberPreset::All,
+        }
+    }
+
+    /// A short name for the preset
+    pub fn name(self) -> &'static str {
+        match self {
+            NumberPreset::All => "All",
+            NumberPreset::IntegersOnly => "Integers Only",
+            NumberPreset::Fractions => "Fractions",
+            NumberPreset::SmallNumbers => "Small Numbers",
+        }
+    }
+
+    /// Whether a mob spawner may use this number for its targets
+    pub fn allows_target(self, num: Num) -> bool {
+        let num = num.reduced();
+        match self {
+            NumberPreset::All => true,
+            NumberPreset::IntegersOnly => num.is_integer(),
+            NumberPreset::Fractions => !num.is_integer(),
+            NumberPreset::SmallNumbers => Self::is_small(num),
+        }
+    }
+
+    /// Whether a weapon cube with this number may appear
+    pub fn allows_weapon(self, num: Num) -> bool {
+        let num = num.reduced();
+        match self {
+            NumberPreset::All | NumberPreset::Fractions => true,
+            NumberPreset::IntegersOnly => num.is_integer(),
+            NumberPreset::SmallNumbers => Self::is_small(num),
+        }
+    }
+
+    fn is_small(num: Num) -> bool {
+        num.numer().abs() <= Self::SMALL_MAX && *num.denom() <= Self::SMALL_MAX_DENOM
+    }
+}
+
+/// Global resource for the number restrictions in place
+#[derive(Debug, Default, Resource)]
+pub struct EducatorConfig {
+    pub preset: NumberPreset,
+}
+
+impl EducatorConfig {
+    #[inline]
+    pub fn allows_target(&self, num: Num) -> bool {
+        self.preset.allows_target(num)
+    }
+
+    #[inline]
+    pub fn allows_weapon(&self, num: Num) -> bool {
+        self.preset.allows_weapon(num)
+    }
+}
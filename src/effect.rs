@@ -51,13 +51,16 @@ pub fn apply_glimmer(time: Res<Time>, mut q: Query<(&mut PointLight, &Glimmers)>
 
 /// Component for entities that wobble a bit.
 ///
-/// Entities with this component will always wobble around the origin position.
-/// Position them first at the parent entity,
-/// then attach entities with this component as children.
+/// The wobble is applied as an offset on top of the entity's translation,
+/// so it composes with other systems moving the entity.
 #[derive(Debug, Component)]
 pub struct Wobbles {
     pub amplitude: f32,
     pub frequency: Vec2,
+    /// time offset so that many entities do not wobble in unison
+    pub phase: f32,
+    /// the wobble offset currently applied to the translation
+    pub offset: Vec3,
 }
 
 impl Default for Wobbles {
@@ -65,19 +68,119 @@ impl Default for Wobbles {
         Self {
             amplitude: 0.075,
             frequency: Vec2::new(0.66, 1.),
+            phase: 0.,
+            offset: Vec3::ZERO,
         }
     }
 }
 
-pub fn apply_wobble(time: Res<LiveTime>, mut q: Query<(&mut Transform, &Wobbles)>) {
+pub fn apply_wobble(time: Res<LiveTime>, mut q: Query<(&mut Transform, &mut Wobbles)>) {
     let time = time.elapsed_seconds();
-    for (mut transform, wobble) in q.iter_mut() {
+    for (mut transform, mut wobble) in q.iter_mut() {
+        let time = time + wobble.phase;
         let offset = Vec3::new(
             wobble.amplitude * (wobble.frequency.x * time).sin(),
             wobble.amplitude * (wobble.frequency.y * time).cos(),
             0.0,
         );
-        *transform = Transform::from_translation(offset);
+        // replace the previous offset with the new one
+        transform.translation += offset - wobble.offset;
+        wobble.offset = offset;
+    }
+}
+
+/// Component for a node which shakes with the trauma it receives,
+/// such as the camera when the player takes damage.
+/// It can also be kicked back briefly, as in recoil.
+///
+/// The shake takes over the whole transform of the entity,
+/// so it should be a dedicated node in the hierarchy
+/// (independent from [`Wobbles`]).
+/// The transform returns exactly to rest once the trauma is gone.
+#[derive(Debug, Default, Component)]
+pub struct CameraShake {
+    /// how much the node is shaking, from 0 to 1
+    pub trauma: f32,
+    /// how far the node is kicked back, from 0 to 1
+    pub kick: f32,
+    /// how strong the shake and kick are at full trauma
+    /// (0 disables them)
+    pub magnitude: f32,
+}
+
+impl CameraShake {
+    /// trauma lost per second
+    const DECAY: f32 = 1.25;
+    /// maximum translation at full trauma and magnitude
+    const MAX_OFFSET: f32 = 0.12;
+    /// maximum roll (in radians) at full trauma and magnitude
+    const MAX_ROLL: f32 = 0.06;
+    /// kick lost per second
+    const KICK_RECOVERY: f32 = 6.;
+    /// how far back the node goes at full kick and magnitude
+    const MAX_KICK: f32 = 0.08;
+    /// the available magnitudes in the settings
+    const MAGNITUDE_STEPS: [f32; 3] = [0., 0.5, 1.];
+
+    pub fn new(magnitude: f32) -> Self {
+        Self {
+            trauma: 0.,
+            kick: 0.,
+            magnitude,
+        }
+    }
+
+    /// shake the node some more (up to full trauma)
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.);
+    }
+
+    /// kick the node back some more (up to full kick)
+    pub fn add_kick(&mut self, amount: f32) {
+        self.kick = (self.kick + amount).min(1.);
+    }
+
+    /// the magnitude after the given one,
+    /// going back to no shake after the strongest
+    pub fn next_magnitude(magnitude: f32) -> f32 {
+        Self::MAGNITUDE_STEPS
+            .into_iter()
+            .find(|step| *step > magnitude)
+            .unwrap_or(0.)
+    }
+}
+
+/// cheap smooth noise from -1 to 1 for the camera shake,
+/// with a different pattern for each seed
+fn shake_noise(time: f32, seed: f32) -> f32 {
+    ((time * 31. + seed * 7.3).sin() + (time * 47. + seed * 3.1).sin() * 0.5) / 1.5
+}
+
+/// system that shakes and kicks back the entities with `CameraShake`
+/// and lets their trauma and kick decay over time
+pub fn apply_camera_shake(time: Res<Time>, mut q: Query<(&mut Transform, &mut CameraShake)>) {
+    let delta = time.delta_seconds();
+    let elapsed = time.elapsed_seconds();
+    for (mut transform, mut shake) in q.iter_mut() {
+        shake.trauma = (shake.trauma - delta * CameraShake::DECAY).max(0.);
+        shake.kick = (shake.kick - delta * CameraShake::KICK_RECOVERY).max(0.);
+        let strength = shake.trauma * shake.trauma * shake.magnitude;
+        // ease back into place
+        let kick = shake.kick * shake.kick * shake.magnitude;
+        if strength == 0. && kick == 0. {
+            // back to rest (without triggering change detection every frame)
+            if *transform != Transform::IDENTITY {
+                *transform = Transform::IDENTITY;
+            }
+            continue;
+        }
+        let offset = Vec3::new(shake_noise(elapsed, 0.), shake_noise(elapsed, 1.), 0.)
+            * CameraShake::MAX_OFFSET
+            * strength
+            // the camera looks towards +Z, so back is -Z
+            - Vec3::Z * CameraShake::MAX_KICK * kick;
+        let roll = shake_noise(elapsed, 2.) * CameraShake::MAX_ROLL * strength;
+        *transform = Transform::from_translation(offset).with_rotation(Quat::from_rotation_z(roll));
     }
 }
 
@@ -124,22 +227,78 @@ pub fn time_to_live(time: Res<Time>, mut cmd: Commands, mut q: Query<(Entity, &m
 }
 
 /// Component to make something fade away
-/// (reduces opacity to 0 over time)
-#[derive(Debug, Default, Component)]
-pub struct FadesAway;
+/// (reduces opacity to 0 over time).
+///
+/// Works on mesh entities with a `StandardMaterial`
+/// (see [`fade_material`])
+/// and on UI nodes, text and images (see [`fade_ui`]).
+#[derive(Debug, Component)]
+pub struct FadesAway {
+    /// how much opacity is lost per second
+    pub rate: f32,
+}
+
+impl Default for FadesAway {
+    fn default() -> Self {
+        Self { rate: 1.5 }
+    }
+}
 
-pub fn fade_away(
+/// system that fades out the material of entities with `FadesAway`,
+/// both in opacity and in emissive strength.
+///
+/// Emissive colors may go well beyond 1 (HDR),
+/// so they are scaled down in proportion to the opacity
+/// and reach zero at the same time.
+pub fn fade_material(
     time: Res<Time>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    mut q: Query<&Handle<StandardMaterial>, With<FadesAway>>,
+    q: Query<(&Handle<StandardMaterial>, &FadesAway)>,
 ) {
     let delta = time.delta_seconds();
-    for material in q.iter_mut() {
+    for (material, fades) in q.iter() {
         let Some(material) = materials.get_mut(material.id()) else {
-            return;
+            continue;
         };
-        let new_alpha = (material.base_color.alpha() - delta * 1.5).max(0.);
+        let alpha = material.base_color.alpha();
+        let new_alpha = (alpha - delta * fades.rate).max(0.);
         material.base_color.set_alpha(new_alpha);
+        let ratio = if alpha > 0. { new_alpha / alpha } else { 0. };
+        let emissive = &mut material.emissive;
+        emissive.red *= ratio;
+        emissive.green *= ratio;
+        emissive.blue *= ratio;
+    }
+}
+
+/// system that fades out the UI elements of entities with `FadesAway`
+/// (background color, text and image)
+pub fn fade_ui(
+    time: Res<Time>,
+    mut q: Query<(
+        &FadesAway,
+        Option<&mut BackgroundColor>,
+        Option<&mut Text>,
+        Option<&mut UiImage>,
+    )>,
+) {
+    let delta = time.delta_seconds();
+    for (fades, background, text, image) in q.iter_mut() {
+        let step = delta * fades.rate;
+        if let Some(mut background) = background {
+            let new_alpha = (background.0.alpha() - step).max(0.);
+            background.0.set_alpha(new_alpha);
+        }
+        if let Some(mut text) = text {
+            for section in text.sections.iter_mut() {
+                let new_alpha = (section.style.color.alpha() - step).max(0.);
+                section.style.color.set_alpha(new_alpha);
+            }
+        }
+        if let Some(mut image) = image {
+            let new_alpha = (image.color.alpha() - step).max(0.);
+            image.color.set_alpha(new_alpha);
+        }
     }
 }
 
@@ -164,3 +323,134 @@ pub fn scale_up(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{f32::consts::TAU, time::Duration};
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn wobbling_entity_stays_centered_over_a_full_period() {
+        let mut world = World::new();
+        world.init_resource::<LiveTime>();
+        let base = Vec3::new(3., 4., 5.);
+        let wobbles = Wobbles {
+            amplitude: 0.2,
+            frequency: Vec2::new(1., 2.),
+            phase: 0.7,
+            ..default()
+        };
+        let amplitude = wobbles.amplitude;
+        let entity = world
+            .spawn((Transform::from_translation(base), wobbles))
+            .id();
+
+        // one full period of both frequencies
+        const STEPS: u32 = 360;
+        let mut sum = Vec3::ZERO;
+        for step in 1..=STEPS {
+            let elapsed = TAU * step as f32 / STEPS as f32;
+            world
+                .resource_mut::<LiveTime>()
+                .0
+                .set_elapsed(Duration::from_secs_f32(elapsed));
+            world.run_system_once(apply_wobble);
+
+            let translation = world.get::<Transform>(entity).unwrap().translation;
+            assert!(translation.distance(base) <= amplitude * 2_f32.sqrt() + 1e-4);
+            assert_eq!(translation.z, base.z);
+            sum += translation;
+        }
+        let center = sum / STEPS as f32;
+        assert!(
+            center.distance(base) < 1e-3,
+            "wobble drifted to {center} from {base}"
+        );
+    }
+
+    fn time_step(world: &mut World, seconds: f32) {
+        let mut time = Time::<()>::default();
+        time.advance_by(Duration::from_secs_f32(seconds));
+        world.insert_resource(time);
+    }
+
+    #[test]
+    fn camera_shake_decays_back_to_rest() {
+        let mut world = World::new();
+        let mut shake = CameraShake::new(1.);
+        shake.add_trauma(1.);
+        shake.add_kick(1.);
+        let camera = world.spawn((Transform::IDENTITY, shake)).id();
+
+        time_step(&mut world, 0.1);
+        world.run_system_once(apply_camera_shake);
+        assert_ne!(world.get::<Transform>(camera), Some(&Transform::IDENTITY));
+        assert!(world.get::<CameraShake>(camera).unwrap().trauma < 1.);
+
+        for _ in 0..20 {
+            time_step(&mut world, 0.1);
+            world.run_system_once(apply_camera_shake);
+        }
+        let shake = world.get::<CameraShake>(camera).unwrap();
+        assert_eq!(shake.trauma, 0.);
+        assert_eq!(shake.kick, 0.);
+        assert_eq!(world.get::<Transform>(camera), Some(&Transform::IDENTITY));
+    }
+
+    #[test]
+    fn emissive_fades_with_opacity_down_to_zero() {
+        let mut world = World::new();
+        world.init_resource::<Assets<StandardMaterial>>();
+        let material = world
+            .resource_mut::<Assets<StandardMaterial>>()
+            .add(StandardMaterial {
+                base_color: Color::WHITE,
+                emissive: LinearRgba::rgb(8., 4., 0.),
+                ..default()
+            });
+        world.spawn((material.clone(), FadesAway { rate: 2. }));
+
+        // a quarter of the way there
+        time_step(&mut world, 0.125);
+        world.run_system_once(fade_material);
+        let materials = world.resource::<Assets<StandardMaterial>>();
+        let m = materials.get(&material).unwrap();
+        assert!((m.base_color.alpha() - 0.75).abs() < 1e-5);
+        assert!((m.emissive.red - 6.).abs() < 1e-4);
+        assert!((m.emissive.green - 3.).abs() < 1e-4);
+
+        // well past the end
+        time_step(&mut world, 1.);
+        world.run_system_once(fade_material);
+        let materials = world.resource::<Assets<StandardMaterial>>();
+        let m = materials.get(&material).unwrap();
+        assert_eq!(m.base_color.alpha(), 0.);
+        assert_eq!(m.emissive.red, 0.);
+        assert_eq!(m.emissive.green, 0.);
+        assert_eq!(m.emissive.blue, 0.);
+    }
+
+    #[test]
+    fn ui_text_fades_away() {
+        let mut world = World::new();
+        let entity = world
+            .spawn((
+                Text::from_section("-1", TextStyle::default()),
+                FadesAway { rate: 1. },
+            ))
+            .id();
+
+        time_step(&mut world, 0.5);
+        world.run_system_once(fade_ui);
+        let text = world.get::<Text>(entity).unwrap();
+        assert!((text.sections[0].style.color.alpha() - 0.5).abs() < 1e-5);
+
+        world.run_system_once(fade_ui);
+        world.run_system_once(fade_ui);
+        let text = world.get::<Text>(entity).unwrap();
+        assert_eq!(text.sections[0].style.color.alpha(), 0.);
+    }
+}
@@ -0,0 +1,249 @@
+//! Achievements,
+//! notable feats which the player can accomplish,
+//! kept across game sessions.
+use bevy::prelude::*;
+
+use crate::{
+    cheat::Cheats,
+    logic::{AttackTest, Num},
+    persist::PersistentData,
+    ui::Toasts,
+};
+
+use super::{
+    memory::MemoryTarget,
+    mob::{Decoy, MobSpawner},
+    player::{AttackResolved, Player},
+    Health, Target,
+};
+
+/// A notable feat
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Achievement {
+    /// clear a wave without a single failed attack
+    FlawlessWave,
+    /// divide a number of 100 or more
+    BigDivider,
+    /// clear a wave with only 1 health left
+    LastStand,
+    /// discover every ending
+    AllEndings,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 4] = [
+        Achievement::FlawlessWave,
+        Achievement::BigDivider,
+        Achievement::LastStand,
+        Achievement::AllEndings,
+    ];
+
+    /// the key for persisting the achievements unlocked
+    const UNLOCKED_KEY: &'static str = "achievements";
+
+    /// identifier of the achievement in persisted data
+    fn key(self) -> &'static str {
+        match self {
+            Achievement::FlawlessWave => "flawless_wave",
+            Achievement::BigDivider => "big_divider",
+            Achievement::LastStand => "last_stand",
+            Achievement::AllEndings => "all_endings",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Achievement::FlawlessWave => "Flawless",
+            Achievement::BigDivider => "Big Divider",
+            Achievement::LastStand => "Last Stand",
+            Achievement::AllEndings => "Explorer",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Achievement::FlawlessWave => "clear a wave without missing",
+            Achievement::BigDivider => "divide a number of 100 or more",
+            Achievement::LastStand => "clear a wave with 1 health left",
+            Achievement::AllEndings => "reach every ending",
+        }
+    }
+
+    /// The achievements which the player has unlocked so far
+    pub fn unlocked(data: &PersistentData) -> Vec<Achievement> {
+        let keys = data.get(Self::UNLOCKED_KEY).unwrap_or_default();
+        Self::ALL
+            .into_iter()
+            .filter(|achievement| keys.split(',').any(|key| key == achievement.key()))
+            .collect()
+    }
+
+    /// Record that this achievement was unlocked,
+    /// returning whether it was not unlocked before
+    pub fn record_unlocked(self, data: &mut PersistentData) -> bool {
+        let mut unlocked = Self::unlocked(data);
+        if unlocked.contains(&self) {
+            return false;
+        }
+        unlocked.push(self);
+        let keys: Vec<_> = Self::ALL
+            .into_iter()
+            .filter(|achievement| unlocked.contains(achievement))
+            .map(Achievement::key)
+            .collect();
+        data.set(Self::UNLOCKED_KEY, keys.join(","));
+        data.save();
+        true
+    }
+
+    /// Record that this achievement was unlocked
+    /// and let the player know with a toast,
+    /// unless it was unlocked before
+    pub fn unlock(self, data: &mut PersistentData, toasts: &mut Toasts) {
+        if !self.record_unlocked(data) {
+            return;
+        }
+        info!("Achievement unlocked: {}", self.name());
+        toasts.push_toast(
+            format!(
+                "Achievement unlocked: {}\n{}",
+                self.name(),
+                self.description()
+            ),
+            3.,
+        );
+    }
+}
+
+/// Event for an achievement accomplished by the player
+#[derive(Debug, Event)]
+pub struct UnlockAchievement(pub Achievement);
+
+/// Resource keeping track of the wave being fought,
+/// for the achievements which depend on how a wave went
+#[derive(Debug, Default, Resource)]
+pub struct AchievementTracker {
+    /// whether a wave is in progress
+    in_wave: bool,
+    /// whether any attack failed during the current wave
+    missed_in_wave: bool,
+}
+
+impl AchievementTracker {
+    pub fn reset(&mut self) {
+        *self = AchievementTracker::default();
+    }
+}
+
+/// system that looks for achievements in the attacks made
+pub fn track_attack_achievements(
+    mut events: EventReader<AttackResolved>,
+    mut tracker: ResMut<AchievementTracker>,
+    mut unlock_events: EventWriter<UnlockAchievement>,
+) {
+    for AttackResolved { result, attack, .. } in events.read() {
+        match result {
+            AttackTest::Failed | AttackTest::Overshot(_) => {
+                tracker.missed_in_wave = true;
+            }
+            AttackTest::Effective(Some(new_num)) if *new_num * *attack >= Num::from(100) => {
+                unlock_events.send(UnlockAchievement(Achievement::BigDivider));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// system that looks for achievements once a wave is cleared
+pub fn track_wave_achievements(
+    mut tracker: ResMut<AchievementTracker>,
+    spawner_q: Query<&MobSpawner>,
+    target_q: Query<(), (With<Target>, Without<Decoy>, Without<MemoryTarget>)>,
+    player_q: Query<&Health, With<Player>>,
+    mut unlock_events: EventWriter<UnlockAchievement>,
+) {
+    let wave_in_progress = spawner_q.iter().any(|spawner| spawner.active) || !target_q.is_empty();
+    if wave_in_progress {
+        tracker.in_wave = true;
+        return;
+    }
+    if !tracker.in_wave {
+        return;
+    }
+
+    // the wave was just cleared
+    if !tracker.missed_in_wave {
+        unlock_events.send(UnlockAchievement(Achievement::FlawlessWave));
+    }
+    if let Ok(health) = player_q.get_single() {
+        if health.value <= 1. {
+            unlock_events.send(UnlockAchievement(Achievement::LastStand));
+        }
+    }
+    tracker.reset();
+}
+
+/// system that records new achievements and lets the player know
+pub fn process_unlock_achievement(
+    mut events: EventReader<UnlockAchievement>,
+    mut persistent_data: ResMut<PersistentData>,
+    cheats: Res<Cheats>,
+    mut toasts: ResMut<Toasts>,
+) {
+    for UnlockAchievement(achievement) in events.read() {
+        // only feats accomplished fairly count
+        if cheats.used_cheats {
+            continue;
+        }
+        achievement.unlock(&mut persistent_data, &mut toasts);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::{
+        assets::DefaultFont,
+        ui::{show_toasts, Toast},
+    };
+
+    fn toasts_after_unlocking(world: &mut World, achievements: &[Achievement]) -> usize {
+        for &achievement in achievements {
+            world.send_event(UnlockAchievement(achievement));
+        }
+        world.run_system_once(process_unlock_achievement);
+        world.run_system_once(show_toasts);
+        world.query::<&Toast>().iter(world).count()
+    }
+
+    #[test]
+    fn unlocked_achievements_are_shown_as_toasts() {
+        let mut world = World::new();
+        world.init_resource::<Events<UnlockAchievement>>();
+        world.init_resource::<PersistentData>();
+        world.init_resource::<Cheats>();
+        world.init_resource::<Toasts>();
+        world.insert_resource(DefaultFont(Handle::default()));
+
+        // one toast each, stacked rather than on top of each other
+        let unlocked = [Achievement::FlawlessWave, Achievement::LastStand];
+        assert_eq!(toasts_after_unlocking(&mut world, &unlocked), 2);
+        // not again once unlocked
+        assert_eq!(
+            toasts_after_unlocking(&mut world, &[Achievement::FlawlessWave]),
+            2
+        );
+        // nor when cheating
+        world.resource_mut::<Cheats>().used_cheats = true;
+        assert_eq!(
+            toasts_after_unlocking(&mut world, &[Achievement::BigDivider]),
+            2
+        );
+        assert_eq!(
+            Achievement::unlocked(world.resource::<PersistentData>()),
+            unlocked
+        );
+    }
+}
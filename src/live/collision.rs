@@ -1,5 +1,8 @@
 //! Simple collision components
-use bevy::{math::bounding::Aabb3d, prelude::*};
+use bevy::{
+    math::bounding::{Aabb3d, BoundingSphere},
+    prelude::*,
+};
 
 #[derive(Debug, Component)]
 pub struct CollidableBox {
@@ -22,3 +25,223 @@ impl Default for CollidableBox {
         Self::new(Vec3::new(1., 1., 1.))
     }
 }
+
+/// A broad phase for collision tests:
+/// bounding boxes are placed in slabs along the Z axis,
+/// so that only the ones near a position need to be tested.
+///
+/// Since the corridor extends along Z,
+/// most boxes are far away from any given projectile.
+/// Boxes spanning many slabs (such as the floor and walls)
+/// are kept apart and tested against everything.
+///
+/// The slabs keep their buffers between rebuilds,
+/// so that it can be kept around and refilled on every step.
+#[derive(Debug)]
+pub struct ZBins<T> {
+    /// the depth of each slab
+    slab: f32,
+    /// the items and their bounds, in insertion order
+    entries: Vec<(T, Aabb3d)>,
+    /// the slab of the first bin
+    first_slab: i32,
+    /// the indices of the entries in each slab,
+    /// starting from `first_slab`
+    bins: Vec<Vec<usize>>,
+    /// the indices of the entries which span too many slabs
+    long: Vec<usize>,
+}
+
+impl<T> ZBins<T> {
+    /// the default depth of each slab
+    pub const DEFAULT_SLAB: f32 = 4.;
+
+    /// the number of slabs from which a box is no longer binned
+    pub const MAX_SLABS: i32 = 4;
+
+    pub fn new(slab: f32) -> Self {
+        Self {
+            slab,
+            entries: Vec::new(),
+            first_slab: 0,
+            bins: Vec::new(),
+            long: Vec::new(),
+        }
+    }
+
+    fn slab_range(slab: f32, bound: &Aabb3d) -> (i32, i32) {
+        (
+            (bound.min.z / slab).floor() as i32,
+            (bound.max.z / slab).floor() as i32,
+        )
+    }
+
+    /// replace all entries with the ones given
+    pub fn rebuild(&mut self, iter: impl IntoIterator<Item = (T, Aabb3d)>) {
+        self.entries.clear();
+        self.long.clear();
+        for bin in &mut self.bins {
+            bin.clear();
+        }
+        self.entries.extend(iter);
+
+        // find out which slabs are needed
+        let mut range: Option<(i32, i32)> = None;
+        for (index, (_, bound)) in self.entries.iter().enumerate() {
+            let (lo, hi) = Self::slab_range(self.slab, bound);
+            if hi - lo >= Self::MAX_SLABS {
+                self.long.push(index);
+                continue;
+            }
+            range = Some(match range {
+                Some((min, max)) => (min.min(lo), max.max(hi)),
+                None => (lo, hi),
+            });
+        }
+        let Some((min, max)) = range else {
+            return;
+        };
+        self.first_slab = min;
+        let len = (max - min + 1) as usize;
+        if self.bins.len() < len {
+            self.bins.resize_with(len, Vec::new);
+        }
+
+        for (index, (_, bound)) in self.entries.iter().enumerate() {
+            let (lo, hi) = Self::slab_range(self.slab, bound);
+            if hi - lo >= Self::MAX_SLABS {
+                continue;
+            }
+            for slab in lo..=hi {
+                self.bins[(slab - min) as usize].push(index);
+            }
+        }
+    }
+
+    /// the items whose bounds may intersect the given sphere,
+    /// in the order in which they were inserted
+    pub fn candidates(&self, sphere: &BoundingSphere) -> impl Iterator<Item = &(T, Aabb3d)> {
+        let radius = sphere.radius();
+        let (lo, hi) =
+            Self::slab_range(self.slab, &Aabb3d::new(sphere.center, Vec3::splat(radius)));
+        let lo = (lo - self.first_slab).max(0);
+        let hi = (hi - self.first_slab).min(self.bins.len() as i32 - 1);
+        let mut indices: Vec<usize> = self.long.clone();
+        if lo <= hi {
+            indices.extend(
+                self.bins[lo as usize..=hi as usize]
+                    .iter()
+                    .flatten()
+                    .copied(),
+            );
+        }
+        indices.sort_unstable();
+        indices.dedup();
+        indices.into_iter().map(|index| &self.entries[index])
+    }
+}
+
+impl<T> Default for ZBins<T> {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_SLAB)
+    }
+}
+
+impl<T> FromIterator<(T, Aabb3d)> for ZBins<T> {
+    fn from_iter<I: IntoIterator<Item = (T, Aabb3d)>>(iter: I) -> Self {
+        let mut bins = ZBins::default();
+        bins.rebuild(iter);
+        bins
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::bounding::IntersectsVolume;
+    use tinyrand::{Rand, Seeded, SplitMix};
+
+    use super::*;
+
+    fn random(rng: &mut SplitMix, min: f32, max: f32) -> f32 {
+        min + (rng.next_u32() as f32 / u32::MAX as f32) * (max - min)
+    }
+
+    /// the first box hit by the sphere, testing every box
+    fn naive_hit(boxes: &[(usize, Aabb3d)], sphere: &BoundingSphere) -> Option<usize> {
+        boxes
+            .iter()
+            .find(|(_, bound)| bound.intersects(sphere))
+            .map(|(index, _)| *index)
+    }
+
+    /// the first box hit by the sphere, using the bins
+    fn binned_hit(bins: &ZBins<usize>, sphere: &BoundingSphere) -> Option<usize> {
+        bins.candidates(sphere)
+            .find(|(_, bound)| bound.intersects(sphere))
+            .map(|(index, _)| *index)
+    }
+
+    #[test]
+    fn bins_hit_the_same_boxes_as_testing_all_of_them() {
+        let mut rng = SplitMix::seed(0x5eed);
+        let mut bins = ZBins::default();
+
+        for round in 0..20 {
+            // a corridor with a long floor and walls,
+            // plus a bunch of mobs of different sizes
+            let mut boxes = vec![
+                (
+                    0,
+                    Aabb3d::new(Vec3::new(0., -1., -100.), Vec3::new(5., 0.5, 100.)),
+                ),
+                (
+                    1,
+                    Aabb3d::new(Vec3::new(-5., 1., -100.), Vec3::new(0.5, 2., 100.)),
+                ),
+                (
+                    2,
+                    Aabb3d::new(Vec3::new(5., 1., -100.), Vec3::new(0.5, 2., 100.)),
+                ),
+            ];
+            for index in 3..100 + round * 10 {
+                let center = Vec3::new(
+                    random(&mut rng, -5., 5.),
+                    random(&mut rng, 0., 3.),
+                    random(&mut rng, -200., 0.),
+                );
+                let half = Vec3::splat(random(&mut rng, 0.1, 3.));
+                boxes.push((index, Aabb3d::new(center, half)));
+            }
+            // rebuilding reuses the bins from the previous round
+            bins.rebuild(boxes.iter().copied());
+
+            for _ in 0..500 {
+                let sphere = BoundingSphere::new(
+                    Vec3::new(
+                        random(&mut rng, -6., 6.),
+                        random(&mut rng, -2., 4.),
+                        random(&mut rng, -210., 10.),
+                    ),
+                    random(&mut rng, 0.05, 1.),
+                );
+                assert_eq!(binned_hit(&bins, &sphere), naive_hit(&boxes, &sphere));
+            }
+        }
+    }
+
+    #[test]
+    fn long_boxes_are_not_binned() {
+        let bins: ZBins<()> = [
+            (
+                (),
+                Aabb3d::new(Vec3::new(0., -1., -100.), Vec3::new(5., 0.5, 100.)),
+            ),
+            ((), Aabb3d::new(Vec3::new(0., 1., -10.), Vec3::splat(0.5))),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(bins.long, vec![0]);
+        assert!(bins.bins.iter().all(|bin| !bin.contains(&0)));
+        assert_eq!(bins.bins.iter().filter(|bin| bin.contains(&1)).count(), 1);
+    }
+}
@@ -0,0 +1,149 @@
+//! The hit stop,
+//! a brief slow-down of the game to give weight to strong hits.
+use bevy::prelude::*;
+
+use super::wheel::WeaponWheel;
+
+/// Resource for the hit stop currently in effect, if any.
+///
+/// The slow-down is applied to virtual time,
+/// so it does not count against the player's [`LiveTime`](super::LiveTime).
+#[derive(Debug, Default, Resource)]
+pub struct HitStop {
+    /// how much longer the hit stop lasts, in real seconds
+    pub remaining: f32,
+}
+
+impl HitStop {
+    /// how fast the game goes during a hit stop
+    const SPEED: f32 = 0.05;
+    /// how long a hit stop lasts when a robust target is destroyed
+    pub const ROBUST_TARGET: f32 = 0.12;
+    /// how long a hit stop lasts on a combo milestone
+    pub const COMBO_MILESTONE: f32 = 0.08;
+
+    /// end the hit stop right away, back at the usual game speed
+    pub fn reset(&mut self, time: &mut Time<Virtual>) {
+        self.remaining = 0.;
+        time.set_relative_speed(1.);
+    }
+
+    /// start a hit stop lasting at least the given number of seconds
+    pub fn trigger(&mut self, duration: f32) {
+        self.remaining = self.remaining.max(duration);
+    }
+}
+
+/// system that slows down the game while there is a hit stop,
+/// restoring the usual speed once it is over
+/// (leaving the game speed alone while the weapon wheel is open)
+pub fn apply_hit_stop(
+    real_time: Res<Time<Real>>,
+    mut time: ResMut<Time<Virtual>>,
+    mut hit_stop: ResMut<HitStop>,
+    wheel_q: Query<(), With<WeaponWheel>>,
+) {
+    if hit_stop.remaining <= 0. {
+        return;
+    }
+    hit_stop.remaining = (hit_stop.remaining - real_time.delta_seconds()).max(0.);
+    if !wheel_q.is_empty() {
+        return;
+    }
+    if hit_stop.remaining > 0. {
+        time.set_relative_speed(HitStop::SPEED);
+    } else {
+        time.set_relative_speed(1.);
+    }
+}
+
+/// system that ends the hit stop when the game stops running,
+/// so that it does not carry over to what comes next
+pub fn end_hit_stop(mut hit_stop: ResMut<HitStop>, mut time: ResMut<Time<Virtual>>) {
+    hit_stop.reset(&mut time);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{ecs::system::RunSystemOnce, state::app::StatesPlugin};
+
+    use super::*;
+    use crate::{live::LiveState, AppState};
+
+    fn real_time_step(world: &mut World, seconds: f32) {
+        world
+            .resource_mut::<Time<Real>>()
+            .advance_by(Duration::from_secs_f32(seconds));
+        world.run_system_once(apply_hit_stop);
+    }
+
+    #[test]
+    fn game_speed_returns_to_normal_after_a_hit_stop() {
+        let mut world = World::new();
+        world.init_resource::<Time<Real>>();
+        world.init_resource::<Time<Virtual>>();
+        world.init_resource::<HitStop>();
+
+        world
+            .resource_mut::<HitStop>()
+            .trigger(HitStop::ROBUST_TARGET);
+        real_time_step(&mut world, 0.05);
+        assert_eq!(
+            world.resource::<Time<Virtual>>().relative_speed(),
+            HitStop::SPEED
+        );
+
+        real_time_step(&mut world, 0.1);
+        assert_eq!(world.resource::<HitStop>().remaining, 0.);
+        assert_eq!(world.resource::<Time<Virtual>>().relative_speed(), 1.);
+    }
+
+    #[test]
+    fn leaving_the_game_during_a_hit_stop_restores_the_game_speed() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin))
+            .init_state::<AppState>()
+            .add_sub_state::<LiveState>()
+            .init_resource::<HitStop>()
+            // as in the live action plugin
+            .add_systems(Update, apply_hit_stop.run_if(in_state(LiveState::Running)))
+            .add_systems(OnExit(LiveState::Running), end_hit_stop);
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Live);
+        app.update();
+        app.world_mut()
+            .resource_mut::<NextState<LiveState>>()
+            .set(LiveState::Running);
+        app.update();
+
+        let speed = |app: &App| app.world().resource::<Time<Virtual>>().relative_speed();
+
+        // defeated in the middle of a hit stop
+        app.world_mut()
+            .resource_mut::<HitStop>()
+            .trigger(HitStop::ROBUST_TARGET);
+        app.update();
+        assert_eq!(speed(&app), HitStop::SPEED);
+        app.world_mut()
+            .resource_mut::<NextState<LiveState>>()
+            .set(LiveState::Defeat);
+        app.update();
+        assert_eq!(speed(&app), 1.);
+        assert_eq!(app.world().resource::<HitStop>().remaining, 0.);
+
+        // and resetting the game ends it as well
+        app.world_mut().resource_mut::<HitStop>().trigger(1.);
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .set_relative_speed(HitStop::SPEED);
+        app.world_mut()
+            .resource_scope(|world, mut hit_stop: Mut<HitStop>| {
+                hit_stop.reset(&mut world.resource_mut::<Time<Virtual>>());
+            });
+        assert_eq!(speed(&app), 1.);
+        assert_eq!(app.world().resource::<HitStop>().remaining, 0.);
+    }
+}
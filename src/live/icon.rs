@@ -1,15 +1,31 @@
-use bevy::{prelude::*, ui::FocusPolicy};
+use bevy::{prelude::*, ui::FocusPolicy, window::PrimaryWindow};
 use bevy_mod_picking::prelude::*;
 use bevy_ui_anchor::{AnchorTarget, AnchorUiNode, HorizontalAnchor, VerticalAnchor};
 
-use crate::{effect::TimeToLive, logic::Num};
+use crate::{
+    effect::{FadesAway, TimeToLive, Velocity},
+    logic::{
+        describe_progress, test_attack_on, AttackTest, Num, NumDisplay, NumberBase, TargetRule,
+    },
+    CameraMarker, GameSettings,
+};
 
-use super::{callback_on_click, player::Player, OnLive};
+use super::{
+    callback_on_click,
+    mob::Decoy,
+    player::Player,
+    weapon::{WeaponButton, WeaponSelected},
+    OnLive, Target,
+};
 
 /// Marker component for the UI node showing a number
 #[derive(Debug, Component)]
 pub struct IconNode;
 
+/// Component for the number currently shown in an icon
+#[derive(Debug, Component)]
+pub struct IconNum(Num);
+
 /// Reverse entity reference for entities with an icon attached
 #[derive(Debug, Component)]
 pub struct HasIcon(pub Entity);
@@ -34,8 +50,10 @@ pub fn clear_icons_of_destroyed_things(
 pub fn update_icon_opacity(
     player_q: Query<&Transform, With<Player>>,
     item_q: Query<(&Transform, &HasIcon)>,
-    mut icon_q: Query<(&mut BackgroundColor, &Children), With<IconNode>>,
+    mut icon_q: Query<&mut BackgroundColor, With<IconNode>>,
+    children_q: Query<&Children>,
     mut icon_text_q: Query<&mut Text>,
+    mut fraction_bar_q: Query<&mut BackgroundColor, (With<FractionBar>, Without<IconNode>)>,
 ) {
     let Ok(player_transform) = player_q.get_single() else {
         return;
@@ -54,31 +72,528 @@ pub fn update_icon_opacity(
 
         // get the icon node
         let icon_e = has_icon.0;
-        if let Ok((mut bg_color, children)) = icon_q.get_mut(icon_e) {
+        if let Ok(mut bg_color) = icon_q.get_mut(icon_e) {
             bg_color.0.set_alpha(opacity);
 
-            // get the text node
-            if let Ok(mut text) = icon_text_q.get_mut(children[0]) {
-                text.sections[0].style.color.set_alpha(opacity);
+            // update the nodes portraying the number
+            for descendant in children_q.iter_descendants(icon_e) {
+                if let Ok(mut text) = icon_text_q.get_mut(descendant) {
+                    for section in &mut text.sections {
+                        section.style.color.set_alpha(opacity);
+                    }
+                } else if let Ok(mut bar_color) = fraction_bar_q.get_mut(descendant) {
+                    bar_color.0.set_alpha(opacity);
+                }
+            }
+        }
+    }
+}
+
+/// Marker component for the text under the icon of a target
+/// showing the sum of the attacks so far
+/// (only for targets with the sum rule)
+/// or the factor pair being filled in
+/// (only for targets with the factor pair rule)
+#[derive(Debug, Component)]
+pub struct SumProgressText;
+
+/// system that shows the running sum of sum rule targets
+/// and the factor pair of factor pair rule targets
+/// under their icon
+pub fn update_sum_progress(
+    mut cmd: Commands,
+    game_settings: Res<GameSettings>,
+    target_q: Query<(&Target, &HasIcon), Changed<Target>>,
+    children_q: Query<&Children>,
+    mut text_q: Query<&mut Text, With<SumProgressText>>,
+) {
+    for (target, has_icon) in &target_q {
+        let num_style = NumStyle::from(&*game_settings);
+        let value = match target.rule {
+            TargetRule::SumTo if target.accumulated == Num::ZERO => String::new(),
+            TargetRule::SumTo => format!("sum: {}", num_style.format(target.accumulated)),
+            // the blanks to fill in
+            TargetRule::FactorPair if target.accumulated == Num::ZERO => "? x ?".to_string(),
+            TargetRule::FactorPair => format!("{} x ?", num_style.format(target.accumulated)),
+            _ => continue,
+        };
+
+        let existing = children_q
+            .get(has_icon.0)
+            .ok()
+            .and_then(|children| children.iter().find(|child| text_q.contains(**child)));
+        if let Some(text_entity) = existing {
+            if let Ok(mut text) = text_q.get_mut(*text_entity) {
+                if let Some(section) = text.sections.get_mut(0) {
+                    section.value = value;
+                }
             }
+        } else if !value.is_empty() {
+            let text_entity = cmd
+                .spawn((
+                    SumProgressText,
+                    Pickable::IGNORE,
+                    TextBundle {
+                        text: Text::from_section(
+                            value,
+                            TextStyle {
+                                color: Color::srgb(0.4, 1., 0.4),
+                                font_size: 20.,
+                                ..default()
+                            },
+                        ),
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            top: Val::Percent(100.),
+                            ..default()
+                        },
+                        focus_policy: FocusPolicy::Pass,
+                        ..default()
+                    },
+                ))
+                .id();
+            cmd.entity(has_icon.0).add_child(text_entity);
+        }
+    }
+}
+
+/// Component for icon nodes which are flashing red,
+/// usually because of a failed attack on the target.
+#[derive(Debug, Component)]
+pub struct IconFlash {
+    /// the time left until the flash is over, in seconds
+    pub remaining: f32,
+}
+
+impl Default for IconFlash {
+    fn default() -> Self {
+        Self { remaining: 0.5 }
+    }
+}
+
+/// system to make flashing icons fade back to their normal color
+pub fn update_icon_flash(
+    mut cmd: Commands,
+    time: Res<Time>,
+    mut icon_q: Query<(Entity, &mut BackgroundColor, &mut IconFlash), With<IconNode>>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, mut bg_color, mut flash) in &mut icon_q {
+        flash.remaining -= delta;
+        let alpha = bg_color.0.alpha();
+        if flash.remaining <= 0. {
+            bg_color.0 = Color::BLACK.with_alpha(alpha);
+            cmd.entity(entity).remove::<IconFlash>();
+        } else {
+            let red = (flash.remaining / IconFlash::default().remaining).min(1.);
+            bg_color.0 = Color::srgba(red * 0.85, 0., 0., alpha);
         }
     }
 }
 
+/// Spawn a short-lived text node next to the given entity,
+/// for giving immediate feedback to the player.
+pub fn spawn_feedback_text(
+    cmd: &mut Commands,
+    entity: Entity,
+    text: impl Into<String>,
+    color: Color,
+) -> Entity {
+    cmd.spawn((
+        OnLive,
+        Pickable::IGNORE,
+        TextBundle {
+            text: Text::from_section(
+                text,
+                TextStyle {
+                    color,
+                    font_size: 28.,
+                    ..default()
+                },
+            ),
+            focus_policy: FocusPolicy::Pass,
+            z_index: ZIndex::Global(-1),
+            ..default()
+        },
+        AnchorUiNode {
+            anchorwidth: HorizontalAnchor::Mid,
+            anchorheight: VerticalAnchor::Bottom,
+            target: AnchorTarget::Entity(entity),
+        },
+        TimeToLive(0.9),
+    ))
+    .id()
+}
+
+/// Component for a text which rises from a point in the world and fades out
+#[derive(Debug, Component)]
+pub struct FloatingText {
+    /// the invisible entity in the world which the text follows
+    anchor: Entity,
+    /// how long the text has been shown, in seconds
+    age: f32,
+}
+
+/// how long a floating text is shown, in seconds
+const FLOATING_TEXT_DURATION: f32 = 1.2;
+
+/// how fast a floating text rises, in world units per second
+const FLOATING_TEXT_SPEED: f32 = 1.5;
+
+/// spawn a text which rises from the given position in the world
+/// and fades out
+pub fn spawn_floating_text(
+    cmd: &mut Commands,
+    position: Vec3,
+    text: impl Into<String>,
+    color: Color,
+) -> Entity {
+    let anchor = cmd
+        .spawn((
+            OnLive,
+            TransformBundle::from_transform(Transform::from_translation(position)),
+            Velocity(Vec3::Y * FLOATING_TEXT_SPEED),
+        ))
+        .id();
+    cmd.spawn((
+        OnLive,
+        Pickable::IGNORE,
+        FloatingText { anchor, age: 0. },
+        FadesAway {
+            rate: 1. / FLOATING_TEXT_DURATION,
+        },
+        TextBundle {
+            text: Text::from_section(
+                text,
+                TextStyle {
+                    color,
+                    font_size: 30.,
+                    ..default()
+                },
+            ),
+            focus_policy: FocusPolicy::Pass,
+            z_index: ZIndex::Global(-1),
+            ..default()
+        },
+        AnchorUiNode {
+            anchorwidth: HorizontalAnchor::Mid,
+            anchorheight: VerticalAnchor::Bottom,
+            target: AnchorTarget::Entity(anchor),
+        },
+    ))
+    .id()
+}
+
+/// system that takes down floating texts once they are done
+/// (the fading itself is done by [`FadesAway`])
+pub fn animate_floating_text(
+    mut cmd: Commands,
+    time: Res<Time>,
+    mut q: Query<(Entity, &mut FloatingText)>,
+) {
+    for (entity, mut floating_text) in &mut q {
+        floating_text.age += time.delta_seconds();
+        if floating_text.age >= FLOATING_TEXT_DURATION {
+            cmd.entity(entity).despawn_recursive();
+            cmd.entity(floating_text.anchor).despawn();
+        }
+    }
+}
+
+/// system to highlight the icon of the most urgent target,
+/// which is the one closest to the player
+/// (only if enabled in the settings)
+pub fn highlight_urgent_target(
+    game_settings: Res<GameSettings>,
+    player_q: Query<&Transform, With<Player>>,
+    target_q: Query<(&Transform, &HasIcon), (With<Target>, Without<Decoy>)>,
+    mut icon_q: Query<(&mut Style, &mut BorderColor), With<IconNode>>,
+) {
+    let Ok(player_transform) = player_q.get_single() else {
+        return;
+    };
+    let player_z = player_transform.translation.z;
+
+    // with the setting off, no icon is highlighted
+    // (clearing the highlight left from when it was on)
+    let urgent_icon = target_q
+        .iter()
+        .filter(|_| game_settings.highlight_urgent)
+        .map(|(transform, has_icon)| ((transform.translation.z - player_z).abs(), has_icon.0))
+        .min_by(|(d1, _), (d2, _)| d1.total_cmp(d2))
+        .map(|(_, icon)| icon);
+
+    for (_, has_icon) in &target_q {
+        let Ok((mut style, mut border_color)) = icon_q.get_mut(has_icon.0) else {
+            continue;
+        };
+        let border = if urgent_icon == Some(has_icon.0) {
+            UiRect::all(Val::Px(3.))
+        } else {
+            UiRect::all(Val::Px(0.))
+        };
+        // avoid triggering change detection every frame
+        if style.border != border {
+            style.border = border;
+            border_color.0 = Color::srgb(1., 0.85, 0.);
+        }
+    }
+}
+
+/// Component for the text node previewing
+/// what the selected weapon would do to the target under the cursor
+#[derive(Debug, Component)]
+pub struct AttackPreview {
+    /// the target being previewed
+    target: Entity,
+}
+
+/// system to show what the selected weapon would do
+/// to the target under the mouse cursor
+/// (only if enabled in the settings)
+pub fn update_attack_preview(
+    mut cmd: Commands,
+    game_settings: Res<GameSettings>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<CameraMarker>>,
+    target_q: Query<(Entity, &GlobalTransform, &Target)>,
+    selected_weapon_q: Query<&WeaponButton, With<WeaponSelected>>,
+    mut preview_q: Query<(Entity, &AttackPreview, &mut Text)>,
+) {
+    if !game_settings.preview_attack {
+        return;
+    }
+
+    // find the target under the cursor, if any
+    let cursor = window_q
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position());
+    let hovered =
+        cursor
+            .zip(camera_q.get_single().ok())
+            .and_then(|(cursor, (camera, camera_transform))| {
+                target_q
+                    .iter()
+                    .filter_map(|(entity, transform, target)| {
+                        let pos = transform.translation();
+                        let center = camera.world_to_viewport(camera_transform, pos)?;
+                        let edge =
+                            camera.world_to_viewport(camera_transform, pos + Vec3::X * 1.375)?;
+                        let distance = center.distance(cursor);
+                        (distance <= center.distance(edge)).then_some((distance, entity, target))
+                    })
+                    .min_by(|(d1, ..), (d2, ..)| d1.total_cmp(d2))
+                    .map(|(_, entity, target)| (entity, target))
+            });
+
+    // describe what the selected weapon would do
+    let preview =
+        hovered
+            .zip(selected_weapon_q.get_single().ok())
+            .map(|((entity, target), weapon)| {
+                let num_style = NumStyle::from(&*game_settings);
+                let text = match test_attack_on(target, weapon.num()) {
+                    AttackTest::Effective(Some(num)) => num_style.format(num),
+                    AttackTest::Effective(None) => "hit".to_string(),
+                    AttackTest::Progress(sum) => {
+                        describe_progress(target, sum, |num| num_style.format(num))
+                    }
+                    AttackTest::Failed if target.rule == TargetRule::Invulnerable => {
+                        "no effect".to_string()
+                    }
+                    AttackTest::Failed | AttackTest::Overshot(_) => "miss".to_string(),
+                };
+                (entity, text)
+            });
+
+    // reuse the existing preview if it still applies
+    let mut keep = false;
+    for (preview_entity, attack_preview, mut text) in &mut preview_q {
+        match &preview {
+            Some((entity, value)) if !keep && *entity == attack_preview.target => {
+                keep = true;
+                if let Some(section) = text.sections.get_mut(0) {
+                    if section.value != *value {
+                        section.value.clone_from(value);
+                    }
+                }
+            }
+            _ => {
+                cmd.entity(preview_entity).despawn_recursive();
+            }
+        }
+    }
+
+    if keep {
+        return;
+    }
+    let Some((entity, value)) = preview else {
+        return;
+    };
+    cmd.spawn((
+        OnLive,
+        AttackPreview { target: entity },
+        Pickable::IGNORE,
+        TextBundle {
+            text: Text::from_section(
+                value,
+                TextStyle {
+                    color: Color::srgb(1., 1., 0.75),
+                    font_size: 24.,
+                    ..default()
+                },
+            ),
+            focus_policy: FocusPolicy::Pass,
+            z_index: ZIndex::Global(-1),
+            ..default()
+        },
+        AnchorUiNode {
+            anchorwidth: HorizontalAnchor::Mid,
+            anchorheight: VerticalAnchor::Top,
+            target: AnchorTarget::Entity(entity),
+        },
+    ));
+}
+
+/// How numbers are portrayed in icons and weapon buttons
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct NumStyle {
+    /// whether to stack fractions over a horizontal bar
+    /// instead of using a slash
+    pub fraction_bar: bool,
+    /// the base in which whole numbers are written
+    pub base: NumberBase,
+    /// how fractions are written
+    pub display: NumDisplay,
+}
+
+impl From<&GameSettings> for NumStyle {
+    fn from(settings: &GameSettings) -> Self {
+        NumStyle {
+            fraction_bar: settings.fraction_bar,
+            base: settings.number_base,
+            display: settings.number_display,
+        }
+    }
+}
+
+impl NumStyle {
+    /// write the number as it should be shown to the player
+    /// (unless stacked)
+    pub fn format(&self, num: Num) -> String {
+        self.display
+            .format(num)
+            .unwrap_or_else(|| self.base.format(num))
+    }
+
+    /// whether the number would be portrayed as a stacked fraction
+    /// (decimals are never stacked)
+    pub fn is_stacked(&self, num: Num) -> bool {
+        self.fraction_bar && self.display == NumDisplay::Fraction && *num.denom() != 1
+    }
+}
+
+/// Marker component for the line separating
+/// the numerator and denominator of a stacked fraction
+#[derive(Debug, Component)]
+pub struct FractionBar;
+
+/// Spawn the nodes portraying a number,
+/// either as a single text or as a stacked fraction.
+pub fn spawn_num_text(
+    cmd: &mut ChildBuilder<'_>,
+    num: Num,
+    font_size: f32,
+    color: Color,
+    num_style: NumStyle,
+) {
+    let style = Style {
+        align_self: AlignSelf::Center,
+        margin: UiRect::all(Val::Auto),
+        ..default()
+    };
+
+    if !num_style.is_stacked(num) {
+        cmd.spawn((
+            TextBundle {
+                style,
+                text: Text::from_section(
+                    num_style.format(num),
+                    TextStyle {
+                        color,
+                        font_size,
+                        ..default()
+                    },
+                ),
+                ..default()
+            },
+            Pickable::IGNORE,
+        ));
+        return;
+    }
+
+    // numerator over a bar over the denominator
+    cmd.spawn((
+        NodeBundle {
+            style: Style {
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..style
+            },
+            focus_policy: FocusPolicy::Pass,
+            ..default()
+        },
+        Pickable::IGNORE,
+    ))
+    .with_children(|cmd| {
+        let text_style = TextStyle {
+            color,
+            font_size,
+            ..default()
+        };
+        cmd.spawn((
+            TextBundle::from_section(num.numer().to_string(), text_style.clone()),
+            Pickable::IGNORE,
+        ));
+        cmd.spawn((
+            FractionBar,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    min_width: Val::Px(font_size * 0.75),
+                    height: Val::Px((font_size / 12.).max(1.5)),
+                    ..default()
+                },
+                background_color: BackgroundColor(color),
+                focus_policy: FocusPolicy::Pass,
+                ..default()
+            },
+            Pickable::IGNORE,
+        ));
+        cmd.spawn((
+            TextBundle::from_section(num.denom().to_string(), text_style),
+            Pickable::IGNORE,
+        ));
+    });
+}
+
 /// Spawn a node that shows the target number on top of the target
-pub fn spawn_icon(cmd: &mut Commands, entity: Entity, num: Num, color: Color) -> Entity {
+pub fn spawn_icon(
+    cmd: &mut Commands,
+    entity: Entity,
+    num: Num,
+    color: Color,
+    num_style: NumStyle,
+) -> Entity {
     // draw a circle
-    let (icon_size, font_size) = if *num.denom() >= 10 {
-        (54., 26.)
-    } else if *num.denom() > 1 || num >= 100.into() {
-        (48., 28.)
-    } else {
-        (42., 34.)
-    };
+    let (icon_size, font_size) = icon_sizes(num, num_style);
     let icon = cmd
         .spawn((
             OnLive,
             IconNode,
+            IconNum(num),
             Pickable::IGNORE,
             NodeBundle {
                 style: Style {
@@ -103,25 +618,7 @@ pub fn spawn_icon(cmd: &mut Commands, entity: Entity, num: Num, color: Color) ->
         ))
         .with_children(|cmd| {
             // and draw the number in the circle
-            cmd.spawn((
-                TextBundle {
-                    style: Style {
-                        align_self: AlignSelf::Center,
-                        margin: UiRect::all(Val::Auto),
-                        ..default()
-                    },
-                    text: Text::from_section(
-                        num.to_string(),
-                        TextStyle {
-                            color,
-                            font_size,
-                            ..default()
-                        },
-                    ),
-                    ..default()
-                },
-                Pickable::IGNORE,
-            ));
+            spawn_num_text(cmd, num, font_size, color, num_style);
         })
         .id();
 
@@ -130,7 +627,168 @@ pub fn spawn_icon(cmd: &mut Commands, entity: Entity, num: Num, color: Color) ->
     icon
 }
 
+/// The size of the circle of an icon showing the given number,
+/// and the font size of the number
+fn icon_sizes(num: Num, num_style: NumStyle) -> (f32, f32) {
+    // the minus sign takes about as much room as a digit,
+    // but the circle is narrower towards the edges
+    let text_len = num_style.format(num).len() + usize::from(*num.numer() < 0);
+    if num_style.is_stacked(num) {
+        (58., 20.)
+    } else if text_len >= 6 {
+        // long binary numbers
+        (64., 18.)
+    } else if *num.denom() >= 10 || text_len >= 4 {
+        (54., 26.)
+    } else if *num.denom() > 1 || text_len >= 3 {
+        (48., 28.)
+    } else {
+        (42., 34.)
+    }
+}
+
+/// system that shows the new number of a target in its icon
+/// once it changes (such as when a robust mob is decomposed)
+pub fn update_target_icon_on_change(
+    mut cmd: Commands,
+    game_settings: Res<GameSettings>,
+    target_q: Query<(&Target, &HasIcon), Changed<Target>>,
+    mut icon_q: Query<(&mut IconNum, &mut Style, Option<&Children>), With<IconNode>>,
+    progress_text_q: Query<(), With<SumProgressText>>,
+) {
+    let num_style = NumStyle::from(&*game_settings);
+    for (target, has_icon) in &target_q {
+        let Ok((mut icon_num, mut style, children)) = icon_q.get_mut(has_icon.0) else {
+            continue;
+        };
+        if icon_num.0 == target.num {
+            // only the progress changed
+            continue;
+        }
+        icon_num.0 = target.num;
+
+        // replace the number, keeping the progress text
+        for child in children.into_iter().flatten() {
+            if !progress_text_q.contains(*child) {
+                cmd.entity(*child).despawn_recursive();
+            }
+        }
+        let (icon_size, font_size) = icon_sizes(target.num, num_style);
+        style.width = Val::Px(icon_size);
+        style.height = Val::Px(icon_size);
+        cmd.entity(has_icon.0).with_children(|cmd| {
+            spawn_num_text(cmd, target.num, font_size, Color::WHITE, num_style);
+        });
+    }
+}
+
 /// Spawn a node that shows the target number on top of the target
-pub fn spawn_target_icon(cmd: &mut Commands, entity: Entity, num: Num) -> Entity {
-    spawn_icon(cmd, entity, num, Color::WHITE)
+pub fn spawn_target_icon(
+    cmd: &mut Commands,
+    entity: Entity,
+    num: Num,
+    num_style: NumStyle,
+) -> Entity {
+    spawn_icon(cmd, entity, num, Color::WHITE, num_style)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    fn spawn_target_with_icon(world: &mut World, z: f32) -> Entity {
+        let icon = world.spawn((IconNode, NodeBundle::default())).id();
+        world.spawn((
+            Target::default(),
+            Transform::from_xyz(0., 0., z),
+            HasIcon(icon),
+        ));
+        icon
+    }
+
+    fn border(world: &World, icon: Entity) -> UiRect {
+        world.get::<Style>(icon).unwrap().border
+    }
+
+    fn sum_progress_text(world: &mut World) -> Vec<String> {
+        world
+            .query_filtered::<&Text, With<SumProgressText>>()
+            .iter(world)
+            .map(|text| text.sections[0].value.clone())
+            .collect()
+    }
+
+    fn sum_progress_text_entity(world: &mut World) -> Entity {
+        world
+            .query_filtered::<Entity, With<SumProgressText>>()
+            .single(world)
+    }
+
+    #[test]
+    fn minus_sign_makes_room_in_the_icon() {
+        let style = NumStyle::default();
+        let (size_12, _) = icon_sizes(12.into(), style);
+        let (size_minus_12, font_minus_12) = icon_sizes((-12).into(), style);
+        let (size_123, font_123) = icon_sizes(123.into(), style);
+        assert!(size_minus_12 > size_12);
+        assert!(size_minus_12 >= size_123);
+        assert!(font_minus_12 <= font_123);
+        // a negative single digit fits like two digits
+        assert_eq!(icon_sizes((-3).into(), style), icon_sizes(42.into(), style));
+    }
+
+    #[test]
+    fn running_sum_is_shown_under_the_icon() {
+        let mut world = World::new();
+        world.init_resource::<GameSettings>();
+        let icon = world.spawn((IconNode, NodeBundle::default())).id();
+        let target = world
+            .spawn((
+                Target {
+                    num: 10.into(),
+                    rule: TargetRule::SumTo,
+                    ..default()
+                },
+                HasIcon(icon),
+            ))
+            .id();
+
+        // nothing to show before the first hit
+        world.run_system_once(update_sum_progress);
+        assert!(sum_progress_text(&mut world).is_empty());
+
+        world.get_mut::<Target>(target).unwrap().accumulated = 3.into();
+        world.run_system_once(update_sum_progress);
+        assert_eq!(sum_progress_text(&mut world), ["sum: 3"]);
+        let text = sum_progress_text_entity(&mut world);
+        assert_eq!(world.get::<Parent>(text).unwrap().get(), icon);
+
+        // overshooting starts over
+        world.get_mut::<Target>(target).unwrap().accumulated = Num::ZERO;
+        world.run_system_once(update_sum_progress);
+        assert_eq!(sum_progress_text(&mut world), [""]);
+    }
+
+    #[test]
+    fn highlight_is_cleared_when_the_setting_is_turned_off() {
+        let mut world = World::new();
+        world.insert_resource(GameSettings {
+            highlight_urgent: true,
+            ..default()
+        });
+        world.spawn((Player, Transform::default()));
+        let near = spawn_target_with_icon(&mut world, -4.);
+        let far = spawn_target_with_icon(&mut world, -12.);
+
+        world.run_system_once(highlight_urgent_target);
+        assert_eq!(border(&world, near), UiRect::all(Val::Px(3.)));
+        assert_eq!(border(&world, far), UiRect::all(Val::Px(0.)));
+
+        world.resource_mut::<GameSettings>().highlight_urgent = false;
+        world.run_system_once(highlight_urgent_target);
+        assert_eq!(border(&world, near), UiRect::all(Val::Px(0.)));
+        assert_eq!(border(&world, far), UiRect::all(Val::Px(0.)));
+    }
 }
@@ -1,24 +1,127 @@
 //! Module for the interlude message mechanism,
 //! which is used to display story, explanations, and dialog to the player.
+use std::borrow::Cow;
 
 use bevy::prelude::*;
-
-use crate::{assets::DefaultFont, ui::Sizes, AppState, GameSettings};
-
-use super::{phase::PhaseTrigger, player::Player, LiveState, OnLive};
+use serde::{de::Error as _, Deserialize, Deserializer};
+
+use crate::{
+    assets::DefaultFont,
+    cheat::Cheats,
+    effect::{FadesAway, TimeToLive},
+    persist::PersistentData,
+    runcard::{spawn_run_card, RunCard},
+    ui::{spawn_button, Sizes, Toasts},
+    AppState, GameSettings,
+};
+
+use super::{
+    achievement::Achievement,
+    levels::{CurrentLevel, Ending},
+    phase::PhaseTrigger,
+    player::Player,
+    stats::{RunStats, RunSummary},
+    LiveState, LiveTime, OnLive,
+};
 
 /// Complete specification for an interlude,
 /// also serving as a marker for the interlude top UI node.
-#[derive(Debug, Clone, Component)]
+#[derive(Debug, Clone, Component, Deserialize)]
 pub struct InterludeSpec {
     /// the text message in the interlude
+    /// (in level files, either `Text("...")` or `File("1_1.txt")`)
+    #[serde(deserialize_with = "deserialize_message")]
     pub message: String,
     /// path to the image to present in this step
-    pub image: Option<&'static str>,
+    #[serde(default)]
+    pub image: Option<Cow<'static, str>>,
     /// what should happen when the player dismisses this interlude
+    #[serde(default)]
     pub effect: InterludeEffect,
 }
 
+/// The message of an interlude in a level file
+#[derive(Debug, Deserialize)]
+enum MessageSource {
+    /// the message itself
+    Text(String),
+    /// the name of a text file with the message,
+    /// either one of the built-in interludes
+    /// or a file next to the level files
+    File(String),
+}
+
+fn deserialize_message<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match MessageSource::deserialize(deserializer)? {
+        MessageSource::Text(text) => Ok(text),
+        MessageSource::File(name) => interlude_text(&name)
+            .ok_or_else(|| D::Error::custom(format!("no interlude text file {name}"))),
+    }
+}
+
+/// the built-in interlude texts, by file name
+const BUILTIN_TEXTS: &[(&str, &str)] = &[
+    ("1_1.txt", include_str!("interludes/1_1.txt")),
+    ("1_2.txt", include_str!("interludes/1_2.txt")),
+    ("2_1.txt", include_str!("interludes/2_1.txt")),
+    ("2_2.txt", include_str!("interludes/2_2.txt")),
+    ("3_1.txt", include_str!("interludes/3_1.txt")),
+    (
+        "z_bedroom_1.txt",
+        include_str!("interludes/z_bedroom_1.txt"),
+    ),
+    (
+        "z_bedroom_2.txt",
+        include_str!("interludes/z_bedroom_2.txt"),
+    ),
+    (
+        "z_bedroom_3.txt",
+        include_str!("interludes/z_bedroom_3.txt"),
+    ),
+    ("z_circle_1.txt", include_str!("interludes/z_circle_1.txt")),
+    ("z_circle_2.txt", include_str!("interludes/z_circle_2.txt")),
+    (
+        "z_dungeon_1.txt",
+        include_str!("interludes/z_dungeon_1.txt"),
+    ),
+    (
+        "z_dungeon_2.txt",
+        include_str!("interludes/z_dungeon_2.txt"),
+    ),
+    (
+        "z_dungeon_3.txt",
+        include_str!("interludes/z_dungeon_3.txt"),
+    ),
+    (
+        "z_dungeon_4.txt",
+        include_str!("interludes/z_dungeon_4.txt"),
+    ),
+    ("z_mirror_1.txt", include_str!("interludes/z_mirror_1.txt")),
+    ("z_mirror_2.txt", include_str!("interludes/z_mirror_2.txt")),
+    ("z_mirror_3.txt", include_str!("interludes/z_mirror_3.txt")),
+    ("z_mirror_4.txt", include_str!("interludes/z_mirror_4.txt")),
+    ("z_mirror_5.txt", include_str!("interludes/z_mirror_5.txt")),
+];
+
+/// The interlude text in the file of the given name,
+/// looking into the built-in interludes first.
+fn interlude_text(name: &str) -> Option<String> {
+    if let Some((_, text)) = BUILTIN_TEXTS.iter().find(|(file, _)| *file == name) {
+        return Some(text.to_string());
+    }
+    let path = super::levels::level_file_dir()?.join(name);
+    match std::fs::read_to_string(&path) {
+        Ok(text) => Some(text),
+        Err(e) => {
+            warn!("Could not read interlude text from {}: {e}", path.display());
+            None
+        }
+    }
+}
+
 impl InterludeSpec {
     pub fn from_sequence<I, T>(seq: I) -> Self
     where
@@ -48,12 +151,12 @@ impl InterludeSpec {
             (None, false) => Some(Self::new_single(message, image)),
             (None, true) => Some(Self {
                 message: message.into(),
-                image,
+                image: image.map(Cow::Borrowed),
                 effect: InterludeEffect::Exit,
             }),
             (Some(next), _) => Some(Self {
                 message: message.into(),
-                image,
+                image: image.map(Cow::Borrowed),
                 effect: InterludeEffect::Next(Box::new(next)),
             }),
         }
@@ -62,7 +165,7 @@ impl InterludeSpec {
     pub fn new_single(message: impl Into<String>, image: Option<&'static str>) -> Self {
         Self {
             message: message.into(),
-            image,
+            image: image.map(Cow::Borrowed),
             effect: InterludeEffect::Resume,
         }
     }
@@ -77,8 +180,40 @@ impl InterludeSpec {
     }
 }
 
+/// How interludes are presented to the player
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum InterludeMode {
+    /// shown until the player moves on
+    #[default]
+    Full,
+    /// shown, but moving on by themselves after a few seconds
+    /// (except for the ones ending the game)
+    Fast,
+    /// not shown at all
+    /// (except for the ones ending the game)
+    Skip,
+}
+
+impl InterludeMode {
+    pub fn next(self) -> Self {
+        match self {
+            InterludeMode::Full => InterludeMode::Fast,
+            InterludeMode::Fast => InterludeMode::Skip,
+            InterludeMode::Skip => InterludeMode::Full,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            InterludeMode::Full => "Full",
+            InterludeMode::Fast => "Fast",
+            InterludeMode::Skip => "Skip",
+        }
+    }
+}
+
 /// What happens after an interlude is advanced.
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Deserialize)]
 pub enum InterludeEffect {
     /// Show the next interlude
     Next(Box<InterludeSpec>),
@@ -96,8 +231,30 @@ pub struct InterludePiece;
 #[derive(Debug, Component)]
 pub struct FadeIn;
 
+/// Component for an interlude which moves on by itself
+/// once the timer finishes
+/// (see [`InterludeMode::Fast`])
+#[derive(Debug, Component)]
+pub struct AutoAdvance(pub Timer);
+
+impl AutoAdvance {
+    /// give the player some time to read the message,
+    /// depending on its length
+    fn for_message(message: &str) -> Self {
+        let secs = 2. + message.chars().count() as f32 / 40.;
+        AutoAdvance(Timer::from_seconds(secs, TimerMode::Once))
+    }
+}
+
+/// Marker component for an interlude which is already advancing,
+/// so that it is not advanced more than once
 #[derive(Debug, Component)]
-pub struct FadeOut;
+pub struct Advancing;
+
+/// Marker component for the button to save a run card,
+/// shown on the ending screen
+#[derive(Debug, Component)]
+pub struct SaveRunCardButton;
 
 pub fn spawn_interlude(
     cmd: &mut Commands,
@@ -105,119 +262,135 @@ pub fn spawn_interlude(
     default_font: &DefaultFont,
     sizes: &Sizes,
     asset_server: &AssetServer,
+    mode: InterludeMode,
 ) -> Entity {
     let message = spec.message.clone();
+    let auto_advance = (mode == InterludeMode::Fast && !spec.is_exit())
+        .then(|| AutoAdvance::for_message(&message));
+
+    let image = spec.image.as_deref().map(|path| asset_server.load(path));
 
-    let image = spec.image.map(|path| asset_server.load(path));
+    // the last interlude before going back to the menu is the ending screen
+    let is_ending_screen = matches!(spec.effect, InterludeEffect::Exit);
 
     let font = &default_font.0;
 
-    cmd.spawn((
-        OnLive,
-        spec,
-        NodeBundle {
-            style: Style {
-                display: Display::Flex,
-                flex_direction: FlexDirection::Column,
-                align_items: AlignItems::Center,
-                justify_content: JustifyContent::Center,
-                position_type: PositionType::Absolute,
-                width: Val::Percent(100.),
-                height: Val::Percent(100.),
-                padding: UiRect::axes(
-                    Val::Px(sizes.outer_padding_h),
-                    Val::Px(sizes.outer_padding_v),
-                ),
-                ..default()
-            },
-            background_color: Color::BLACK.into(),
-            border_color: Color::WHITE.into(),
-            border_radius: BorderRadius::all(Val::Px(2.)),
-            z_index: ZIndex::Global(9),
-            ..default()
-        },
-    ))
-    .with_children(|cmd| {
-        // inner node for the border
-        cmd.spawn((
-            InterludePiece,
+    let entity = cmd
+        .spawn((
+            OnLive,
+            spec,
             NodeBundle {
                 style: Style {
                     display: Display::Flex,
-                    flex_direction: FlexDirection::Row,
-                    border: UiRect::all(Val::Px(2.)),
-                    padding: UiRect {
-                        top: Val::Px(40.),
-                        bottom: Val::Px(20.),
-                        left: Val::Px(20.),
-                        right: Val::Px(20.),
-                    },
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    position_type: PositionType::Absolute,
                     width: Val::Percent(100.),
                     height: Val::Percent(100.),
+                    padding: UiRect::axes(
+                        Val::Px(sizes.outer_padding_h),
+                        Val::Px(sizes.outer_padding_v),
+                    ),
                     ..default()
                 },
-                border_color: BorderColor(Color::WHITE),
+                background_color: Color::BLACK.into(),
+                border_color: Color::WHITE.into(),
+                border_radius: BorderRadius::all(Val::Px(2.)),
+                z_index: ZIndex::Global(9),
                 ..default()
             },
         ))
         .with_children(|cmd| {
-            // if there is an image, add it
-            if let Some(image) = image {
+            // inner node for the border
+            cmd.spawn((
+                InterludePiece,
+                NodeBundle {
+                    style: Style {
+                        display: Display::Flex,
+                        flex_direction: FlexDirection::Row,
+                        border: UiRect::all(Val::Px(2.)),
+                        padding: UiRect {
+                            top: Val::Px(40.),
+                            bottom: Val::Px(20.),
+                            left: Val::Px(20.),
+                            right: Val::Px(20.),
+                        },
+                        width: Val::Percent(100.),
+                        height: Val::Percent(100.),
+                        ..default()
+                    },
+                    border_color: BorderColor(Color::WHITE),
+                    ..default()
+                },
+            ))
+            .with_children(|cmd| {
+                // if there is an image, add it
+                if let Some(image) = image {
+                    cmd.spawn((
+                        InterludePiece,
+                        FadeIn,
+                        ImageBundle {
+                            style: Style {
+                                margin: UiRect {
+                                    left: Val::Px(10.),
+                                    right: Val::Px(10.),
+                                    top: Val::Px(10.),
+                                    bottom: Val::Px(10.),
+                                    ..default()
+                                },
+                                max_height: Val::Percent(100.),
+                                max_width: Val::Percent(40.),
+                                ..default()
+                            },
+                            image: UiImage {
+                                // start invisible, will move up through a system
+                                color: Color::srgba(1., 1., 1., 0.125),
+                                texture: image,
+                                ..default()
+                            },
+                            ..default()
+                        },
+                    ));
+                }
+
+                // message node
                 cmd.spawn((
                     InterludePiece,
                     FadeIn,
-                    ImageBundle {
+                    TextBundle {
                         style: Style {
-                            margin: UiRect {
-                                left: Val::Px(10.),
-                                right: Val::Px(10.),
-                                top: Val::Px(10.),
-                                bottom: Val::Px(10.),
-                                ..default()
-                            },
-                            max_height: Val::Percent(100.),
-                            max_width: Val::Percent(40.),
+                            margin: UiRect::all(Val::Auto),
                             ..default()
                         },
-                        image: UiImage {
-                            // start invisible, will move up through a system
-                            color: Color::srgba(1., 1., 1., 0.125),
-                            texture: image,
-                            ..default()
+                        text: Text {
+                            justify: JustifyText::Center,
+                            linebreak_behavior: bevy::text::BreakLineOn::WordBoundary,
+                            sections: vec![TextSection {
+                                value: message.into(),
+                                style: TextStyle {
+                                    font: font.clone(),
+                                    font_size: sizes.interlude_font_size,
+                                    // start invisible, will move up through a system
+                                    color: Color::srgba(1., 1., 1., 0.125),
+                                },
+                            }],
                         },
                         ..default()
                     },
                 ));
+            });
+
+            if is_ending_screen {
+                spawn_button(cmd, sizes, font.clone(), "Save Run Card", SaveRunCardButton);
             }
+        })
+        .id();
 
-            // message node
-            cmd.spawn((
-                InterludePiece,
-                FadeIn,
-                TextBundle {
-                    style: Style {
-                        margin: UiRect::all(Val::Auto),
-                        ..default()
-                    },
-                    text: Text {
-                        justify: JustifyText::Center,
-                        linebreak_behavior: bevy::text::BreakLineOn::WordBoundary,
-                        sections: vec![TextSection {
-                            value: message.into(),
-                            style: TextStyle {
-                                font: font.clone(),
-                                font_size: sizes.interlude_font_size,
-                                // start invisible, will move up through a system
-                                color: Color::srgba(1., 1., 1., 0.125),
-                            },
-                        }],
-                    },
-                    ..default()
-                },
-            ));
-        });
-    })
-    .id()
+    if let Some(auto_advance) = auto_advance {
+        cmd.entity(entity).insert(auto_advance);
+    }
+    entity
 }
 
 pub fn process_interlude_trigger(
@@ -237,12 +410,19 @@ pub fn process_interlude_trigger(
     for (entity, spec, trigger) in trigger_q.iter() {
         if trigger.should_trigger(&player_transform.translation) {
             // do not show interludes which just resume the game afterwards
-            if game_settings.skip_interludes && !spec.is_exit() {
+            if game_settings.interlude_mode == InterludeMode::Skip && !spec.is_exit() {
                 continue;
             }
 
             // spawn the interlude
-            spawn_interlude(&mut cmd, spec.clone(), &default_font, &sizes, &asset_server);
+            spawn_interlude(
+                &mut cmd,
+                spec.clone(),
+                &default_font,
+                &sizes,
+                &asset_server,
+                game_settings.interlude_mode,
+            );
             // despawn the trigger
             cmd.entity(entity).despawn();
             // issue state transition
@@ -251,6 +431,77 @@ pub fn process_interlude_trigger(
     }
 }
 
+/// Component for a tip shown at the top of the screen
+/// once the player reaches it,
+/// which unlike an interlude does not stop the game.
+#[derive(Debug, Clone, Component)]
+pub struct Banner {
+    pub text: String,
+    /// how long the banner is shown, in seconds
+    pub duration: f32,
+}
+
+/// Marker component for the UI node of a banner being shown
+#[derive(Debug, Component)]
+pub struct BannerNode;
+
+/// system that shows a banner when the player reaches it,
+/// replacing any banner still being shown
+pub fn process_banner_trigger(
+    mut cmd: Commands,
+    game_settings: Res<GameSettings>,
+    trigger_q: Query<(Entity, &Banner, &PhaseTrigger)>,
+    banner_node_q: Query<Entity, With<BannerNode>>,
+    player_q: Query<&Transform, With<Player>>,
+    default_font: Res<DefaultFont>,
+) {
+    let Ok(player_transform) = player_q.get_single() else {
+        return;
+    };
+
+    for (entity, banner, trigger) in trigger_q.iter() {
+        if !trigger.should_trigger(&player_transform.translation) {
+            continue;
+        }
+        cmd.entity(entity).despawn();
+
+        // banners are tips, so they go away with the interludes
+        if game_settings.interlude_mode == InterludeMode::Skip {
+            continue;
+        }
+
+        for node in &banner_node_q {
+            cmd.entity(node).despawn();
+        }
+        cmd.spawn((
+            OnLive,
+            BannerNode,
+            TextBundle {
+                text: Text::from_section(
+                    banner.text.clone(),
+                    TextStyle {
+                        font: default_font.0.clone(),
+                        font_size: 24.,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_justify(JustifyText::Center),
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(0.),
+                    width: Val::Percent(100.),
+                    padding: UiRect::axes(Val::Px(16.), Val::Px(10.)),
+                    ..default()
+                },
+                background_color: BackgroundColor(Color::srgba(0., 0., 0., 0.6)),
+                z_index: ZIndex::Global(9),
+                ..default()
+            },
+            TimeToLive(banner.duration),
+        ));
+    }
+}
+
 /// An event made to advance the interlude.
 /// The event carries the entity of the previous interlude
 /// and the effect which should be applied next.
@@ -265,8 +516,9 @@ pub fn on_click_advance_interlude(
     touches: Res<Touches>,
     // should only fetch the interlude being presented,
     // hence `Without<PhaseTrigger>`
-    interlude_q: Query<(Entity, &InterludeSpec), Without<PhaseTrigger>>,
-    interlude_pieces_q: Query<(Entity, Has<FadeOut>), With<InterludePiece>>,
+    interlude_q: Query<(Entity, &InterludeSpec), (Without<PhaseTrigger>, Without<Advancing>)>,
+    interlude_pieces_q: Query<(Entity, Has<FadesAway>), With<InterludePiece>>,
+    button_q: Query<&Interaction, With<SaveRunCardButton>>,
     mut advance_event: EventWriter<AdvanceInterlude>,
 ) {
     // advance on left mouse click, Enter, or tap
@@ -276,21 +528,52 @@ pub fn on_click_advance_interlude(
     {
         return;
     }
+    // clicking a button on the interlude does not advance it
+    if button_q
+        .iter()
+        .any(|interaction| *interaction != Interaction::None)
+    {
+        return;
+    }
+    let Ok((interlude_entity, spec)) = interlude_q.get_single() else {
+        return;
+    };
 
+    let mut fading_out = false;
     for (entity, has_fadeout) in interlude_pieces_q.iter() {
         // add fade-out if it does not exist yet
-        if !has_fadeout {
-            cmd.entity(entity).insert(FadeOut);
+        if has_fadeout {
+            fading_out = true;
         } else {
-            // fetch interlude spec and advance immediately
-            if let Ok((e, spec)) = interlude_q.get_single() {
-                // removing the fade-out is important,
-                // otherwise it may try to advance the same interlude twice
-                cmd.entity(entity).remove::<FadeOut>();
-                advance_event.send(AdvanceInterlude(e, spec.effect.clone()));
-            }
+            cmd.entity(entity).insert(FadesAway { rate: 2. });
         }
     }
+
+    if fading_out {
+        // already fading out, advance immediately
+        cmd.entity(interlude_entity).insert(Advancing);
+        advance_event.send(AdvanceInterlude(interlude_entity, spec.effect.clone()));
+    }
+}
+
+/// system that starts fading out interludes
+/// which move on by themselves once their time is up,
+/// so that they advance as if the player had clicked
+pub fn auto_advance_interlude(
+    mut cmd: Commands,
+    time: Res<Time>,
+    mut interlude_q: Query<&mut AutoAdvance, (Without<PhaseTrigger>, Without<Advancing>)>,
+    interlude_pieces_q: Query<Entity, (With<InterludePiece>, Without<FadesAway>)>,
+) {
+    let Ok(mut auto_advance) = interlude_q.get_single_mut() else {
+        return;
+    };
+    if !auto_advance.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    for entity in &interlude_pieces_q {
+        cmd.entity(entity).insert(FadesAway { rate: 2. });
+    }
 }
 
 /// make interlude content fade in from black
@@ -319,41 +602,37 @@ pub fn fade_in_interlude(
     }
 }
 
-/// system to slowly fade out interlude content before transitioning
+/// system to transition once the interlude content has faded out
+/// (the fading itself is done by [`fade_ui`](crate::effect::fade_ui))
 pub fn fade_out_interlude(
-    time: Res<Time>,
+    mut cmd: Commands,
     // should only fetch the interlude being presented,
     // hence `Without<PhaseTrigger>`
-    interlude_q: Query<(Entity, &InterludeSpec), Without<PhaseTrigger>>,
-    mut text_q: Query<&mut Text, With<FadeOut>>,
-    mut image_q: Query<&mut UiImage, With<FadeOut>>,
+    interlude_q: Query<(Entity, &InterludeSpec), (Without<PhaseTrigger>, Without<Advancing>)>,
+    text_q: Query<&Text, (With<InterludePiece>, With<FadesAway>)>,
     mut advance_event: EventWriter<AdvanceInterlude>,
 ) {
-    let delta = time.delta_seconds();
-
-    let mut should_transition = false;
-    if let Ok(mut text) = text_q.get_single_mut() {
-        for section in text.sections.iter_mut() {
-            let new_alpha = (section.style.color.alpha() - delta * 2.).max(0.);
-            section.style.color.set_alpha(new_alpha);
-
-            if new_alpha == 0. {
-                // time to transition
-                should_transition = true;
-            }
-        }
-    }
-    if let Ok(mut image) = image_q.get_single_mut() {
-        let new_alpha = (image.color.alpha() - delta * 2.).max(0.);
-        image.color.set_alpha(new_alpha);
-    }
+    let Ok(text) = text_q.get_single() else {
+        return;
+    };
+    // time to transition once the message is gone
+    let should_transition = text
+        .sections
+        .iter()
+        .any(|section| section.style.color.alpha() == 0.);
 
     if should_transition {
-        let (e, spec) = interlude_q.single();
+        let Ok((e, spec)) = interlude_q.get_single() else {
+            // already advancing
+            return;
+        };
+        cmd.entity(e).insert(Advancing);
         advance_event.send(AdvanceInterlude(e, spec.effect.clone()));
     }
 }
 
+/// system that moves on from the interlude being presented,
+/// one step per frame at most
 pub fn process_advance_interlude(
     mut events: EventReader<AdvanceInterlude>,
     mut cmd: Commands,
@@ -362,12 +641,17 @@ pub fn process_advance_interlude(
     asset_server: Res<AssetServer>,
     sizes: Res<Sizes>,
     default_font: Res<DefaultFont>,
+    current_level: Res<CurrentLevel>,
+    cheats: Res<Cheats>,
+    mut persistent_data: ResMut<PersistentData>,
+    mut toasts: ResMut<Toasts>,
+    live_time: Res<LiveTime>,
+    game_settings: Res<GameSettings>,
 ) {
     for event in events.read() {
         let AdvanceInterlude(entity, effect) = event;
         // despawn the current interlude
-        // (using `get_entity` because there is a race condition
-        // which might trigger this event more than once for the same step)
+        // (using `get_entity` just in case it is already gone)
         if let Some(e_cmd) = cmd.get_entity(*entity) {
             e_cmd.despawn_recursive();
 
@@ -380,6 +664,7 @@ pub fn process_advance_interlude(
                         &default_font,
                         &sizes,
                         &asset_server,
+                        game_settings.interlude_mode,
                     );
                 }
                 InterludeEffect::Resume => {
@@ -387,6 +672,21 @@ pub fn process_advance_interlude(
                     next_live_state.set(LiveState::Running);
                 }
                 InterludeEffect::Exit => {
+                    // record the ending reached
+                    // (only if it was reached fairly)
+                    if let Some(ending) = Ending::of_level(current_level.id) {
+                        if !cheats.used_cheats {
+                            ending.record_discovered(&mut persistent_data);
+                            let time = live_time.elapsed_seconds();
+                            if ending.record_time(&mut persistent_data, time) {
+                                info!("New best time for {}: {}", ending.name(), &*live_time);
+                            }
+                            if Ending::discovered(&persistent_data).len() == Ending::ALL.len() {
+                                Achievement::AllEndings.unlock(&mut persistent_data, &mut toasts);
+                            }
+                        }
+                    }
+
                     // issue state transition back to menu
                     next_root_state.set(AppState::Menu);
                 }
@@ -395,3 +695,229 @@ pub fn process_advance_interlude(
         break;
     }
 }
+
+/// system that saves a run card of the ending reached
+/// when its button is pressed on the ending screen
+pub fn save_run_card_on_click(
+    mut cmd: Commands,
+    button_q: Query<&Interaction, (Changed<Interaction>, With<SaveRunCardButton>)>,
+    card_q: Query<(), With<RunCard>>,
+    current_level: Res<CurrentLevel>,
+    live_time: Res<LiveTime>,
+    run_stats: Res<RunStats>,
+    cheats: Res<Cheats>,
+    default_font: Res<DefaultFont>,
+    sizes: Res<Sizes>,
+    persistent_data: Res<PersistentData>,
+) {
+    if !button_q
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        return;
+    }
+    // one card at a time
+    if !card_q.is_empty() {
+        return;
+    }
+    let Some(ending) = Ending::of_level(current_level.id) else {
+        return;
+    };
+    let summary = RunSummary {
+        ending,
+        time: live_time.elapsed_seconds(),
+        accuracy: run_stats.accuracy(),
+        used_cheats: cheats.used_cheats,
+    };
+    spawn_run_card(
+        &mut cmd,
+        &summary,
+        default_font.0.clone(),
+        &sizes,
+        &persistent_data,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{ecs::system::RunSystemOnce, state::app::StatesPlugin, time::TimeUpdateStrategy};
+
+    use super::*;
+    use crate::effect::fade_ui;
+
+    fn spawn_interlude_in(world: &mut World, spec: InterludeSpec, mode: InterludeMode) {
+        world.run_system_once(
+            move |mut cmd: Commands,
+                  default_font: Res<DefaultFont>,
+                  sizes: Res<Sizes>,
+                  asset_server: Res<AssetServer>| {
+                spawn_interlude(
+                    &mut cmd,
+                    spec.clone(),
+                    &default_font,
+                    &sizes,
+                    &asset_server,
+                    mode,
+                );
+            },
+        );
+    }
+
+    /// an app presenting interludes in the given mode,
+    /// a tenth of a second per update
+    fn interlude_app(mode: InterludeMode) -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default(), StatesPlugin))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                100,
+            )))
+            .init_asset::<Image>()
+            .init_asset::<Font>()
+            .init_state::<AppState>()
+            .add_sub_state::<LiveState>()
+            .init_resource::<DefaultFont>()
+            .init_resource::<Sizes>()
+            .init_resource::<CurrentLevel>()
+            .init_resource::<Cheats>()
+            .init_resource::<PersistentData>()
+            .init_resource::<Toasts>()
+            .init_resource::<LiveTime>()
+            .insert_resource(GameSettings {
+                interlude_mode: mode,
+                ..default()
+            })
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<ButtonInput<MouseButton>>()
+            .init_resource::<Touches>()
+            .add_event::<AdvanceInterlude>()
+            .add_systems(
+                Update,
+                (
+                    fade_in_interlude,
+                    fade_ui,
+                    auto_advance_interlude,
+                    on_click_advance_interlude,
+                    fade_out_interlude,
+                    process_advance_interlude,
+                )
+                    .chain(),
+            );
+        app.update();
+        app
+    }
+
+    /// the messages of the interludes being presented
+    fn interlude_messages(app: &mut App) -> Vec<String> {
+        app.world_mut()
+            .query_filtered::<&InterludeSpec, Without<PhaseTrigger>>()
+            .iter(app.world())
+            .map(|spec| spec.message.clone())
+            .collect()
+    }
+
+    fn update_pressing_enter(app: &mut App) {
+        let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        input.release_all();
+        input.clear();
+        input.press(KeyCode::Enter);
+        app.update();
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .clear();
+    }
+
+    #[test]
+    fn rapid_presses_advance_one_step_at_a_time() {
+        let mut app = interlude_app(InterludeMode::Full);
+        let spec = InterludeSpec::from_sequence([("a", None), ("b", None), ("c", None)]);
+        spawn_interlude_in(app.world_mut(), spec, InterludeMode::Full);
+
+        // the first press starts fading out, the second one moves on
+        update_pressing_enter(&mut app);
+        assert_eq!(interlude_messages(&mut app), vec!["a"]);
+        update_pressing_enter(&mut app);
+        assert_eq!(interlude_messages(&mut app), vec!["b"]);
+        // the next interlude is not skipped
+        app.update();
+        assert_eq!(interlude_messages(&mut app), vec!["b"]);
+    }
+
+    #[test]
+    fn advancing_twice_in_one_frame_moves_on_once() {
+        let mut app = interlude_app(InterludeMode::Full);
+        let spec = InterludeSpec::from_sequence([("a", None), ("b", None), ("c", None)]);
+        spawn_interlude_in(app.world_mut(), spec, InterludeMode::Full);
+        let (entity, effect) = app
+            .world_mut()
+            .query_filtered::<(Entity, &InterludeSpec), Without<PhaseTrigger>>()
+            .iter(app.world())
+            .map(|(entity, spec)| (entity, spec.effect.clone()))
+            .next()
+            .unwrap();
+
+        app.world_mut()
+            .send_event(AdvanceInterlude(entity, effect.clone()));
+        app.world_mut().send_event(AdvanceInterlude(entity, effect));
+        app.update();
+        assert_eq!(interlude_messages(&mut app), vec!["b"]);
+        app.update();
+        assert_eq!(interlude_messages(&mut app), vec!["b"]);
+    }
+
+    #[test]
+    fn fast_interludes_advance_without_input() {
+        let spec = || InterludeSpec::from_sequence([("a", None), ("b", None)]);
+
+        let mut app = interlude_app(InterludeMode::Fast);
+        spawn_interlude_in(app.world_mut(), spec(), InterludeMode::Fast);
+        let mut updates = 0;
+        while interlude_messages(&mut app) == ["a"] {
+            assert!(updates < 100, "interlude did not advance on its own");
+            app.update();
+            updates += 1;
+        }
+        assert_eq!(interlude_messages(&mut app), vec!["b"]);
+        // not before the player had some time to read it
+        assert!(updates >= 20, "advanced after {updates} updates");
+
+        // interludes still wait for the player in full mode
+        let mut app = interlude_app(InterludeMode::Full);
+        spawn_interlude_in(app.world_mut(), spec(), InterludeMode::Full);
+        for _ in 0..100 {
+            app.update();
+        }
+        assert_eq!(interlude_messages(&mut app), vec!["a"]);
+    }
+
+    fn run_card_buttons_on(spec: InterludeSpec) -> usize {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Image>()
+            .init_asset::<Font>()
+            .init_resource::<DefaultFont>()
+            .init_resource::<Sizes>();
+        let world = app.world_mut();
+        spawn_interlude_in(world, spec, InterludeMode::Full);
+        world
+            .query_filtered::<(), With<SaveRunCardButton>>()
+            .iter(world)
+            .count()
+    }
+
+    #[test]
+    fn only_the_ending_screen_offers_a_run_card() {
+        assert_eq!(
+            run_card_buttons_on(InterludeSpec::new_single("Onwards", None)),
+            0
+        );
+        let ending =
+            InterludeSpec::from_sequence_and_exit([("So it ends", None), ("The end", None)]);
+        let InterludeEffect::Next(last) = ending.effect.clone() else {
+            panic!("expected a second interlude, got {:?}", ending.effect);
+        };
+        assert_eq!(run_card_buttons_on(ending), 0);
+        assert_eq!(run_card_buttons_on(*last), 1);
+    }
+}
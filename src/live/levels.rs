@@ -1,11 +1,23 @@
 //! Module for level components and specifications
-use std::fmt::{self, Write};
+use std::{
+    fmt::{self, Write},
+    path::PathBuf,
+};
 
 use bevy::prelude::*;
+use serde::Deserialize;
 
-use crate::logic::Num;
+use crate::{
+    logic::{can_damage, Num, TargetRule},
+    persist::PersistentData,
+};
 
-use super::{interlude::InterludeSpec, mob::MobSpawner, Decision};
+use super::{
+    interlude::InterludeSpec,
+    mob::{Formation, MobSpawner},
+    phase::PhaseTrigger,
+    Decision,
+};
 
 /// Level identifier.
 ///
@@ -19,7 +31,7 @@ use super::{interlude::InterludeSpec, mob::MobSpawner, Decision};
 ///
 /// This allows for the game to provide unique level quirks
 /// based on the decisions made in the previous levels.
-#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
 pub struct LevelId {
     pub stage: u8,
     pub decisions: u8,
@@ -47,22 +59,215 @@ impl fmt::Display for LevelId {
 }
 
 impl LevelId {
-    pub fn add_decision(&mut self, decision: Decision) -> bool {
+    /// The level at the given stage after the given decisions,
+    /// or `None` if there is no such stage.
+    /// Decisions beyond the stage are ignored.
+    pub fn new(stage: u8, decisions: u8) -> Option<LevelId> {
+        if stage > LevelSpec::MAX_STAGES {
+            return None;
+        }
+        let mask = (1 << stage) - 1;
+        Some(LevelId {
+            stage,
+            decisions: decisions & mask,
+        })
+    }
+
+    /// The level reached by making the given decision,
+    /// or `None` if there are no more levels ahead.
+    pub fn next(self, decision: Decision) -> Option<LevelId> {
         if self.stage >= LevelSpec::MAX_STAGES {
-            warn!("Cannot move to the next level: maximum stage reached");
-            return false;
+            return None;
         }
 
-        if decision == Decision::Left {
-            self.stage += 1;
-        } else {
-            self.decisions |= 1 << self.stage;
-            self.stage += 1;
+        let mut next = self;
+        if decision == Decision::Right {
+            next.decisions |= 1 << self.stage;
+        }
+        next.stage += 1;
+        Some(next)
+    }
+
+    pub fn add_decision(&mut self, decision: Decision) -> bool {
+        match self.next(decision) {
+            Some(next) => {
+                *self = next;
+                true
+            }
+            None => {
+                warn!("Cannot move to the next level: maximum stage reached");
+                false
+            }
+        }
+    }
+}
+
+/// One of the possible endings of the game,
+/// reached after the final stage
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Ending {
+    /// Ending 1: walk in circles
+    Circle,
+    /// Ending 2: the bedroom
+    Bedroom,
+    /// Ending 3: the dungeon
+    Dungeon,
+    /// Ending 4: the mirror
+    Mirror,
+}
+
+impl Ending {
+    pub const ALL: [Ending; 4] = [
+        Ending::Circle,
+        Ending::Bedroom,
+        Ending::Dungeon,
+        Ending::Mirror,
+    ];
+
+    /// the key for persisting the endings discovered
+    const DISCOVERED_KEY: &'static str = "endings";
+
+    /// The ending reached at the given level, if any
+    pub fn of_level(level: LevelId) -> Option<Self> {
+        match level {
+            LevelId {
+                stage: 5,
+                // keep going left then right
+                decisions: 0b10000,
+            }
+            | LevelId {
+                stage: 5,
+                // keep going right, then left
+                decisions: 0b01111,
+            } => Some(Ending::Bedroom),
+
+            LevelId {
+                stage: 5,
+                // zig-zag
+                decisions: 0b01010,
+            }
+            | LevelId {
+                stage: 5,
+                // zig-zag
+                decisions: 0b10101,
+            } => Some(Ending::Dungeon),
+
+            LevelId {
+                stage: 5,
+                decisions: 0b01001,
+            } => Some(Ending::Mirror),
+
+            // fallback for most levels after the final stage
+            // (this will depend on how many levels I mange to build...)
+            LevelId { stage: 5, .. } => Some(Ending::Circle),
+            _ => None,
+        }
+    }
+
+    fn level_spec(self) -> LevelSpec {
+        match self {
+            Ending::Circle => LevelSpec::ending_circle(),
+            Ending::Bedroom => LevelSpec::ending_bedroom(),
+            Ending::Dungeon => LevelSpec::ending_dungeon(),
+            Ending::Mirror => LevelSpec::ending_mirror(),
         }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Ending::Circle => "Walking in Circles",
+            Ending::Bedroom => "The Bedroom",
+            Ending::Dungeon => "The Dungeon",
+            Ending::Mirror => "The Mirror",
+        }
+    }
+
+    /// identifier of the ending in persisted data
+    fn key(self) -> &'static str {
+        match self {
+            Ending::Circle => "circle",
+            Ending::Bedroom => "bedroom",
+            Ending::Dungeon => "dungeon",
+            Ending::Mirror => "mirror",
+        }
+    }
+
+    /// The endings which the player has discovered so far
+    pub fn discovered(data: &PersistentData) -> Vec<Ending> {
+        let keys = data.get(Self::DISCOVERED_KEY).unwrap_or_default();
+        Self::ALL
+            .into_iter()
+            .filter(|ending| keys.split(',').any(|key| key == ending.key()))
+            .collect()
+    }
+
+    /// Record that this ending was discovered
+    pub fn record_discovered(self, data: &mut PersistentData) {
+        let mut discovered = Self::discovered(data);
+        if discovered.contains(&self) {
+            return;
+        }
+        discovered.push(self);
+        let keys: Vec<_> = Self::ALL
+            .into_iter()
+            .filter(|ending| discovered.contains(ending))
+            .map(Ending::key)
+            .collect();
+        data.set(Self::DISCOVERED_KEY, keys.join(","));
+        data.save();
+    }
+
+    /// The best live time taken to reach this ending, in seconds
+    pub fn best_time(self, data: &PersistentData) -> Option<f32> {
+        data.get(&self.best_time_key())?
+            .parse()
+            .ok()
+            .filter(|time: &f32| time.is_finite() && *time > 0.)
+    }
+
+    /// Record the live time taken to reach this ending,
+    /// saving it only if it is better than the previous one.
+    ///
+    /// Returns whether it was a new best.
+    pub fn record_time(self, data: &mut PersistentData, time: f32) -> bool {
+        if self.best_time(data).is_some_and(|best| best <= time) {
+            return false;
+        }
+        data.set(self.best_time_key(), time);
+        data.save();
         true
     }
+
+    fn best_time_key(self) -> String {
+        format!("best_time.{}", self.key())
+    }
 }
 
+/// The directory with the level files
+/// which take the place of the built-in levels,
+/// if the platform has a file system.
+///
+/// It is resolved in the same way as the asset directory,
+/// so it does not depend on the working directory.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn level_file_dir() -> Option<PathBuf> {
+    Some(
+        bevy::asset::io::file::FileAssetReader::get_base_path()
+            .join("assets")
+            .join("levels"),
+    )
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn level_file_dir() -> Option<PathBuf> {
+    None
+}
+
+/// Global resource for the level to start the game in
+/// instead of the first one (used for warping to a level)
+#[derive(Debug, Default, Resource)]
+pub struct LevelWarp(pub Option<LevelId>);
+
 /// Global resource for the current level
 #[derive(Debug, Default, Resource)]
 pub struct CurrentLevel {
@@ -83,11 +288,17 @@ impl CurrentLevel {
     pub fn reset(&mut self) {
         *self = CurrentLevel::default();
     }
+
+    /// Go straight to the given level
+    pub fn jump_to(&mut self, id: LevelId) {
+        self.id = id;
+        self.spec = LevelSpec::level(id);
+    }
 }
 
 /// Generic thing in a level
 /// to be placed relative to the corridor length
-#[derive(Debug)]
+#[derive(Debug, Deserialize)]
 pub struct Thing {
     /// position relative to the length of the corridor,
     /// from 0 (start) to 1 (end)
@@ -109,11 +320,41 @@ where
 }
 
 /// The actual thing that should appear in the level
-#[derive(Debug)]
+#[derive(Debug, Deserialize)]
 pub enum ThingKind {
-    WeaponCube { x: f32, num: Num },
+    WeaponCube {
+        x: f32,
+        num: Num,
+        /// the cooldown added per use of this weapon,
+        /// if different from the default for its number
+        #[serde(default)]
+        cooldown: Option<f32>,
+        /// how many targets a shot of this weapon can hit
+        /// before it is spent (0 or 1 for just one)
+        #[serde(default)]
+        pierce: u8,
+        /// whether shots of this weapon steer towards targets
+        #[serde(default)]
+        homing: bool,
+        /// how many shots this weapon has before it is used up
+        /// (`None` for a weapon which is never used up)
+        #[serde(default)]
+        ammo: Option<u32>,
+    },
     MobSpawner(MobSpawner),
+    /// a bonus challenge to shoot numbers in ascending order from memory
+    /// (only if bonus rooms are enabled)
+    MemoryChallenge {
+        count: u32,
+    },
     Interlude(InterludeSpec),
+    /// a short tip shown at the top of the screen
+    /// without stopping the game
+    Banner {
+        text: String,
+        /// how long the banner is shown, in seconds
+        duration: f32,
+    },
     Dread,
     MoveOn,
 }
@@ -130,11 +371,26 @@ impl From<InterludeSpec> for ThingKind {
     }
 }
 
-#[derive(Debug)]
+/// The full specification of a level.
+///
+/// Besides the built-in levels,
+/// a level can be read from a RON file in the level directory
+/// named after its stage and decisions.
+#[derive(Debug, Deserialize)]
 pub struct LevelSpec {
     pub corridor_length: f32,
     /// the seed defining reproducible behavior patterns in the level
     pub rng_seed: u64,
+    /// the expected time to clear the level, in seconds
+    /// (derived from the level's contents if not set)
+    #[serde(default)]
+    pub par_time: Option<f32>,
+    /// the color of the light carried by the player
+    #[serde(default = "LevelSpec::default_light_color")]
+    pub light_color: Color,
+    /// the intensity of the light carried by the player
+    #[serde(default = "LevelSpec::default_light_intensity")]
+    pub light_intensity: f32,
     /// the things in the level
     pub things: Vec<Thing>,
 }
@@ -145,6 +401,9 @@ impl Default for LevelSpec {
     }
 }
 
+/// fraction literal, such as `frac!(3 / 4)`.
+/// The numerator may be negative (`frac!(-1 / 2)`),
+/// but the denominator must be positive.
 macro_rules! frac {
     ($a: literal / $b: literal) => {
         Num::new_raw($a, $b)
@@ -157,7 +416,90 @@ macro_rules! frac {
 impl LevelSpec {
     const MAX_STAGES: u8 = 5;
 
+    /// the usual warm white light
+    pub const DEFAULT_LIGHT_COLOR: Color = Color::srgb(1., 1., 224. / 255.);
+    pub const DEFAULT_LIGHT_INTENSITY: f32 = 4_400_000.;
+
+    /// The expected time to clear the level, in seconds.
+    ///
+    /// If the level does not define one,
+    /// it is estimated from the time to walk the corridor
+    /// plus the time for all mobs to spawn.
+    pub fn par_time(&self) -> f32 {
+        if let Some(par_time) = self.par_time {
+            return par_time;
+        }
+        let spawn_time: f32 = self
+            .things
+            .iter()
+            .filter_map(|thing| match &thing.what {
+                ThingKind::MobSpawner(spawner) => {
+                    Some(spawner.count as f32 * spawner.spawn_interval)
+                }
+                _ => None,
+            })
+            .sum();
+        self.corridor_length / 8. + spawn_time
+    }
+
+    fn default_light_color() -> Color {
+        Self::DEFAULT_LIGHT_COLOR
+    }
+
+    fn default_light_intensity() -> f32 {
+        Self::DEFAULT_LIGHT_INTENSITY
+    }
+
+    /// Load the level from its file in the level directory
+    /// (such as `level_2_1.ron` for stage 2 with decisions 1),
+    /// if there is one,
+    /// so that levels can be tweaked without recompiling.
+    fn from_file(level: LevelId) -> Option<Self> {
+        let path = level_file_dir()?.join(format!("level_{}_{}.ron", level.stage, level.decisions));
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                warn!("Could not read level file {}: {e}", path.display());
+                return None;
+            }
+        };
+        match Self::from_ron(&contents) {
+            Ok(spec) => {
+                info!("Loaded level {level} from {}", path.display());
+                Some(spec)
+            }
+            Err(e) => {
+                warn!("Could not load level file {}: {e}", path.display());
+                None
+            }
+        }
+    }
+
+    /// Read a level in the RON format,
+    /// rejecting levels which are not well formed
+    /// or which have targets that cannot be defeated
+    /// with the weapons placed before them
+    /// (a level file cannot tell which weapons the player brings).
+    fn from_ron(contents: &str) -> Result<Self, String> {
+        let spec: Self = ron::from_str(contents).map_err(|e| e.to_string())?;
+        spec.validate()?;
+        if let Some((at, num)) = spec.unsolvable_targets(&[]).first() {
+            return Err(format!("target {num} in wave at {at} cannot be defeated"));
+        }
+        Ok(spec)
+    }
+
     fn level(level: LevelId) -> Self {
+        // level files take precedence over the built-in levels
+        if let Some(spec) = Self::from_file(level) {
+            return spec;
+        }
+
+        if let Some(ending) = Ending::of_level(level) {
+            return ending.level_spec();
+        }
+
         match level {
             // starting level
             LevelId { stage: 0, .. } => Self::level_0(),
@@ -180,47 +522,229 @@ impl LevelSpec {
             // stage 4 xxx>
             level @ LevelId { stage: 4, .. } => Self::level_4r(level),
 
-            // ending 2
-            LevelId {
-                stage: 5,
-                // keep going left then right
-                decisions: 0b10000,
+            // there should be no levels beyond the endings,
+            // but just walk in circles if it happens
+            _ => {
+                warn!("Unexpected level {level}, falling back to an ending");
+                Self::ending_circle()
             }
-            | LevelId {
-                stage: 5,
-                // keep going right, then left
-                decisions: 0b01111,
-            } => Self::ending_bedroom(),
+        }
+    }
 
-            // ending 3: zig-zag
-            LevelId {
-                stage: 5,
-                // zig-zag
-                decisions: 0b01010,
+    /// The bonus room of the given level, if it has one.
+    ///
+    /// Bonus rooms are kept apart from the things in the level,
+    /// so that the level stays the same when they are disabled.
+    pub fn bonus_room(level: LevelId) -> Option<Thing> {
+        match level {
+            // a numbers memory challenge after the first waves
+            LevelId { stage: 1, .. } => {
+                Some((0.52, ThingKind::MemoryChallenge { count: 4 }).into())
             }
-            | LevelId {
-                stage: 5,
-                // zig-zag
-                decisions: 0b10101,
-            } => Self::ending_dungeon(),
+            _ => None,
+        }
+    }
 
-            // ending 4: the mirror
-            LevelId {
-                stage: 5,
-                decisions: 0b01001,
-            } => Self::ending_mirror(),
+    /// Check that the level is well formed,
+    /// describing the first problem found otherwise.
+    fn validate(&self) -> Result<(), String> {
+        if self.corridor_length <= 0. {
+            return Err(format!("bad corridor length {}", self.corridor_length));
+        }
+        if self.things.is_empty() {
+            return Err("level has nothing in it".to_string());
+        }
+        let mut spawner_ids = vec![];
+        for thing in &self.things {
+            if !(0. ..=1.).contains(&thing.at) {
+                return Err(format!("thing out of the corridor at {}", thing.at));
+            }
+            if let ThingKind::WeaponCube {
+                cooldown: Some(cooldown),
+                ..
+            } = thing.what
+            {
+                if cooldown <= 0. {
+                    return Err(format!(
+                        "weapon cube at {} has bad cooldown {cooldown}",
+                        thing.at
+                    ));
+                }
+            }
+            if let ThingKind::MemoryChallenge { count } = thing.what {
+                // numbers are picked from 1 to 30
+                if !(2..=30).contains(&count) {
+                    return Err(format!(
+                        "memory challenge at {} has bad size {count}",
+                        thing.at
+                    ));
+                }
+            }
+            if let ThingKind::Banner { text, duration } = &thing.what {
+                if text.is_empty() || *duration <= 0. {
+                    return Err(format!("banner at {} would not be seen", thing.at));
+                }
+            }
+            if let ThingKind::MobSpawner(spawner) = &thing.what {
+                if spawner.count == 0 || spawner.target_options.is_empty() {
+                    return Err(format!("mob spawner at {} spawns nothing", thing.at));
+                }
+                // chained waves must follow a spawner placed before them
+                if let Some(after) = spawner.after {
+                    if !spawner_ids.contains(&after) {
+                        return Err(format!(
+                            "mob spawner at {} waits for unknown spawner {:?}",
+                            thing.at, after
+                        ));
+                    }
+                }
+                if let Some(id) = spawner.id {
+                    if spawner_ids.contains(&id) {
+                        return Err(format!("duplicate mob spawner id {:?}", id));
+                    }
+                    spawner_ids.push(id);
+                }
+            }
+        }
+        if !self.things.windows(2).all(|w| w[0].at <= w[1].at) {
+            return Err("things are not sorted by position".to_string());
+        }
+        Ok(())
+    }
 
-            // fallback for most levels after the final stage
-            // (this will depend on how many levels I mange to build...)
-            LevelId { stage: 5, .. } => Self::ending_circle(),
-            _ => unreachable!("Unexpected level {level}"),
+    /// Find the target numbers of each wave
+    /// which cannot be defeated with the weapons brought into the level
+    /// and the ones placed up to that point in the level,
+    /// along with the position of the wave.
+    fn unsolvable_targets(&self, brought: &[Num]) -> Vec<(f32, Num)> {
+        let mut out = vec![];
+        for thing in &self.things {
+            let ThingKind::MobSpawner(spawner) = &thing.what else {
+                continue;
+            };
+            let weapons: Vec<Num> = self
+                .things
+                .iter()
+                .filter(|other| other.at <= thing.at)
+                .filter_map(|other| match other.what {
+                    ThingKind::WeaponCube { num, .. } => Some(num),
+                    _ => None,
+                })
+                .chain(brought.iter().copied())
+                .collect();
+            for &num in &spawner.target_options {
+                if !can_damage(spawner.target_rule, num, &weapons) {
+                    out.push((thing.at, num));
+                }
+            }
         }
+        out
+    }
+
+    /// Describe the level in a human readable form,
+    /// one line per thing,
+    /// including the Z position at which it is triggered.
+    pub fn summary(&self) -> String {
+        let mut out = format!(
+            "corridor length: {}, seed: {:#x}, par time: {:.0}s",
+            self.corridor_length,
+            self.rng_seed,
+            self.par_time()
+        );
+        for Thing { at, what } in &self.things {
+            let trigger_z = PhaseTrigger::new_by_corridor(self.corridor_length, *at).at_z;
+            let description = match what {
+                ThingKind::WeaponCube {
+                    x,
+                    num,
+                    cooldown,
+                    pierce,
+                    homing,
+                    ammo,
+                } => {
+                    // cubes are not triggered, they are just there
+                    let z = at * self.corridor_length;
+                    let mut desc = format!("weapon cube {num} at x: {x}, z: {z:.1}");
+                    if let Some(cooldown) = cooldown {
+                        let _ = write!(desc, ", cooldown {cooldown}s");
+                    }
+                    if *pierce > 1 {
+                        let _ = write!(desc, ", pierces {pierce}");
+                    }
+                    if *homing {
+                        let _ = write!(desc, ", homing");
+                    }
+                    if let Some(ammo) = ammo {
+                        let _ = write!(desc, ", {ammo} shots");
+                    }
+                    let _ = write!(out, "\n  {at:.3}: {desc}");
+                    continue;
+                }
+                ThingKind::MobSpawner(spawner) => {
+                    let options: Vec<String> = spawner
+                        .target_options
+                        .iter()
+                        .map(|n| n.to_string())
+                        .collect();
+                    let mut desc = format!(
+                        "mob spawner of {} every {}s, {:?} [{}]",
+                        spawner.count,
+                        spawner.spawn_interval,
+                        spawner.target_rule,
+                        options.join(", ")
+                    );
+                    if let Some(id) = spawner.id {
+                        let _ = write!(desc, ", id {}", id.0);
+                    }
+                    if let Some(after) = spawner.after {
+                        let _ = write!(desc, ", after {}", after.0);
+                    }
+                    if spawner.reuse_failed {
+                        let _ = write!(desc, ", reusing failed targets");
+                    }
+                    if spawner.decoys > 0 {
+                        let _ = write!(desc, ", {} decoys", spawner.decoys);
+                    }
+                    if let Some(speed) = spawner.movement_speed {
+                        let _ = write!(desc, ", advancing at {speed}");
+                    }
+                    if let Some(interval) = spawner.attack_interval {
+                        let _ = write!(desc, ", attacking every {interval}s");
+                    }
+                    if spawner.robustness > 1 {
+                        let _ = write!(desc, ", {} hits each", spawner.robustness);
+                    }
+                    if spawner.formation != Formation::Scattered {
+                        let _ = write!(desc, ", in {:?} formation", spawner.formation);
+                    }
+                    if let Some(color) = spawner.color {
+                        let _ = write!(desc, ", colored {}", color.to_srgba().to_hex());
+                    }
+                    desc
+                }
+                ThingKind::MemoryChallenge { count } => format!("memory challenge of {count}"),
+                ThingKind::Interlude(_) => "interlude".to_string(),
+                ThingKind::Banner { text, duration } => {
+                    format!("banner \"{text}\" for {duration}s")
+                }
+                ThingKind::Dread => "dread".to_string(),
+                ThingKind::MoveOn => "move on".to_string(),
+            };
+            let _ = write!(
+                out,
+                "\n  {at:.3}: {description} (trigger z: {trigger_z:.1})"
+            );
+        }
+        out
     }
 
     fn level_0() -> Self {
         LevelSpec {
             corridor_length: 150.,
             rng_seed: 0x01,
+            par_time: None,
+            light_color: Self::DEFAULT_LIGHT_COLOR,
+            light_intensity: Self::DEFAULT_LIGHT_INTENSITY,
             things: vec![
                 // starting story
                 (
@@ -255,7 +779,7 @@ impl LevelSpec {
                 // add a weapon cube
                 (
                     0.5,
-                    ThingKind::WeaponCube { x: 0., num: 2.into() }
+                    ThingKind::WeaponCube { x: 0., num: 2.into(), cooldown: None, pierce: 0, homing: false, ammo: None }
                 ).into(),
 
                 // add a mob spawner that spawns a few mobs
@@ -289,6 +813,9 @@ impl LevelSpec {
         LevelSpec {
             corridor_length: 200.,
             rng_seed: 0x3333_3333_fefe + level.decisions as u64 * 997,
+            par_time: None,
+            light_color: Self::DEFAULT_LIGHT_COLOR,
+            light_intensity: Self::DEFAULT_LIGHT_INTENSITY,
             things: vec![
                 // another message
                 (
@@ -301,11 +828,11 @@ impl LevelSpec {
                 // give two cubes to the player
                 (
                     0.15,
-                    ThingKind::WeaponCube { x: 1., num: 2.into() }
+                    ThingKind::WeaponCube { x: 1., num: 2.into(), cooldown: None, pierce: 0, homing: false, ammo: None }
                 ).into(),
                 (
                     0.2,
-                    ThingKind::WeaponCube { x: -1., num: 3.into() }
+                    ThingKind::WeaponCube { x: -1., num: 3.into(), cooldown: None, pierce: 0, homing: false, ammo: None }
                 ).into(),
 
                 // mob spawner with 2s and 3s
@@ -314,10 +841,20 @@ impl LevelSpec {
                     MobSpawner::new(10, 2., [2, 3]),
                 ).into(),
 
+                // a heads-up about the next wave
+                (
+                    0.6,
+                    ThingKind::Banner {
+                        text: "Up ahead: 4, 6, and 9 are all made of 2s and 3s".to_string(),
+                        duration: 4.,
+                    },
+                ).into(),
+
                 // a bit more difficult
+                // (with another go at the numbers missed so far, when practicing)
                 (
                     0.65,
-                    MobSpawner::new(16, 1.75, [2, 3, 4, 6, 9]),
+                    MobSpawner::new(16, 1.75, [2, 3, 4, 6, 9]).reusing_failed(),
                 ).into(),
 
                 // another interlude just before the fork
@@ -384,6 +921,9 @@ impl LevelSpec {
         LevelSpec {
             corridor_length: 180.,
             rng_seed: 0xc36b_58ca_1297_c528 + level.decisions as u64 * 997,
+            par_time: None,
+            light_color: Color::srgb(1., 0.9, 0.75),
+            light_intensity: Self::DEFAULT_LIGHT_INTENSITY,
             things: vec![
                 // give three cubes to the player
                 (
@@ -391,6 +931,10 @@ impl LevelSpec {
                     ThingKind::WeaponCube {
                         x: 1.,
                         num: 3.into(),
+                        cooldown: None,
+                        pierce: 0,
+                        homing: false,
+                        ammo: None,
                     },
                 )
                     .into(),
@@ -399,6 +943,10 @@ impl LevelSpec {
                     ThingKind::WeaponCube {
                         x: 0.,
                         num: 5.into(),
+                        cooldown: None,
+                        pierce: 0,
+                        homing: false,
+                        ammo: None,
                     },
                 )
                     .into(),
@@ -407,6 +955,10 @@ impl LevelSpec {
                     ThingKind::WeaponCube {
                         x: -1.,
                         num: 7.into(),
+                        cooldown: None,
+                        pierce: 0,
+                        homing: false,
+                        ammo: None,
                     },
                 )
                     .into(),
@@ -419,6 +971,10 @@ impl LevelSpec {
                     ThingKind::WeaponCube {
                         x: 0.,
                         num: 2.into(),
+                        cooldown: None,
+                        pierce: 0,
+                        homing: false,
+                        ammo: None,
                     },
                 )
                     .into(),
@@ -480,6 +1036,9 @@ impl LevelSpec {
         LevelSpec {
             corridor_length: 180.,
             rng_seed: 0x3434_3434_1297_c528 + level.decisions as u64 * 997,
+            par_time: None,
+            light_color: Color::srgb(0.9, 1., 0.9),
+            light_intensity: Self::DEFAULT_LIGHT_INTENSITY,
             things: vec![
                 // give three cubes to the player
                 (
@@ -487,6 +1046,10 @@ impl LevelSpec {
                     ThingKind::WeaponCube {
                         x: 1.,
                         num: 4.into(),
+                        cooldown: None,
+                        pierce: 0,
+                        homing: false,
+                        ammo: None,
                     },
                 )
                     .into(),
@@ -495,6 +1058,10 @@ impl LevelSpec {
                     ThingKind::WeaponCube {
                         x: 0.,
                         num: 6.into(),
+                        cooldown: None,
+                        pierce: 0,
+                        homing: false,
+                        ammo: None,
                     },
                 )
                     .into(),
@@ -503,6 +1070,10 @@ impl LevelSpec {
                     ThingKind::WeaponCube {
                         x: -1.,
                         num: 7.into(),
+                        cooldown: None,
+                        pierce: 0,
+                        homing: false,
+                        ammo: None,
                     },
                 )
                     .into(),
@@ -515,6 +1086,10 @@ impl LevelSpec {
                     ThingKind::WeaponCube {
                         x: 0.,
                         num: 11.into(),
+                        cooldown: None,
+                        pierce: 0,
+                        homing: false,
+                        ammo: None,
                     },
                 )
                     .into(),
@@ -660,6 +1235,9 @@ impl LevelSpec {
         LevelSpec {
             corridor_length: 180.,
             rng_seed: 0x3454_4321_ffff + level.decisions as u64 * 997,
+            par_time: None,
+            light_color: Color::srgb(0.9, 0.95, 1.),
+            light_intensity: 4_000_000.,
             things: vec![
                 // spawn a 1/3 cube
                 (
@@ -667,6 +1245,10 @@ impl LevelSpec {
                     ThingKind::WeaponCube {
                         x: 0.5,
                         num: frac!(1 / 3),
+                        cooldown: None,
+                        pierce: 0,
+                        homing: false,
+                        ammo: None,
                     },
                 )
                     .into(),
@@ -676,6 +1258,10 @@ impl LevelSpec {
                     ThingKind::WeaponCube {
                         x: -0.5,
                         num: frac!(1 / 4),
+                        cooldown: None,
+                        pierce: 0,
+                        homing: false,
+                        ammo: None,
                     },
                 )
                     .into(),
@@ -689,6 +1275,10 @@ impl LevelSpec {
                     ThingKind::WeaponCube {
                         x: 0.5,
                         num: frac!(3 / 4),
+                        cooldown: None,
+                        pierce: 0,
+                        homing: false,
+                        ammo: None,
                     },
                 )
                     .into(),
@@ -867,9 +1457,26 @@ impl LevelSpec {
             )
         };
 
+        // a short wave where only a bigger fraction will do
+        let spawner_bigger = MobSpawner::new_with_target_rule(
+            6,
+            2.,
+            [
+                frac!(1 / 9),
+                frac!(1 / 6),
+                frac!(1 / 4),
+                frac!(1 / 3),
+                frac!(3 / 8),
+            ],
+            TargetRule::GreaterThan,
+        );
+
         let mut out = LevelSpec {
             corridor_length: 250.,
             rng_seed: 0x1ab2_4547_fdab,
+            par_time: None,
+            light_color: Color::srgb(0.8, 0.85, 1.),
+            light_intensity: 3_600_000.,
             things: vec![
                 // spawn 4 fraction cubes
                 (
@@ -877,6 +1484,10 @@ impl LevelSpec {
                     ThingKind::WeaponCube {
                         x: 1.,
                         num: frac!(1 / 2),
+                        cooldown: None,
+                        pierce: 0,
+                        homing: false,
+                        ammo: None,
                     },
                 )
                     .into(),
@@ -885,6 +1496,10 @@ impl LevelSpec {
                     ThingKind::WeaponCube {
                         x: 0.5,
                         num: frac!(1 / 5),
+                        cooldown: None,
+                        pierce: 0,
+                        homing: false,
+                        ammo: None,
                     },
                 )
                     .into(),
@@ -893,6 +1508,10 @@ impl LevelSpec {
                     ThingKind::WeaponCube {
                         x: 0.,
                         num: frac!(1 / 7),
+                        cooldown: None,
+                        pierce: 0,
+                        homing: false,
+                        ammo: None,
                     },
                 )
                     .into(),
@@ -901,6 +1520,10 @@ impl LevelSpec {
                     ThingKind::WeaponCube {
                         x: -0.5,
                         num: frac!(1 / 8),
+                        cooldown: None,
+                        pierce: 0,
+                        homing: false,
+                        ammo: None,
                     },
                 )
                     .into(),
@@ -908,12 +1531,18 @@ impl LevelSpec {
                 (0.26, spawner_1).into(),
                 // spawn another mob spawner
                 (0.32, spawner_2).into(),
+                // spawn a mob spawner which needs big attacks
+                (0.42, spawner_bigger).into(),
                 // spawn a 2 cube
                 (
                     0.55,
                     ThingKind::WeaponCube {
                         x: 0.,
                         num: 2.into(),
+                        cooldown: None,
+                        pierce: 0,
+                        homing: false,
+                        ammo: None,
                     },
                 )
                     .into(),
@@ -930,10 +1559,15 @@ impl LevelSpec {
                     ThingKind::WeaponCube {
                         x: 0.5,
                         num: frac!(7 / 8),
+                        cooldown: None,
+                        pierce: 0,
+                        homing: false,
+                        ammo: None,
                     },
                 )
                     .into(),
             );
+            out.things.sort_by(|a, b| a.at.total_cmp(&b.at));
         }
 
         out
@@ -943,6 +1577,9 @@ impl LevelSpec {
         LevelSpec {
             corridor_length: 250.,
             rng_seed: 0x5c98_a112_fabf_551d + level.decisions as u64 * 997,
+            par_time: None,
+            light_color: Color::srgb(0.95, 0.8, 1.),
+            light_intensity: 3_600_000.,
             things: vec![
                 // spawn 4 fraction cubes
                 (
@@ -950,6 +1587,10 @@ impl LevelSpec {
                     ThingKind::WeaponCube {
                         x: 1.,
                         num: frac!(1 / 3),
+                        cooldown: None,
+                        pierce: 0,
+                        homing: false,
+                        ammo: None,
                     },
                 )
                     .into(),
@@ -958,6 +1599,10 @@ impl LevelSpec {
                     ThingKind::WeaponCube {
                         x: 0.5,
                         num: frac!(1 / 4),
+                        cooldown: None,
+                        pierce: 0,
+                        homing: false,
+                        ammo: None,
                     },
                 )
                     .into(),
@@ -966,6 +1611,10 @@ impl LevelSpec {
                     ThingKind::WeaponCube {
                         x: 0.,
                         num: frac!(1 / 5),
+                        cooldown: None,
+                        pierce: 0,
+                        homing: false,
+                        ammo: None,
                     },
                 )
                     .into(),
@@ -974,6 +1623,10 @@ impl LevelSpec {
                     ThingKind::WeaponCube {
                         x: -0.5,
                         num: frac!(1 / 6),
+                        cooldown: None,
+                        pierce: 0,
+                        homing: false,
+                        ammo: None,
                     },
                 )
                     .into(),
@@ -1030,6 +1683,10 @@ impl LevelSpec {
                     ThingKind::WeaponCube {
                         x: 0.,
                         num: 2.into(),
+                        cooldown: None,
+                        pierce: 0,
+                        homing: false,
+                        ammo: None,
                     },
                 )
                     .into(),
@@ -1155,7 +1812,136 @@ impl LevelSpec {
         LevelSpec {
             corridor_length: 1000.,
             rng_seed: 0,
+            par_time: None,
+            light_color: Self::DEFAULT_LIGHT_COLOR,
+            light_intensity: Self::DEFAULT_LIGHT_INTENSITY,
             things: vec![(0., InterludeSpec::from_sequence_and_exit(interludes)).into()],
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unexpected_levels_fall_back_to_an_ending() {
+        let beyond = LevelId {
+            stage: LevelSpec::MAX_STAGES + 2,
+            decisions: 0b101,
+        };
+        assert_eq!(
+            LevelSpec::level(beyond).summary(),
+            LevelSpec::ending_circle().summary()
+        );
+
+        let mut last = LevelId {
+            stage: LevelSpec::MAX_STAGES,
+            decisions: 0,
+        };
+        assert!(!last.add_decision(Decision::Right));
+        assert_eq!(last.stage, LevelSpec::MAX_STAGES);
+    }
+
+    #[test]
+    fn best_times_only_improve() {
+        let mut data = PersistentData::default();
+        assert_eq!(Ending::Circle.best_time(&data), None);
+
+        assert!(Ending::Circle.record_time(&mut data, 300.));
+        assert!(!Ending::Circle.record_time(&mut data, 320.));
+        assert!(Ending::Circle.record_time(&mut data, 250.));
+        assert_eq!(Ending::Circle.best_time(&data), Some(250.));
+        // one entry per ending
+        assert_eq!(Ending::Mirror.best_time(&data), None);
+    }
+
+    #[test]
+    fn corrupt_best_times_are_ignored() {
+        let mut data = PersistentData::default();
+        data.set("best_time.circle", "not a time");
+        data.set("best_time.mirror", -4.);
+        assert_eq!(Ending::Circle.best_time(&data), None);
+        assert_eq!(Ending::Mirror.best_time(&data), None);
+        assert!(Ending::Circle.record_time(&mut data, 500.));
+        assert_eq!(Ending::Circle.best_time(&data), Some(500.));
+    }
+
+    #[test]
+    fn example_level_file_loads() {
+        let spec = LevelSpec::from_ron(include_str!("../../assets/levels/example.ron")).unwrap();
+        assert_eq!(spec.corridor_length, 60.);
+        assert_eq!(spec.rng_seed, 1234);
+        assert_eq!(spec.things.len(), 8);
+        assert!(matches!(
+            spec.things[1].what,
+            ThingKind::WeaponCube { num, .. } if num == Num::from_integer(2)
+        ));
+        let ThingKind::MobSpawner(spawner) = &spec.things[3].what else {
+            panic!("expected a mob spawner, got {:?}", spec.things[3].what);
+        };
+        assert_eq!(spawner.count, 4);
+        assert_eq!(spawner.target_options.len(), 3);
+    }
+
+    #[test]
+    fn level_files_with_unbeatable_targets_are_rejected() {
+        let contents = "(
+            corridor_length: 20.0,
+            rng_seed: 1,
+            things: [
+                (at: 0.1, what: WeaponCube(x: 0.0, num: (2, 1))),
+                (at: 0.5, what: MobSpawner((count: 1, spawn_interval: 1.0, target_options: [(7, 1)]))),
+            ],
+        )";
+        let problem = LevelSpec::from_ron(contents).unwrap_err();
+        assert!(problem.contains("cannot be defeated"), "{problem}");
+
+        let contents = contents.replace("(2, 1)", "(7, 1)");
+        assert!(LevelSpec::from_ron(&contents).is_ok());
+    }
+
+    #[test]
+    fn level_file_dir_is_under_the_asset_root() {
+        let dir = level_file_dir().unwrap();
+        assert!(dir.ends_with("assets/levels"));
+        // tests run with the manifest directory as the asset root
+        assert!(dir.join("example.ron").is_file());
+    }
+
+    #[test]
+    fn every_reachable_level_is_solvable() {
+        /// check the level and the ones after it,
+        /// given the weapons the player brings into it
+        fn check_from(level: LevelId, brought: &[Num]) -> usize {
+            let spec = LevelSpec::level(level);
+            if let Err(problem) = spec.validate() {
+                panic!("Level {level} is not well formed: {problem}");
+            }
+            let unsolvable = spec.unsolvable_targets(brought);
+            assert!(
+                unsolvable.is_empty(),
+                "Level {level} has targets which cannot be defeated (at, num): {unsolvable:?}"
+            );
+
+            let mut weapons = brought.to_vec();
+            for thing in &spec.things {
+                if let ThingKind::WeaponCube { num, .. } = thing.what {
+                    if !weapons.contains(&num) {
+                        weapons.push(num);
+                    }
+                }
+            }
+            let mut checked = 1;
+            for decision in [Decision::Left, Decision::Right] {
+                if let Some(next) = level.next(decision) {
+                    checked += check_from(next, &weapons);
+                }
+            }
+            checked
+        }
+
+        let checked = check_from(LevelId::default(), &[]);
+        assert!(checked > 1, "only {checked} levels were checked");
+    }
+}
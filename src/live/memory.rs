@@ -0,0 +1,249 @@
+//! The numbers memory challenge,
+//! an optional bonus encounter in which a few numbers are shown,
+//! then hidden,
+//! and the player must shoot them in ascending order from memory.
+use bevy::{prelude::*, ui::FocusPolicy};
+use tinyrand::RandRange;
+
+use crate::{
+    assets::AudioHandles,
+    effect::{Collapsing, TimeToLive, Velocity},
+    logic::{Num, TargetRule},
+    GameSettings,
+};
+
+use super::{
+    icon::{spawn_feedback_text, HasIcon, IconNode, NumStyle},
+    mob::{spawn_mob, MobAssets, Randomness},
+    phase::PhaseTrigger,
+    player::{Player, TargetDestroyed},
+    weapon::PlayerAttack,
+    Health, OnLive, Target,
+};
+
+/// Component for the entity starting a memory challenge
+/// once the player reaches it.
+#[derive(Debug, Component)]
+pub struct MemoryChallenge {
+    /// the number of targets to remember
+    pub count: u32,
+}
+
+/// Component for the targets of a memory challenge.
+///
+/// These are not affected by the usual rules of attack,
+/// see [`process_memory_attacks`].
+#[derive(Debug, Component)]
+pub struct MemoryTarget;
+
+/// Component for memory targets which are still showing their number,
+/// holding the time left until it is hidden, in seconds.
+#[derive(Debug, Component)]
+pub struct MemoryReveal(pub f32);
+
+/// how long the numbers are shown, in seconds
+const REVEAL_TIME: f32 = 3.;
+
+/// Z offset where memory targets are placed
+/// relative to the challenge position
+const TARGET_Z_OFFSET: f32 = 12.;
+
+/// horizontal space between memory targets
+const TARGET_SPACING: f32 = 2.75;
+
+/// system that starts a memory challenge when the player reaches it
+pub fn process_memory_trigger(
+    mut cmd: Commands,
+    mob_assets: Res<MobAssets>,
+    game_settings: Res<GameSettings>,
+    mut challenge_q: Query<(
+        Entity,
+        &MemoryChallenge,
+        &mut Randomness,
+        &Transform,
+        &PhaseTrigger,
+    )>,
+    player_q: Query<&Transform, With<Player>>,
+) {
+    let Ok(player_transform) = player_q.get_single() else {
+        return;
+    };
+
+    let num_style = NumStyle::from(&*game_settings);
+    for (entity, challenge, mut random, transform, phase) in &mut challenge_q {
+        if !phase.should_trigger(&player_transform.translation) {
+            continue;
+        }
+
+        // pick distinct numbers
+        let mut numbers: Vec<u32> = Vec::with_capacity(challenge.count as usize);
+        while numbers.len() < challenge.count as usize {
+            let n = random.rng.next_range(1..31_u32);
+            if !numbers.contains(&n) {
+                numbers.push(n);
+            }
+        }
+
+        // place them side by side
+        let half_width = (challenge.count - 1) as f32 * TARGET_SPACING / 2.;
+        for (i, n) in numbers.into_iter().enumerate() {
+            let rel_x = i as f32 * TARGET_SPACING - half_width;
+            let rel_y = random.rng.next_range(0..3_u32) as f32 - 1.;
+            let pos = transform.translation + Vec3::new(rel_x, rel_y, TARGET_Z_OFFSET);
+            let target_entity = spawn_mob(
+                &mut cmd,
+                &mob_assets,
+                pos,
+                Target {
+                    num: Num::from(n as i16),
+                    rule: TargetRule::Invulnerable,
+                    accumulated: Num::ZERO,
+                },
+                num_style,
+            );
+            cmd.entity(target_entity)
+                .insert((MemoryTarget, MemoryReveal(REVEAL_TIME)));
+        }
+
+        cmd.entity(entity).despawn();
+    }
+}
+
+/// system that hides the numbers of memory targets
+/// once their reveal time is over
+pub fn hide_memory_numbers(
+    mut cmd: Commands,
+    time: Res<Time>,
+    mut target_q: Query<(Entity, &HasIcon, &mut MemoryReveal)>,
+    mut icon_q: Query<&mut Visibility, With<IconNode>>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, has_icon, mut reveal) in &mut target_q {
+        reveal.0 -= delta;
+        if reveal.0 > 0. {
+            continue;
+        }
+        if let Ok(mut visibility) = icon_q.get_mut(has_icon.0) {
+            *visibility = Visibility::Hidden;
+        }
+        cmd.entity(entity).remove::<MemoryReveal>();
+    }
+}
+
+/// system that checks that memory targets are attacked in ascending order.
+///
+/// The player is rewarded with full health
+/// if all of them are defeated in order.
+/// A wrong guess shows the expected order and ends the challenge.
+pub fn process_memory_attacks(
+    mut cmd: Commands,
+    audio_handles: Res<AudioHandles>,
+    mut events: EventReader<PlayerAttack>,
+    mut target_destroyed_events: EventWriter<TargetDestroyed>,
+    target_q: Query<(Entity, &Target, &GlobalTransform, Has<MemoryReveal>), With<MemoryTarget>>,
+    mut player_q: Query<&mut Health, With<Player>>,
+    game_settings: Res<GameSettings>,
+) {
+    let num_style = NumStyle::from(&*game_settings);
+    // targets destroyed during this run of the system
+    let mut destroyed: Vec<Entity> = vec![];
+
+    for PlayerAttack { entity, .. } in events.read() {
+        if destroyed.contains(entity) {
+            continue;
+        }
+        let Ok((_, target, transform, revealed)) = target_q.get(*entity) else {
+            continue;
+        };
+        if revealed {
+            spawn_feedback_text(&mut cmd, *entity, "Memorize!", Color::srgb(1., 1., 0.75));
+            continue;
+        }
+
+        let mut remaining: Vec<(Entity, Num)> = target_q
+            .iter()
+            .filter(|(e, ..)| !destroyed.contains(e))
+            .map(|(e, t, ..)| (e, t.num))
+            .collect();
+        remaining.sort_by_key(|(_, num)| *num);
+        let smallest = remaining.first().map(|(_, num)| *num);
+
+        if Some(target.num) == smallest {
+            // correct, destroy it
+            collapse(&mut cmd, *entity);
+            destroyed.push(*entity);
+            target_destroyed_events.send(TargetDestroyed);
+            audio_handles.play_hit02(&mut cmd, transform.translation());
+
+            if remaining.len() == 1 {
+                // challenge complete!
+                if let Ok(mut health) = player_q.get_single_mut() {
+                    health.replenish();
+                }
+                spawn_message(
+                    &mut cmd,
+                    "Bonus! Health restored".to_string(),
+                    Color::srgb(0.4, 1., 0.4),
+                );
+            }
+        } else {
+            // wrong, show the expected order and end the challenge
+            let order: Vec<String> = remaining
+                .iter()
+                .map(|(_, num)| num_style.format(*num))
+                .collect();
+            spawn_message(
+                &mut cmd,
+                format!(
+                    "{} is wrong! The order was {}",
+                    num_style.format(target.num),
+                    order.join(", ")
+                ),
+                Color::srgb(1., 0.2, 0.2),
+            );
+            for (other, _) in remaining {
+                collapse(&mut cmd, other);
+                destroyed.push(other);
+                target_destroyed_events.send(TargetDestroyed);
+            }
+        }
+    }
+}
+
+/// show the outcome of the challenge for a moment
+/// (the targets may be gone by then)
+fn spawn_message(cmd: &mut Commands, message: String, color: Color) {
+    cmd.spawn((
+        OnLive,
+        TextBundle {
+            text: Text::from_section(
+                message,
+                TextStyle {
+                    color,
+                    font_size: 28.,
+                    ..default()
+                },
+            )
+            .with_justify(JustifyText::Center),
+            focus_policy: FocusPolicy::Pass,
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(18.),
+                width: Val::Percent(100.),
+                ..default()
+            },
+            z_index: ZIndex::Global(11),
+            ..default()
+        },
+        TimeToLive(2.5),
+    ));
+}
+
+/// add the effects to destroy a memory target
+fn collapse(cmd: &mut Commands, entity: Entity) {
+    cmd.entity(entity).remove::<Target>().insert((
+        Collapsing::default(),
+        Velocity(Vec3::new(0., 8., 6.)),
+        TimeToLive(0.5),
+    ));
+}
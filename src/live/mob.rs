@@ -2,38 +2,204 @@
 
 use bevy::prelude::*;
 use bevy_mod_picking::{prelude::Pickable, PickableBundle};
+use serde::Deserialize;
 use tinyrand::RandRange;
 
 use crate::{
-    effect::ScalesUp,
+    assets::AudioHandles,
+    effect::{Collapsing, ScalesUp, TimeToLive, Wobbles},
     logic::{Num, TargetRule},
+    CameraMarker, GameSettings,
 };
 
 use super::{
     collision::CollidableBox,
-    icon::{spawn_target_icon, HasIcon},
+    icon::{spawn_target_icon, HasIcon, NumStyle},
     phase::PhaseTrigger,
-    player::{Player, TargetDestroyed},
+    player::{DamagePlayer, Player, TargetDestroyed},
+    stats::FailedTargets,
+    weapon::AttackCooldown,
     Health, LiveTime, OnLive, Target,
 };
 
 /// Component representing a spawner of mobs.
-#[derive(Debug, Clone, Component)]
+///
+/// In level files,
+/// only the count, spawn interval, and target options are required.
+#[derive(Debug, Clone, Component, Deserialize)]
 pub struct MobSpawner {
     /// time to wait between each spawn
     pub spawn_interval: f32,
     /// live time in seconds of the last spawn
+    #[serde(skip)]
     pub last_spawn: f32,
     /// the options for the target number
     pub target_options: Vec<Num>,
+    #[serde(default)]
     pub target_rule: TargetRule,
     /// Whether it is actively spawning mobs.
     ///
     /// It starts disabled so it can be spawned at level start.
+    #[serde(skip)]
     pub active: bool,
     /// count for the number of mobs yet to be spawned
     /// (should despawn itself when it reaches 0)
     pub count: u32,
+    /// an identifier for other spawners to refer to
+    #[serde(default)]
+    pub id: Option<SpawnerId>,
+    /// the spawner which must be cleared
+    /// (all of its mobs spawned and defeated)
+    /// before this one can be activated
+    #[serde(default)]
+    pub after: Option<SpawnerId>,
+    /// the number of decoys to mix in with the mobs
+    #[serde(default)]
+    pub decoys: u32,
+    /// whether to spawn targets which the player failed before
+    /// instead of the usual options (only in practice mode)
+    #[serde(default)]
+    pub reuse_failed: bool,
+    /// how fast the mobs advance towards the player, if at all
+    #[serde(default)]
+    pub movement_speed: Option<f32>,
+    /// the time between attacks of each mob on the player, in seconds,
+    /// if they attack at all
+    #[serde(default)]
+    pub attack_interval: Option<f32>,
+    /// how many effective attacks each mob takes to be destroyed,
+    /// with its number decomposed on every hit but the last
+    #[serde(default = "MobSpawner::default_robustness")]
+    pub robustness: u32,
+    /// how the mobs are placed around the spawner
+    #[serde(default)]
+    pub formation: Formation,
+    /// how many mobs and decoys there were when the spawner was activated,
+    /// so that a formation can make room for all of them
+    #[serde(skip)]
+    pub wave_size: u32,
+    /// the color of the mobs, if not the usual one
+    #[serde(default)]
+    pub color: Option<Color>,
+    /// how much the mobs sway in place, if at all
+    #[serde(default)]
+    pub wobble: Option<f32>,
+}
+
+/// The arrangement of the mobs of a spawner
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+pub enum Formation {
+    /// pseudorandom positions
+    #[default]
+    Scattered,
+    /// side by side, in a horizontal line
+    Line,
+    /// in a V shape, with the tip at the bottom
+    Vee,
+    /// in rows of four
+    Grid,
+}
+
+impl Formation {
+    /// the widest that a line or a V can be
+    const LINE_WIDTH: f32 = 7.;
+    /// horizontal space between mobs in a line or a V,
+    /// unless there are too many of them to fit
+    const LINE_SPACING: f32 = 1.75;
+    /// how many mobs fit in a row of the grid
+    const GRID_COLUMNS: u32 = 4;
+    /// the tallest that the grid can be
+    const GRID_HEIGHT: f32 = 3.;
+    /// vertical space between rows of the grid,
+    /// unless there are too many of them to fit
+    const GRID_ROW_SPACING: f32 = 1.5;
+
+    /// The space between evenly spread slots,
+    /// with no more than the given spacing
+    /// and no wider than the given extent in total.
+    fn spacing(slots: u32, spacing: f32, extent: f32) -> f32 {
+        if slots > 1 {
+            (extent / (slots - 1) as f32).min(spacing)
+        } else {
+            0.
+        }
+    }
+
+    /// The horizontal and vertical offsets of a mob from the spawner,
+    /// given its position in the wave and the size of the wave,
+    /// or `None` if the offsets should be random.
+    ///
+    /// The slots are spread over the whole wave,
+    /// so that no two mobs of the wave take the same place.
+    pub fn offset(self, index: u32, size: u32) -> Option<(f32, f32)> {
+        let size = size.max(index + 1);
+        // the slot in a line or a V, centered on the spawner
+        let slot = index as f32 - (size - 1) as f32 / 2.;
+        let spacing = Self::spacing(size, Self::LINE_SPACING, Self::LINE_WIDTH);
+        match self {
+            Formation::Scattered => None,
+            Formation::Line => Some((slot * spacing, 0.)),
+            Formation::Vee => {
+                // from the tip at the bottom to the ends at the top
+                let rise = if size > 1 {
+                    slot.abs() / ((size - 1) as f32 / 2.)
+                } else {
+                    0.
+                };
+                Some((slot * spacing, rise * 2. - 1.5))
+            }
+            Formation::Grid => {
+                let rows = size.div_ceil(Self::GRID_COLUMNS);
+                let row_spacing = Self::spacing(rows, Self::GRID_ROW_SPACING, Self::GRID_HEIGHT);
+                let column = (index % Self::GRID_COLUMNS) as f32;
+                let row = (index / Self::GRID_COLUMNS) as f32;
+                Some((
+                    (column - (Self::GRID_COLUMNS - 1) as f32 / 2.) * 2.,
+                    (row - (rows - 1) as f32 / 2.) * row_spacing,
+                ))
+            }
+        }
+    }
+}
+
+/// Identifier of a mob spawner within a level,
+/// so that waves can be chained into a sequence
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+pub struct SpawnerId(pub u8);
+
+/// Component for decoy mobs,
+/// which cannot be defeated
+/// and do not need to be defeated for the wave to be cleared.
+///
+/// They look slightly different from the other mobs.
+#[derive(Debug, Component)]
+pub struct Decoy;
+
+/// Component for mobs spawned by an identified spawner
+#[derive(Debug, Component)]
+pub struct SpawnedBy(pub SpawnerId);
+
+/// Resource keeping track of which identified mob spawners
+/// were activated and cleared in the current level
+#[derive(Debug, Default, Resource)]
+pub struct SpawnerProgress {
+    activated: Vec<SpawnerId>,
+    cleared: Vec<SpawnerId>,
+}
+
+impl SpawnerProgress {
+    pub fn reset(&mut self) {
+        self.activated.clear();
+        self.cleared.clear();
+    }
+
+    pub fn is_activated(&self, id: SpawnerId) -> bool {
+        self.activated.contains(&id)
+    }
+
+    pub fn is_cleared(&self, id: SpawnerId) -> bool {
+        self.cleared.contains(&id)
+    }
 }
 
 /// Component for things containing some form of randomness.
@@ -74,8 +240,101 @@ impl MobSpawner {
             target_rule,
             active: false,
             last_spawn: 0.,
+            id: None,
+            after: None,
+            decoys: 0,
+            reuse_failed: false,
+            movement_speed: None,
+            attack_interval: None,
+            robustness: Self::default_robustness(),
+            formation: Formation::default(),
+            wave_size: 0,
+            color: None,
+            wobble: None,
         }
     }
+
+    /// mobs are destroyed by a single effective attack unless said otherwise
+    fn default_robustness() -> u32 {
+        1
+    }
+
+    /// give this spawner an identifier
+    /// so that other spawners can wait for it
+    pub fn with_id(mut self, id: SpawnerId) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    /// only activate this spawner
+    /// once the spawner with the given identifier is cleared
+    pub fn after(mut self, id: SpawnerId) -> Self {
+        self.after = Some(id);
+        self
+    }
+
+    /// mix the given number of decoys in with the mobs
+    pub fn with_decoys(mut self, decoys: u32) -> Self {
+        self.decoys = decoys;
+        self
+    }
+
+    /// In practice mode,
+    /// spawn the targets which the player recently failed to attack
+    /// (if they can be attacked with the weapons at hand),
+    /// falling back to the usual options once there are none left.
+    pub fn reusing_failed(mut self) -> Self {
+        self.reuse_failed = true;
+        self
+    }
+
+    /// make the mobs (but not the decoys) advance towards the player
+    /// at the given speed
+    pub fn advancing(mut self, speed: f32) -> Self {
+        self.movement_speed = Some(speed);
+        self
+    }
+
+    /// make the mobs (but not the decoys) attack the player
+    /// every given number of seconds while they are in view
+    pub fn attacking_every(mut self, interval: f32) -> Self {
+        self.attack_interval = Some(interval);
+        self
+    }
+
+    /// make the mobs (but not the decoys) take the given number
+    /// of effective attacks to be destroyed
+    pub fn robust(mut self, robustness: u32) -> Self {
+        self.robustness = robustness.max(1);
+        self
+    }
+
+    /// place the mobs in the given formation
+    pub fn in_formation(mut self, formation: Formation) -> Self {
+        self.formation = formation;
+        self
+    }
+
+    /// give the mobs (but not the decoys) a color of their own,
+    /// so that the wave stands out
+    /// (fraction colors take precedence if enabled)
+    pub fn colored(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// make the mobs (but not the decoys) sway in place
+    /// with the given amplitude
+    pub fn wobbling(mut self, amplitude: f32) -> Self {
+        self.wobble = Some(amplitude);
+        self
+    }
+
+    /// readjust the last spawn time
+    /// so that the next mob is spawned after the given delay
+    pub fn hurry(&mut self, time: f32, delay: f32) {
+        self.last_spawn = time - self.spawn_interval + delay;
+    }
 }
 
 #[derive(Bundle)]
@@ -100,21 +359,46 @@ pub fn process_spawner_trigger(
     mut cmd: Commands,
     time: Res<LiveTime>,
     mut q: Query<(Entity, &mut MobSpawner, &PhaseTrigger)>,
+    all_spawners_q: Query<&MobSpawner>,
     player_q: Query<&Transform, With<Player>>,
+    audio_handles: Res<AudioHandles>,
+    mut progress: ResMut<SpawnerProgress>,
 ) {
     let Ok(player_transform) = player_q.get_single() else {
         return;
     };
 
     let time = time.elapsed_seconds();
+    let mut activated = false;
     for (entity, mut spawner, phase) in q.iter_mut() {
-        if phase.should_trigger(&player_transform.translation) {
-            spawner.active = true;
-            spawner.last_spawn = time - spawner.spawn_interval;
+        if !phase.should_trigger(&player_transform.translation) {
+            continue;
+        }
+        // wait for the previous wave to be cleared
+        // (if that wave is in the level at all)
+        if let Some(dependency) = spawner.after {
+            let dependency_present = progress.is_activated(dependency)
+                || all_spawners_q.iter().any(|s| s.id == Some(dependency));
+            if dependency_present && !progress.is_cleared(dependency) {
+                continue;
+            }
+        }
 
-            // remove phase trigger
-            cmd.entity(entity).remove::<PhaseTrigger>();
+        spawner.active = true;
+        spawner.last_spawn = time - spawner.spawn_interval;
+        spawner.wave_size = spawner.count + spawner.decoys;
+        activated = true;
+        if let Some(id) = spawner.id {
+            progress.activated.push(id);
         }
+
+        // remove phase trigger
+        cmd.entity(entity).remove::<PhaseTrigger>();
+    }
+
+    // let the player know that a fight is coming (only once)
+    if activated {
+        audio_handles.play_stinger(&mut cmd);
     }
 }
 
@@ -122,24 +406,44 @@ pub fn process_spawner_trigger(
 /// relative to the mob spawner position
 const MOB_SPAWN_Z_OFFSET: f32 = 12.;
 
+/// time offset between the wobbles of consecutive mobs in a wave, in seconds
+const MOB_WOBBLE_PHASE_STEP: f32 = 1.3;
+
 /// system that makes active mob spawners spawn mobs
 pub fn spawn_mobs_on_time(
     mut cmd: Commands,
     time: Res<LiveTime>,
-    mob_assets: Res<MobAssets>,
+    mut mob_assets: ResMut<MobAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    game_settings: Res<GameSettings>,
+    mut fraction_colors: ResMut<FractionColors>,
+    mut failed_targets: ResMut<FailedTargets>,
     mut mob_spawner_q: Query<(&mut MobSpawner, &mut Randomness, &Transform)>,
 ) {
     let time = time.elapsed_seconds();
+    let num_style = NumStyle::from(&*game_settings);
     for (mut spawner, mut random, transform) in &mut mob_spawner_q {
         if !spawner.active {
             continue;
         }
+        if spawner.target_options.is_empty() {
+            // nothing to spawn, let the spawner be destroyed
+            spawner.count = 0;
+            continue;
+        }
         let relative_elapsed = time - spawner.last_spawn;
         if relative_elapsed >= spawner.spawn_interval {
             // spawn a mob
-            // use an RNG to pseudorandomize the position
-            let rel_x = (random.rng.next_range(0..14_u32) as f32 - 7.) / 2.;
-            let rel_y = random.rng.next_range(0..5_u32) as f32 - 2.5;
+            // in formation, or using an RNG to pseudorandomize the position
+            let remaining = spawner.count + spawner.decoys;
+            let size = spawner.wave_size.max(remaining);
+            let index = size - remaining;
+            let (rel_x, rel_y) = spawner.formation.offset(index, size).unwrap_or_else(|| {
+                (
+                    (random.rng.next_range(0..14_u32) as f32 - 7.) / 2.,
+                    random.rng.next_range(0..5_u32) as f32 - 2.5,
+                )
+            });
             let rel_z = if spawner.count % 2 == 0 {
                 MOB_SPAWN_Z_OFFSET + (spawner.count / 2) as f32 * 0.2
             } else {
@@ -153,15 +457,90 @@ pub fn spawn_mobs_on_time(
             // randomize num choice
             let new_num = spawner.target_options[choice as usize];
 
-            spawn_mob(
+            // decide whether to spawn a decoy instead,
+            // spreading them across the wave
+            let spawn_decoy = spawner.decoys > 0
+                && random.rng.next_range(0..spawner.count + spawner.decoys) < spawner.decoys;
+            if spawn_decoy {
+                let decoy_entity = spawn_mob(
+                    &mut cmd,
+                    &mob_assets,
+                    new_pos,
+                    Target {
+                        num: new_num,
+                        rule: TargetRule::Invulnerable,
+                        accumulated: Num::ZERO,
+                    },
+                    num_style,
+                );
+                cmd.entity(decoy_entity)
+                    .insert((Decoy, mob_assets.decoy_material.clone()));
+
+                spawner.last_spawn += spawner.spawn_interval;
+                spawner.decoys -= 1;
+                continue;
+            }
+
+            // give the player a second chance at a target failed before
+            let reused = if spawner.reuse_failed && game_settings.practice_mode {
+                failed_targets.take_solvable()
+            } else {
+                None
+            };
+            let (new_num, rule) = reused.unwrap_or((new_num, spawner.target_rule));
+
+            let mob_entity = spawn_mob(
                 &mut cmd,
                 &mob_assets,
                 new_pos,
                 Target {
                     num: new_num,
-                    rule: spawner.target_rule,
+                    rule,
+                    accumulated: Num::ZERO,
                 },
+                num_style,
             );
+            if let Some(id) = spawner.id {
+                cmd.entity(mob_entity).insert(SpawnedBy(id));
+            }
+            if let Some(speed) = spawner.movement_speed {
+                cmd.entity(mob_entity).insert(MobMovement { speed });
+            }
+            if let Some(amplitude) = spawner.wobble {
+                // desynchronize the mobs of the wave
+                // (without taking from the RNG, so that the rest stays the same)
+                cmd.entity(mob_entity).insert(Wobbles {
+                    amplitude,
+                    frequency: Vec2::new(1.2, 1.6),
+                    phase: index as f32 * MOB_WOBBLE_PHASE_STEP,
+                    ..default()
+                });
+            }
+            if spawner.robustness > 1 {
+                let health = spawner.robustness as f32;
+                cmd.entity(mob_entity).insert(Health {
+                    value: health,
+                    max: health,
+                });
+            }
+            if let Some(interval) = spawner.attack_interval {
+                // the first attack also takes a full interval
+                cmd.entity(mob_entity).insert(AttackCooldown {
+                    value: interval,
+                    max: interval,
+                    locked: false,
+                });
+            }
+            if game_settings.fraction_colors {
+                let group = fraction_colors.group_of(new_num);
+                // past the last color, mobs keep the default material
+                if let Some(material) = mob_assets.group_material(group) {
+                    cmd.entity(mob_entity).insert(material);
+                }
+            } else if let Some(color) = spawner.color {
+                cmd.entity(mob_entity)
+                    .insert(mob_assets.colored_material(color, &mut materials));
+            }
 
             // update spawner properties
             spawner.last_spawn += spawner.spawn_interval;
@@ -170,11 +549,29 @@ pub fn spawn_mobs_on_time(
     }
 }
 
+/// system that marks activated spawners as cleared
+/// once they are gone and all of their mobs were defeated
+pub fn track_cleared_spawners(
+    mut progress: ResMut<SpawnerProgress>,
+    spawner_q: Query<&MobSpawner>,
+    mob_q: Query<&SpawnedBy, With<Target>>,
+) {
+    let newly_cleared: Vec<SpawnerId> = progress
+        .activated
+        .iter()
+        .copied()
+        .filter(|id| !progress.is_cleared(*id))
+        .filter(|id| !spawner_q.iter().any(|spawner| spawner.id == Some(*id)))
+        .filter(|id| !mob_q.iter().any(|spawned_by| spawned_by.0 == *id))
+        .collect();
+    progress.cleared.extend(newly_cleared);
+}
+
 /// system that makes mob spawners spawn immediately when there are no targets left
 pub fn hurry_mob_spawners_on_no_targets(
     time: Res<LiveTime>,
     mut mob_spawner_q: Query<(&mut MobSpawner, &mut Randomness, &Transform)>,
-    target_q: Query<Entity, With<Target>>,
+    target_q: Query<Entity, (With<Target>, Without<Decoy>)>,
     mut events: EventReader<TargetDestroyed>,
 ) {
     // only act upon the target destroyed event
@@ -191,7 +588,150 @@ pub fn hurry_mob_spawners_on_no_targets(
     // so that a mob is spawned shortly after
     for (mut spawner, _, _) in &mut mob_spawner_q.iter_mut() {
         if spawner.active && spawner.count > 0 {
-            spawner.last_spawn = time.elapsed_seconds() - spawner.spawn_interval + 0.15;
+            spawner.hurry(time.elapsed_seconds(), 0.15);
+            break;
+        }
+    }
+}
+
+/// Component for mobs which advance towards the player
+#[derive(Debug, Component)]
+pub struct MobMovement {
+    /// distance covered per second
+    pub speed: f32,
+}
+
+/// how close along the corridor an advancing mob must be
+/// to reach the player
+const MOB_REACH_DISTANCE: f32 = 2.;
+
+/// system that moves advancing mobs towards the player.
+///
+/// A mob reaching the player hurts them and is destroyed.
+pub fn advance_mobs(
+    mut cmd: Commands,
+    time: Res<Time>,
+    mut mob_q: Query<(Entity, &MobMovement, &mut Transform), (With<Target>, Without<Player>)>,
+    player_q: Query<&Transform, With<Player>>,
+    mut damage_player_events: EventWriter<DamagePlayer>,
+    mut target_destroyed_events: EventWriter<TargetDestroyed>,
+) {
+    let Ok(player_transform) = player_q.get_single() else {
+        return;
+    };
+    let player_z = player_transform.translation.z;
+    let delta = time.delta_seconds();
+
+    for (entity, movement, mut transform) in &mut mob_q {
+        let distance = player_z - transform.translation.z;
+        if distance.abs() > MOB_REACH_DISTANCE {
+            transform.translation.z += distance.signum() * movement.speed * delta;
+            continue;
+        }
+
+        damage_player_events.send(DamagePlayer {
+            damage: 1.,
+            source: Some(transform.translation),
+        });
+        cmd.entity(entity)
+            .remove::<(Target, MobMovement)>()
+            .insert((Collapsing::default(), TimeToLive(0.5)));
+        target_destroyed_events.send(TargetDestroyed);
+    }
+}
+
+/// how far behind the player a mob can be before it is removed
+const MOB_BEHIND_MARGIN: f32 = 8.;
+
+/// system that despawns mobs (and their icons)
+/// which were left far behind the player.
+///
+/// A mob which was still a live target counts as destroyed,
+/// so that the player does not wait for it to move on.
+pub fn despawn_mobs_behind_player(
+    mut cmd: Commands,
+    mob_q: Query<
+        (
+            Entity,
+            &Transform,
+            Option<&HasIcon>,
+            Has<Target>,
+            Has<Decoy>,
+        ),
+        With<Mob>,
+    >,
+    player_q: Query<&Transform, With<Player>>,
+    mut target_destroyed_events: EventWriter<TargetDestroyed>,
+) {
+    let Ok(player_transform) = player_q.get_single() else {
+        return;
+    };
+    let limit_z = player_transform.translation.z - MOB_BEHIND_MARGIN;
+
+    for (entity, transform, has_icon, is_target, is_decoy) in &mob_q {
+        if transform.translation.z >= limit_z {
+            continue;
+        }
+        cmd.entity(entity).despawn_recursive();
+        if let Some(HasIcon(icon_entity)) = has_icon {
+            cmd.entity(*icon_entity).despawn_recursive();
+        }
+        if is_target && !is_decoy {
+            target_destroyed_events.send(TargetDestroyed);
+        }
+    }
+}
+
+/// system that makes mobs with an attack cooldown
+/// attack the player whenever the cooldown is over,
+/// as long as they can be seen
+/// (see [`MobSpawner::attacking_every`])
+pub fn mob_attacks(
+    mut mob_q: Query<(&GlobalTransform, &mut AttackCooldown), (With<Mob>, With<Target>)>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<CameraMarker>>,
+    mut damage_player_events: EventWriter<DamagePlayer>,
+) {
+    let Ok((camera, camera_transform)) = camera_q.get_single() else {
+        return;
+    };
+
+    for (transform, mut cooldown) in &mut mob_q {
+        if cooldown.value > 0. {
+            continue;
+        }
+        let position = transform.translation();
+        let in_view = camera
+            .world_to_ndc(camera_transform, position)
+            .is_some_and(|ndc| ndc.x.abs() <= 1. && ndc.y.abs() <= 1. && ndc.z > 0.);
+        if !in_view {
+            continue;
+        }
+
+        // the sound is played when the damage is processed
+        damage_player_events.send(DamagePlayer {
+            damage: 1.,
+            source: Some(position),
+        });
+        cooldown.value = cooldown.max;
+    }
+}
+
+/// system that makes an active mob spawner spawn its next mob right away
+/// when the player presses the skip key (N).
+///
+/// Only meant for practice mode.
+pub fn skip_to_next_spawn(
+    input: Res<ButtonInput<KeyCode>>,
+    time: Res<LiveTime>,
+    mut mob_spawner_q: Query<&mut MobSpawner>,
+) {
+    if !input.just_pressed(KeyCode::KeyN) {
+        return;
+    }
+
+    for mut spawner in &mut mob_spawner_q {
+        if spawner.active && spawner.count > 0 {
+            spawner.hurry(time.elapsed_seconds(), 0.);
             break;
         }
     }
@@ -218,6 +758,74 @@ pub struct MobBundle {
 pub struct MobAssets {
     mesh: Handle<Mesh>,
     material: Handle<StandardMaterial>,
+    /// a slightly duller material for decoys
+    decoy_material: Handle<StandardMaterial>,
+    /// materials for telling groups of equivalent numbers apart
+    group_materials: Vec<Handle<StandardMaterial>>,
+    /// materials for the mob spawners with a color of their own,
+    /// created as they are needed and reused afterwards
+    colored_materials: Vec<(Color, Handle<StandardMaterial>)>,
+}
+
+impl MobAssets {
+    /// the colors for each group of equivalent numbers
+    const GROUP_COLORS: [Color; 6] = [
+        Color::srgb(0.1, 0.55, 0.6),
+        Color::srgb(0.8, 0.45, 0.05),
+        Color::srgb(0.15, 0.3, 0.8),
+        Color::srgb(0.25, 0.6, 0.15),
+        Color::srgb(0.75, 0.65, 0.1),
+        Color::srgb(0.7, 0.1, 0.1),
+    ];
+
+    /// the material for the given group of equivalent numbers,
+    /// if there are still colors for it
+    /// (reusing them would make different numbers look alike)
+    fn group_material(&self, group: usize) -> Option<Handle<StandardMaterial>> {
+        self.group_materials.get(group).cloned()
+    }
+
+    /// the material for mobs of the given color,
+    /// only adding a new one the first time the color is used
+    fn colored_material(
+        &mut self,
+        color: Color,
+        materials: &mut Assets<StandardMaterial>,
+    ) -> Handle<StandardMaterial> {
+        if let Some((_, material)) = self.colored_materials.iter().find(|(c, _)| *c == color) {
+            return material.clone();
+        }
+        let material = materials.add(StandardMaterial {
+            base_color: color,
+            ..Default::default()
+        });
+        self.colored_materials.push((color, material.clone()));
+        material
+    }
+}
+
+/// Resource assigning a color group to each distinct value
+/// among the mobs spawned in the level,
+/// so that equivalent fractions share the same color
+#[derive(Debug, Default, Resource)]
+pub struct FractionColors {
+    values: Vec<Num>,
+}
+
+impl FractionColors {
+    pub fn reset(&mut self) {
+        self.values.clear();
+    }
+
+    /// the color group of the given number
+    fn group_of(&mut self, num: Num) -> usize {
+        let reduced = num.reduced();
+        if let Some(i) = self.values.iter().position(|v| *v == reduced) {
+            return i;
+        }
+        self.values.push(reduced);
+        self.values.len() - 1
+    }
 }
 
 const TARGET_SIZE: f32 = 2.75;
@@ -236,11 +844,39 @@ impl FromWorld for MobAssets {
             ..Default::default()
         });
 
-        Self { mesh, material }
+        let decoy_material = materials.add(StandardMaterial {
+            base_color: Color::srgb(0.42, 0.2, 0.45),
+            perceptual_roughness: 0.9,
+            ..Default::default()
+        });
+
+        let group_materials = MobAssets::GROUP_COLORS
+            .into_iter()
+            .map(|base_color| {
+                materials.add(StandardMaterial {
+                    base_color,
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        Self {
+            mesh,
+            material,
+            decoy_material,
+            group_materials,
+            colored_materials: Vec::new(),
+        }
     }
 }
 
-pub fn spawn_mob(cmd: &mut Commands, assets: &MobAssets, position: Vec3, target: Target) {
+pub fn spawn_mob(
+    cmd: &mut Commands,
+    assets: &MobAssets,
+    position: Vec3,
+    target: Target,
+    num_style: NumStyle,
+) -> Entity {
     let num = target.num;
     let target_entity = cmd
         .spawn(MobBundle {
@@ -273,8 +909,247 @@ pub fn spawn_mob(cmd: &mut Commands, assets: &MobAssets, position: Vec3, target:
         .id();
 
     // spawn icon
-    let icon_entity = spawn_target_icon(cmd, target_entity, num);
+    let icon_entity = spawn_target_icon(cmd, target_entity, num, num_style);
 
     // add reverse reference
     cmd.entity(target_entity).insert(HasIcon(icon_entity));
+
+    target_entity
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+    use tinyrand::{Seeded, SplitMix};
+
+    use super::*;
+    use crate::live::weapon::update_cooldown;
+
+    /// a world with the time and the events of mobs and the player
+    fn test_world() -> World {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<Events<DamagePlayer>>();
+        world.init_resource::<Events<TargetDestroyed>>();
+        world
+    }
+
+    fn advance_time(world: &mut World, seconds: f32) {
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(seconds));
+    }
+
+    #[test]
+    fn advancing_mob_moves_towards_the_player() {
+        let mut world = test_world();
+        world.spawn((Player, Transform::from_xyz(0., 0., 20.)));
+        let mob = world
+            .spawn((
+                Target::default(),
+                MobMovement { speed: 2. },
+                Transform::from_xyz(0., 0., 0.),
+            ))
+            .id();
+
+        let mut last_z = 0.;
+        for _ in 0..4 {
+            advance_time(&mut world, 0.5);
+            world.run_system_once(advance_mobs);
+            let z = world.get::<Transform>(mob).unwrap().translation.z;
+            assert!(z > last_z, "mob did not advance: {z} <= {last_z}");
+            last_z = z;
+        }
+        assert_eq!(last_z, 4.);
+        assert!(world.resource::<Events<DamagePlayer>>().is_empty());
+    }
+
+    #[test]
+    fn mob_attacks_once_its_interval_elapses() {
+        let mut world = test_world();
+        // with no projection set up yet,
+        // the camera sees whatever is just in front of it
+        world.spawn((CameraMarker, Camera::default(), GlobalTransform::default()));
+        world.spawn((
+            Mob,
+            Target::default(),
+            GlobalTransform::from_translation(Vec3::new(0., 0., 0.5)),
+            AttackCooldown {
+                value: 2.,
+                max: 2.,
+                locked: false,
+            },
+        ));
+
+        let mut attacks = vec![];
+        for _ in 0..3 {
+            advance_time(&mut world, 1.);
+            world.run_system_once(update_cooldown);
+            world.run_system_once(mob_attacks);
+            attacks.push(world.resource::<Events<DamagePlayer>>().len());
+        }
+        // nothing in the first second, one attack after two,
+        // then none until the interval elapses again
+        assert_eq!(attacks, vec![0, 1, 1]);
+    }
+
+    #[test]
+    fn line_formation_is_evenly_spaced() {
+        for size in [1, 2, 5, 6, 10] {
+            let xs: Vec<f32> = (0..size)
+                .map(|index| Formation::Line.offset(index, size).unwrap().0)
+                .collect();
+            // centered on the spawner
+            let sum: f32 = xs.iter().sum();
+            assert!(sum.abs() < 1e-4, "line of {size} is off center: {xs:?}");
+            // with the same gap between any two neighbors
+            let gaps: Vec<f32> = xs.windows(2).map(|w| w[1] - w[0]).collect();
+            for gap in &gaps {
+                assert!(
+                    *gap > 0.,
+                    "line of {size} has mobs on top of each other: {xs:?}"
+                );
+                assert!(
+                    (gap - gaps[0]).abs() < 1e-4,
+                    "line of {size} is uneven: {xs:?}"
+                );
+            }
+            // and never wider than the corridor allows
+            assert!(xs
+                .iter()
+                .all(|x| x.abs() <= Formation::LINE_WIDTH / 2. + 1e-4));
+        }
+    }
+
+    #[test]
+    fn spawner_colors_get_their_own_materials() {
+        let mut world = World::new();
+        world.init_resource::<Assets<Mesh>>();
+        world.init_resource::<Assets<StandardMaterial>>();
+        let mut mob_assets = MobAssets::from_world(&mut world);
+        let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+        let red = Color::srgb(0.75, 0.15, 0.1);
+        let orange = Color::srgb(0.8, 0.45, 0.1);
+
+        let red_material = mob_assets.colored_material(red, &mut materials);
+        let orange_material = mob_assets.colored_material(orange, &mut materials);
+        assert_ne!(red_material, orange_material);
+        assert_eq!(materials.get(&red_material).unwrap().base_color, red);
+        assert_eq!(materials.get(&orange_material).unwrap().base_color, orange);
+
+        // the same color again takes no new material
+        let count = materials.len();
+        assert_eq!(
+            mob_assets.colored_material(red, &mut materials),
+            red_material
+        );
+        assert_eq!(materials.len(), count);
+    }
+
+    #[test]
+    fn fraction_colors_do_not_repeat() {
+        let mut world = World::new();
+        world.init_resource::<Assets<Mesh>>();
+        world.init_resource::<Assets<StandardMaterial>>();
+        let mob_assets = MobAssets::from_world(&mut world);
+        let mut fraction_colors = FractionColors::default();
+
+        // equivalent fractions share a color
+        assert_eq!(
+            fraction_colors.group_of(Num::new_raw(2, 4)),
+            fraction_colors.group_of(Num::new(1, 2))
+        );
+        fraction_colors.reset();
+
+        let materials: Vec<_> = (1..=MobAssets::GROUP_COLORS.len() as i16 + 2)
+            .map(|n| mob_assets.group_material(fraction_colors.group_of(Num::from_integer(n))))
+            .collect();
+        let (colored, rest) = materials.split_at(MobAssets::GROUP_COLORS.len());
+        for (i, material) in colored.iter().enumerate() {
+            let material = material.as_ref().expect("should have a color");
+            assert!(
+                !colored[..i].contains(&Some(material.clone())),
+                "color {i} was used before"
+            );
+        }
+        assert_eq!(rest, [None, None]);
+    }
+
+    #[test]
+    fn mobs_far_behind_the_player_are_removed() {
+        let mut world = test_world();
+        world.spawn((Player, Transform::from_xyz(0., 0., 20.)));
+        let spawn_mob = |world: &mut World, z: f32| {
+            let icon = world.spawn_empty().id();
+            let mob = world
+                .spawn((Mob, Target::default(), Transform::from_xyz(0., 0., z)))
+                .id();
+            world.entity_mut(mob).insert(HasIcon(icon));
+            (mob, icon)
+        };
+        let (ahead, ahead_icon) = spawn_mob(&mut world, 30.);
+        let (close_behind, _) = spawn_mob(&mut world, 20. - MOB_BEHIND_MARGIN / 2.);
+        let (far_behind, far_behind_icon) = spawn_mob(&mut world, 20. - MOB_BEHIND_MARGIN * 2.);
+        let (decoy, _) = spawn_mob(&mut world, 20. - MOB_BEHIND_MARGIN * 2.);
+        world.entity_mut(decoy).insert(Decoy);
+
+        world.run_system_once(despawn_mobs_behind_player);
+
+        assert!(world.get_entity(ahead).is_some());
+        assert!(world.get_entity(ahead_icon).is_some());
+        assert!(world.get_entity(close_behind).is_some());
+        assert!(world.get_entity(far_behind).is_none());
+        assert!(world.get_entity(far_behind_icon).is_none());
+        assert!(world.get_entity(decoy).is_none());
+        // the target left behind counts as destroyed, but not the decoy
+        assert_eq!(world.resource::<Events<TargetDestroyed>>().len(), 1);
+    }
+
+    /// let the spawner spawn all of its mobs,
+    /// returning how many targets and decoys came out of it
+    fn spawn_wave(spawner: MobSpawner) -> (usize, usize) {
+        let mut world = World::new();
+        world.init_resource::<Assets<Mesh>>();
+        world.init_resource::<Assets<StandardMaterial>>();
+        world.init_resource::<MobAssets>();
+        world.init_resource::<GameSettings>();
+        world.init_resource::<FractionColors>();
+        world.init_resource::<FailedTargets>();
+        world.init_resource::<LiveTime>();
+        world.spawn((
+            MobSpawner {
+                active: true,
+                spawn_interval: 0.,
+                ..spawner
+            },
+            Randomness {
+                rng: SplitMix::seed(1),
+            },
+            Transform::default(),
+        ));
+        for _ in 0..20 {
+            world.run_system_once(spawn_mobs_on_time);
+        }
+        let decoys = world
+            .query_filtered::<&Target, With<Decoy>>()
+            .iter(&world)
+            .inspect(|target| assert_eq!(target.rule, TargetRule::Invulnerable))
+            .count();
+        let targets = world
+            .query_filtered::<(), (With<Target>, Without<Decoy>)>()
+            .iter(&world)
+            .count();
+        (targets, decoys)
+    }
+
+    #[test]
+    fn decoys_are_only_mixed_in_when_asked_for() {
+        assert_eq!(spawn_wave(MobSpawner::new(5, 1., [6, 8])), (5, 0));
+        assert_eq!(
+            spawn_wave(MobSpawner::new(5, 1., [6, 8]).with_decoys(2)),
+            (5, 2)
+        );
+    }
 }
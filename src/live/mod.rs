@@ -1,43 +1,61 @@
 //! The live action module, containing the active game logic
-use std::fmt;
+use std::{fmt, time::Duration};
 
-use bevy::{prelude::*, time::Stopwatch, ui::FocusPolicy};
+use bevy::{
+    prelude::*,
+    time::Stopwatch,
+    ui::FocusPolicy,
+    window::{CursorGrabMode, PrimaryWindow},
+};
 use bevy_mod_picking::{
     events::{Click, Pointer},
     prelude::*,
 };
 
+mod achievement;
 pub mod collision;
+mod hitstop;
 mod icon;
 mod interlude;
 mod levels;
+mod memory;
 mod mob;
 mod phase;
 mod player;
 mod projectile;
+mod save;
 mod scene;
+mod stats;
 mod weapon;
+mod wheel;
 
+use hitstop::HitStop;
 use interlude::AdvanceInterlude;
-use mob::MobSpawner;
+use mob::{Decoy, FractionColors, MobSpawner, SpawnerProgress};
 use phase::PhaseTrigger;
 use player::{
     process_attacks, process_damage_player, process_player_movement, update_player_cooldown_meter,
-    update_player_health_meter, DamagePlayer, Player, PlayerMovement, TargetDestroyed,
+    update_player_health_meter, AttackResolved, DamagePlayer, Player, PlayerMovement,
+    TargetDestroyed,
 };
 use projectile::ProjectileAssets;
-use weapon::{ChangeWeapon, PlayerAttack, WeaponCubeAssets};
+use stats::{Combo, FailedTargets, LevelDeaths, LevelStats, RecentAttacks, RunStats, Score};
+use weapon::{
+    ChangeWeapon, ChargeState, PlayerAttack, PlayerWeapon, PreferredWeapon, WeaponCubeAssets,
+};
 // re-export some stuff
 pub use weapon::TriggerWeapon;
 
 use crate::{
     assets::{AudioHandles, DefaultFont},
+    cheat::Cheats,
     despawn_all_at,
     effect::{
         self, apply_collapse, apply_rotation, apply_velocity, stay_on_floor, time_to_live,
-        Collapsing,
+        Collapsing, TimeToLive, Velocity,
     },
     logic::{Num, TargetRule},
+    persist::PersistentData,
     postprocess::PostProcessSettings,
     structure::Fork,
     ui::{button_system, spawn_button_in_group, spawn_button_with_style, MeterBundle, Sizes},
@@ -46,7 +64,11 @@ use crate::{
 
 use super::CameraMarker;
 
-pub use levels::CurrentLevel;
+pub use achievement::Achievement;
+pub use interlude::InterludeMode;
+pub use levels::{CurrentLevel, Ending, LevelId, LevelWarp};
+pub use save::{ResumeRun, SaveState};
+pub use stats::RunSummary;
 
 /// Running or paused
 #[derive(SubStates, Default, Debug, Clone, PartialEq, Eq, Hash)]
@@ -76,7 +98,14 @@ impl Plugin for LiveActionPlugin {
             // live game setup
             .add_systems(
                 OnEnter(AppState::Live),
-                (reset_game, scene::setup_scene, setup_ui, start_running).chain(),
+                (
+                    reset_game,
+                    scene::setup_scene,
+                    setup_ui,
+                    start_running,
+                    save::restore_saved_run,
+                )
+                    .chain(),
             )
             // partial live game take-down when exiting Running and entering Loading
             .add_systems(
@@ -123,20 +152,54 @@ impl Plugin for LiveActionPlugin {
             // live game take-down
             .add_systems(OnExit(AppState::Live), despawn_all_at::<OnLive>)
             .add_systems(OnEnter(LiveState::Defeat), enter_defeat)
+            .add_systems(
+                OnEnter(LiveState::Running),
+                (mark_running_since, confine_cursor),
+            )
+            .add_systems(
+                OnExit(LiveState::Running),
+                (
+                    release_cursor,
+                    wheel::close_weapon_wheel,
+                    hitstop::end_hit_stop,
+                ),
+            )
+            .add_systems(OnEnter(LiveState::Paused), update_pause_info)
             // systems which should function regardless of the game state
             .add_systems(Update, pause_on_esc.run_if(in_state(AppState::Live)))
+            .add_systems(
+                Update,
+                weapon::rebuild_weapon_buttons
+                    .run_if(in_state(AppState::Live))
+                    .run_if(resource_changed::<GameSettings>),
+            )
+            .add_systems(
+                Update,
+                structure::apply_focus_mode.run_if(in_state(AppState::Live)),
+            )
             // systems that only run when the game is running
             .add_systems(
                 Update,
                 (
                     update_player_cooldown_meter,
                     update_player_health_meter,
-                    effect::apply_wobble,
-                    effect::fade_away,
+                    (effect::apply_wobble, effect::apply_camera_shake),
+                    effect::fade_material,
                     effect::apply_rotation,
                     icon::update_icon_opacity,
-                    weapon::update_cooldown,
-                    weapon::weapon_keyboard_input,
+                    icon::update_icon_flash,
+                    icon::highlight_urgent_target,
+                    icon::update_attack_preview,
+                    fade_onward_cue,
+                    player::fade_damage_indicator,
+                    update_distance_text,
+                    memory::hide_memory_numbers,
+                    (weapon::update_cooldown, weapon::update_charge),
+                    (
+                        weapon::weapon_keyboard_input,
+                        weapon::cycle_weapon_selection,
+                        weapon::scroll_weapon_selection,
+                    ),
                     weapon::weapon_button_action,
                     weapon::process_weapon_button_selected,
                     weapon::process_weapon_button_deselected,
@@ -149,45 +212,90 @@ impl Plugin for LiveActionPlugin {
                         .chain(),
                     (
                         icon::clear_icons_of_destroyed_things,
+                        icon::update_target_icon_on_change,
+                        icon::update_sum_progress,
                         apply_collapse,
                         time_to_live,
+                        icon::animate_floating_text,
+                        effect::fade_ui,
                         process_end_of_corridor,
                         mob::process_spawner_trigger,
+                        memory::process_memory_trigger,
                         interlude::process_interlude_trigger,
+                        interlude::process_banner_trigger,
                         button_system::<Decision>,
                         decision_action,
                     )
                         .chain(),
                 )
+                    .run_if(in_state(LiveState::Running))
+                    .run_if(window_not_minimized),
+            )
+            // weapon wheel
+            .add_systems(
+                Update,
+                (
+                    wheel::open_weapon_wheel,
+                    wheel::update_weapon_wheel,
+                    hitstop::apply_hit_stop,
+                )
+                    .chain()
                     .run_if(in_state(LiveState::Running)),
             )
+            // practice mode controls
+            .add_systems(
+                Update,
+                mob::skip_to_next_spawn
+                    .run_if(in_state(LiveState::Running))
+                    .run_if(in_practice_mode),
+            )
             // running at fixed step
             .add_systems(
                 FixedUpdate,
                 (
                     // some systems need to run after those which create TargetDestroyed events
                     (
+                        mob::advance_mobs,
+                        mob::despawn_mobs_behind_player,
                         process_target_destroyed,
                         process_attacks,
+                        stats::record_resolved_attacks,
+                        stats::score_attacks,
+                        memory::process_memory_attacks,
                         mob::hurry_mob_spawners_on_no_targets,
                     )
                         .chain(),
-                    projectile::projectile_collision,
-                    mob::destroy_spawner_when_done,
+                    (
+                        projectile::steer_homing_projectiles,
+                        projectile::slow_projectiles_near_targets,
+                        projectile::projectile_collision,
+                    )
+                        .chain(),
+                    (mob::destroy_spawner_when_done, mob::track_cleared_spawners).chain(),
                     process_new_target,
-                    mob::spawn_mobs_on_time,
-                    process_damage_player,
+                    (stats::update_failed_target_weapons, mob::spawn_mobs_on_time).chain(),
+                    (mob::mob_attacks, process_damage_player).chain(),
                     (process_live_time, update_timer_text).chain(),
+                    update_wave_counter,
+                    update_grade_text,
+                    (update_score_text, update_combo_text),
                     weapon::process_weapon_change,
-                    weapon::trigger_weapon,
+                    (weapon::trigger_weapon, weapon::update_ammo_counters).chain(),
                     weapon::process_new_weapon,
                     weapon::process_approach_weapon_cube,
                     phase::process_approach_dread,
                     phase::process_approach_move_on,
                     button_system::<weapon::WeaponButton>,
                     on_enter_next_level,
+                    (
+                        achievement::track_attack_achievements,
+                        achievement::track_wave_achievements,
+                        achievement::process_unlock_achievement,
+                    )
+                        .chain(),
                 )
-                    .run_if(in_state(LiveState::Running)),
+                    .run_if(in_state(LiveState::Running))
+                    .run_if(window_not_minimized),
             )
             // paused
             .add_systems(
@@ -199,11 +307,17 @@ impl Plugin for LiveActionPlugin {
             .add_systems(
                 Update,
                 (
-                    (button_system::<DefeatButton>, defeat_button_action),
+                    (
+                        button_system::<DefeatButton>,
+                        defeat_button_action,
+                        process_auto_retry,
+                        player::fade_damage_indicator,
+                    ),
                     (
                         // these effects are also OK in the defeat screen
                         effect::apply_wobble,
-                        effect::fade_away,
+                        effect::apply_camera_shake,
+                        effect::fade_material,
                         effect::apply_rotation,
                         effect::apply_velocity,
                         stay_on_floor,
@@ -218,42 +332,155 @@ impl Plugin for LiveActionPlugin {
                 Update,
                 (
                     interlude::fade_in_interlude,
-                    interlude::fade_out_interlude,
-                    interlude::on_click_advance_interlude,
-                    interlude::process_advance_interlude,
+                    (
+                        interlude::save_run_card_on_click,
+                        button_system::<interlude::SaveRunCardButton>,
+                    ),
+                    (
+                        effect::fade_ui,
+                        interlude::auto_advance_interlude,
+                        interlude::on_click_advance_interlude,
+                        interlude::fade_out_interlude,
+                        interlude::process_advance_interlude,
+                    )
+                        .chain(),
                 )
                     .run_if(in_state(LiveState::ShowingInterlude)),
             )
             // resources
             .init_resource::<CurrentLevel>()
+            .init_resource::<LevelWarp>()
             .init_resource::<LiveTime>()
+            .init_resource::<HitStop>()
             .init_resource::<ProjectileAssets>()
             .init_resource::<WeaponCubeAssets>()
             .init_resource::<mob::MobAssets>()
+            .init_resource::<PreferredWeapon>()
+            .init_resource::<LevelStats>()
+            .init_resource::<LevelDeaths>()
+            .init_resource::<SpawnerProgress>()
+            .init_resource::<FractionColors>()
+            .init_resource::<achievement::AchievementTracker>()
+            .init_resource::<RecentAttacks>()
+            .init_resource::<FailedTargets>()
+            .init_resource::<RunStats>()
+            .init_resource::<Score>()
+            .init_resource::<Combo>()
+            .init_resource::<ResumeRun>()
+            .init_resource::<ChargeState>()
+            .init_resource::<RunningSince>()
+            .init_resource::<SessionSeed>()
             .insert_resource(AmbientLight::NONE)
             // events
             .add_event::<TriggerWeapon>()
             .add_event::<ChangeWeapon>()
             .add_event::<PlayerAttack>()
             .add_event::<TargetDestroyed>()
+            .add_event::<AttackResolved>()
+            .add_event::<achievement::UnlockAchievement>()
             .add_event::<DamagePlayer>()
             .add_event::<AdvanceInterlude>()
             .add_event::<AdvanceLevel>();
     }
 }
 
-fn start_running(mut next_state: ResMut<NextState<LiveState>>) {
+/// run condition for gameplay systems,
+/// which should not run while the window is minimized.
+///
+/// Both the per-frame and the fixed step sets are gated,
+/// so that nothing moves or spawns behind a minimized window.
+/// The fixed steps which pass while minimized are skipped,
+/// so that restoring the window resumes the game where it was
+/// instead of catching up with all of them at once
+/// (which could spawn a whole wave of mobs in an instant).
+fn window_not_minimized(window_q: Query<&Window, With<PrimaryWindow>>) -> bool {
+    window_q
+        .get_single()
+        .map_or(true, |window| window.width() > 0. && window.height() > 0.)
+}
+
+/// run condition for systems which only apply in practice mode
+fn in_practice_mode(game_settings: Res<GameSettings>) -> bool {
+    game_settings.practice_mode
+}
+
+fn start_running(
+    mut next_state: ResMut<NextState<LiveState>>,
+    mut live_time: ResMut<LiveTime>,
+    mut level_stats: ResMut<LevelStats>,
+    mut spawner_progress: ResMut<SpawnerProgress>,
+    mut fraction_colors: ResMut<FractionColors>,
+    mut achievement_tracker: ResMut<achievement::AchievementTracker>,
+    current_level: Res<CurrentLevel>,
+    level_deaths: Res<LevelDeaths>,
+    game_settings: Res<GameSettings>,
+) {
     next_state.set(LiveState::Running);
+    // time was stopped at the end of the previous corridor
+    live_time.resume();
+    level_stats.reset(live_time.elapsed_seconds());
+    level_stats.assisted = level_deaths.should_assist(current_level.id, &game_settings);
+    spawner_progress.reset();
+    fraction_colors.reset();
+    achievement_tracker.reset();
 }
 
 fn reset_game(
     mut next_state: ResMut<NextState<LiveState>>,
     mut live_time: ResMut<LiveTime>,
     mut current_level: ResMut<CurrentLevel>,
+    mut session_seed: ResMut<SessionSeed>,
+    real_time: Res<Time<Real>>,
+    mut recent_attacks: ResMut<RecentAttacks>,
+    mut run_stats: ResMut<RunStats>,
+    resume_run: Res<ResumeRun>,
+    mut level_warp: ResMut<LevelWarp>,
+    mut score: ResMut<Score>,
+    mut combo: ResMut<Combo>,
+    mut hit_stop: ResMut<HitStop>,
+    mut virtual_time: ResMut<Time<Virtual>>,
 ) {
     next_state.set(LiveState::default());
     live_time.reset();
     current_level.reset();
+    recent_attacks.reset();
+    run_stats.reset();
+    score.reset();
+    combo.reset();
+    hit_stop.reset(&mut virtual_time);
+    save::apply_resume_level(&resume_run, &mut current_level, &mut live_time);
+    if let Some(id) = level_warp.0.take() {
+        current_level.jump_to(id);
+    }
+    *session_seed = SessionSeed::new(&real_time);
+}
+
+/// Resource for a random value picked at the start of each session,
+/// used to shuffle the numbers in each level if so desired.
+#[derive(Debug, Default, Resource)]
+pub struct SessionSeed(pub u64);
+
+impl SessionSeed {
+    fn new(real_time: &Time<Real>) -> Self {
+        use std::hash::{BuildHasher, Hasher};
+
+        // the time taken to start the game is hard to predict,
+        // and this works on every platform
+        let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+        hasher.write_u128(real_time.elapsed().as_nanos());
+        SessionSeed(hasher.finish())
+    }
+
+    /// The seed to use in a level,
+    /// which is the level's own seed
+    /// unless the numbers should be shuffled
+    pub fn level_seed(&self, rng_seed: u64, game_settings: &GameSettings) -> u64 {
+        if game_settings.shuffle_numbers {
+            rng_seed ^ self.0
+        } else {
+            rng_seed
+        }
+    }
 }
 
 fn enter_defeat(
@@ -261,9 +488,22 @@ fn enter_defeat(
     mut defeat_div_q: Query<&mut Style, With<DefeatDiv>>,
     mut postprocess_settings_q: Query<&mut PostProcessSettings>,
     audio_sources: Res<AudioHandles>,
+    game_settings: Res<GameSettings>,
+    current_level: Res<CurrentLevel>,
+    mut level_deaths: ResMut<LevelDeaths>,
+    mut retry_hint_q: Query<&mut Text, With<RetryHint>>,
 ) {
-    for mut style in defeat_div_q.iter_mut() {
-        style.display = Display::Flex;
+    level_deaths.record(current_level.id);
+    for mut text in &mut retry_hint_q {
+        text.sections[0].value = level_deaths.retry_hint(current_level.id, &game_settings);
+    }
+    if game_settings.auto_retry {
+        // skip the defeat screen, restart shortly after
+        cmd.spawn((OnLive, AutoRetry::default()));
+    } else {
+        for mut style in defeat_div_q.iter_mut() {
+            style.display = Display::Flex;
+        }
     }
     if let Ok(mut settings) = postprocess_settings_q.get_single_mut() {
         settings.oscillate = 0.5;
@@ -275,9 +515,17 @@ fn enter_defeat(
 #[derive(Debug, Default, Component)]
 pub struct OnLive;
 
-/// Resource that keeps track of the live (in-game) time.
+/// Resource that keeps track of the live (in-game) time
+/// across the whole session.
 ///
-/// With this one, time does not count while it is paused.
+/// Time only counts while the game is [running](LiveState::Running)
+/// (it is ticked in the fixed running schedule),
+/// so it does not count while paused, in an interlude,
+/// loading a level, or defeated,
+/// nor while the window is minimized.
+/// It also stops once the player reaches the end of the corridor,
+/// so that choosing the next path does not count,
+/// and resumes when the next level starts.
 #[derive(Debug, Default, Resource)]
 pub struct LiveTime(pub Stopwatch);
 
@@ -286,6 +534,16 @@ impl LiveTime {
         self.0.reset();
     }
 
+    /// stop counting time until resumed
+    pub fn pause(&mut self) {
+        self.0.pause();
+    }
+
+    /// count time again
+    pub fn resume(&mut self) {
+        self.0.unpause();
+    }
+
     pub fn elapsed_seconds(&self) -> f32 {
         self.0.elapsed_secs() as f32
     }
@@ -293,15 +551,17 @@ impl LiveTime {
 
 impl fmt::Display for LiveTime {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        // mm:ss.S
-        let elapsed = self.0.elapsed_secs();
-        let elapsed_whole = elapsed as i64;
-        let minutes = elapsed_whole / 60;
-        let rest = elapsed - (minutes as f32 * 60.);
-        write!(f, "{minutes:02}:{rest:04.1}")
+        f.write_str(&format_time(self.0.elapsed_secs()))
     }
 }
 
+/// format a duration in seconds as mm:ss.S
+pub fn format_time(secs: f32) -> String {
+    let minutes = secs as i64 / 60;
+    let rest = secs - (minutes as f32 * 60.);
+    format!("{minutes:02}:{rest:04.1}")
+}
+
 fn process_live_time(time: Res<Time>, mut live_time: ResMut<LiveTime>) {
     live_time.0.tick(time.delta());
 }
@@ -336,6 +596,70 @@ fn pause_on_esc(
     }
 }
 
+/// How hard the game is overall,
+/// on top of how hard each level is made to be
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn next(self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    /// how many more mobs each spawner makes
+    fn count_factor(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.75,
+            Difficulty::Normal => 1.,
+            Difficulty::Hard => 1.25,
+        }
+    }
+
+    /// how much longer spawners wait between mobs
+    fn interval_factor(self) -> f32 {
+        match self {
+            Difficulty::Easy => 1.25,
+            Difficulty::Normal => 1.,
+            Difficulty::Hard => 0.8,
+        }
+    }
+
+    /// how much more health the player starts with
+    pub fn health_factor(self) -> f32 {
+        match self {
+            Difficulty::Easy => 1.5,
+            Difficulty::Normal => 1.,
+            Difficulty::Hard => 0.75,
+        }
+    }
+
+    /// Adjust a mob spawner from a level to this difficulty,
+    /// always leaving at least one mob to spawn.
+    pub fn adjust_spawner(self, spawner: &mut MobSpawner) {
+        if spawner.count > 0 {
+            spawner.count = ((spawner.count as f32 * self.count_factor()).round() as u32).max(1);
+        }
+        spawner.spawn_interval *= self.interval_factor();
+    }
+}
+
 /// Component for things with a health meter.
 ///
 /// Most attacks will deduct `1.` from health,
@@ -372,6 +696,9 @@ pub struct Target {
     pub num: Num,
     /// the rule for attacking the target
     pub rule: TargetRule,
+    /// the sum of the attacks made so far
+    /// (only used by the sum rule)
+    pub accumulated: Num,
 }
 
 /// Component for the player's attack cooldown meter
@@ -397,13 +724,26 @@ struct PauseButton;
 #[derive(Debug, Component)]
 enum PausedButtonAction {
     Resume,
+    ToggleFocusMode,
     GiveUp,
 }
 
+fn focus_mode_label(focus_mode: bool) -> String {
+    if focus_mode {
+        "Focus Mode: ON".to_string()
+    } else {
+        "Focus Mode: OFF".to_string()
+    }
+}
+
 /// Marker component for the UI node that shows defeat
 #[derive(Debug, Default, Component)]
 struct DefeatDiv;
 
+/// Marker component for the hint in the defeat screen
+#[derive(Debug, Component)]
+struct RetryHint;
+
 /// Group marker component for the buttons in the defeat screen
 #[derive(Debug, Default, Component)]
 struct DefeatButton;
@@ -414,10 +754,38 @@ enum DefeatButtonAction {
     GiveUp,
 }
 
+/// Marker component for the text entity in the pause screen
+/// reminding the player of their weapons and the rules of the game.
+#[derive(Debug, Component)]
+struct PauseInfo;
+
 /// Marker component for the text entity showing the game timer.
 #[derive(Debug, Component)]
 pub struct TimeIndicator;
 
+/// Marker component for the text entity showing
+/// the distance left to the end of the corridor.
+#[derive(Debug, Component)]
+pub struct DistanceIndicator;
+
+/// Marker component for the text entity showing the player's score.
+#[derive(Debug, Component)]
+pub struct ScoreIndicator;
+
+/// Marker component for the text entity showing
+/// the effective attacks in a row.
+#[derive(Debug, Component)]
+pub struct ComboIndicator;
+
+/// Marker component for the text entity showing the player's grade.
+#[derive(Debug, Component)]
+pub struct GradeIndicator;
+
+/// Marker component for the text entity showing
+/// how many mobs are left in the current wave.
+#[derive(Debug, Component)]
+pub struct WaveCounter;
+
 /// Set up the main UI components in the game for the first time
 fn setup_ui(
     mut cmd: Commands,
@@ -491,6 +859,118 @@ fn setup_ui(
             ));
         }
 
+        // if enabled, add distance indicator
+        if game_settings.show_distance {
+            root.spawn((
+                DistanceIndicator,
+                TextBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            color: Color::WHITE,
+                            font: font.clone(),
+                            font_size: 26.,
+                            ..default()
+                        },
+                    ),
+                    focus_policy: FocusPolicy::Pass,
+                    style: Style {
+                        margin: UiRect {
+                            left: Val::Px(75.),
+                            bottom: Val::Px(4.),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    z_index: ZIndex::Global(11),
+                    ..default()
+                },
+            ));
+        }
+
+        // add score indicator
+        root.spawn((
+            ScoreIndicator,
+            TextBundle {
+                text: Text::from_section(
+                    "Score: 0",
+                    TextStyle {
+                        color: Color::WHITE,
+                        font: font.clone(),
+                        font_size: 26.,
+                        ..default()
+                    },
+                ),
+                focus_policy: FocusPolicy::Pass,
+                style: Style {
+                    margin: UiRect {
+                        left: Val::Px(75.),
+                        bottom: Val::Px(4.),
+                        ..default()
+                    },
+                    ..default()
+                },
+                z_index: ZIndex::Global(11),
+                ..default()
+            },
+        ));
+
+        // add combo indicator (empty while there is no combo)
+        root.spawn((
+            ComboIndicator,
+            TextBundle {
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        color: Color::srgb(1., 0.85, 0.3),
+                        font: font.clone(),
+                        font_size: 26.,
+                        ..default()
+                    },
+                ),
+                focus_policy: FocusPolicy::Pass,
+                style: Style {
+                    margin: UiRect {
+                        left: Val::Px(75.),
+                        bottom: Val::Px(4.),
+                        ..default()
+                    },
+                    ..default()
+                },
+                z_index: ZIndex::Global(11),
+                ..default()
+            },
+        ));
+
+        // if enabled, add grade indicator
+        if game_settings.show_grade {
+            root.spawn((
+                GradeIndicator,
+                TextBundle {
+                    text: Text::from_section(
+                        "Grade: -",
+                        TextStyle {
+                            color: Color::WHITE,
+                            font: font.clone(),
+                            font_size: 26.,
+                            ..default()
+                        },
+                    ),
+                    focus_policy: FocusPolicy::Pass,
+                    style: Style {
+                        margin: UiRect {
+                            left: Val::Px(75.),
+                            bottom: Val::Px(4.),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    z_index: ZIndex::Global(11),
+                    ..default()
+                },
+            ));
+        }
+
         // insert cooldown meter
         root.spawn((
             MeterBundle::new(Val::Px(10.), Color::srgba_u8(0, 63, 255, 192)),
@@ -504,6 +984,34 @@ fn setup_ui(
         ));
     });
 
+    // wave counter at the top right corner,
+    // which is hidden until a wave starts
+    cmd.spawn((
+        OnLive,
+        WaveCounter,
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    color: Color::WHITE,
+                    font: font.clone(),
+                    font_size: 26.,
+                    ..default()
+                },
+            ),
+            focus_policy: FocusPolicy::Pass,
+            style: Style {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                top: Val::Px(12.),
+                right: Val::Px(16.),
+                ..default()
+            },
+            z_index: ZIndex::Global(11),
+            ..default()
+        },
+    ));
+
     // node for the pausing screen, which is hidden by default
     cmd.spawn((
         PausedDiv,
@@ -524,6 +1032,30 @@ fn setup_ui(
         },
     ))
     .with_children(|cmd| {
+        // information about the weapons and rules,
+        // filled in when the game is paused
+        cmd.spawn((
+            PauseInfo,
+            TextBundle {
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        color: Color::WHITE,
+                        font: font.clone(),
+                        font_size: 22.,
+                        ..default()
+                    },
+                )
+                .with_justify(JustifyText::Center),
+                style: Style {
+                    max_width: Val::Percent(80.),
+                    margin: UiRect::bottom(Val::Px(24.)),
+                    ..default()
+                },
+                ..default()
+            },
+        ));
+
         // button to resume the game
         spawn_button_in_group(
             cmd,
@@ -534,6 +1066,16 @@ fn setup_ui(
             PausedButtonAction::Resume,
         );
 
+        // button to toggle focus mode without leaving the game
+        spawn_button_in_group(
+            cmd,
+            &sizes,
+            font.clone(),
+            focus_mode_label(game_settings.focus_mode),
+            PauseButton,
+            PausedButtonAction::ToggleFocusMode,
+        );
+
         // button to return to main menu
         spawn_button_in_group(
             cmd,
@@ -585,6 +1127,30 @@ fn setup_ui(
             ..default()
         });
 
+        // a hint after a few defeats in the same level
+        cmd.spawn((
+            RetryHint,
+            TextBundle {
+                style: Style {
+                    margin: UiRect {
+                        bottom: Val::Px(24.),
+                        ..default()
+                    },
+                    ..default()
+                },
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        color: Color::srgb(0.85, 0.85, 0.85),
+                        font: font.clone(),
+                        font_size: 20.,
+                        ..default()
+                    },
+                ),
+                ..default()
+            },
+        ));
+
         // button to restart the current level
         spawn_button_in_group(
             cmd,
@@ -621,19 +1187,132 @@ fn update_timer_text(
     }
 }
 
+/// system that updates the distance indicator
+/// with the distance left until the player stops at the fork
+fn update_distance_text(
+    current_level: Res<CurrentLevel>,
+    player_q: Query<&Transform, With<Player>>,
+    mut distance_text_q: Query<&mut Text, With<DistanceIndicator>>,
+) {
+    let Ok(player_transform) = player_q.get_single() else {
+        return;
+    };
+
+    let distance =
+        (current_level.spec.corridor_length - FORK_STOP_DISTANCE - player_transform.translation.z)
+            .max(0.);
+
+    for mut distance_text in &mut distance_text_q {
+        let Some(section) = distance_text.sections.get_mut(0) else {
+            continue;
+        };
+
+        section.value = format!("{distance:.0} m left");
+    }
+}
+
+/// system that updates the grade indicator
+/// with the grade of the player's recent attacks
+fn update_grade_text(
+    recent_attacks: Res<RecentAttacks>,
+    mut grade_text_q: Query<&mut Text, With<GradeIndicator>>,
+) {
+    for mut grade_text in &mut grade_text_q {
+        let Some(section) = grade_text.sections.get_mut(0) else {
+            continue;
+        };
+
+        let grade = recent_attacks.grade().unwrap_or('-');
+        section.value = format!("Grade: {grade}");
+    }
+}
+
+/// system that updates the score indicator
+fn update_score_text(score: Res<Score>, mut score_text_q: Query<&mut Text, With<ScoreIndicator>>) {
+    for mut score_text in &mut score_text_q {
+        let Some(section) = score_text.sections.get_mut(0) else {
+            continue;
+        };
+
+        section.value = format!("Score: {}", score.0);
+    }
+}
+
+/// system that updates the combo indicator,
+/// only shown for two or more effective attacks in a row
+fn update_combo_text(
+    live_time: Res<LiveTime>,
+    combo: Res<Combo>,
+    mut combo_text_q: Query<&mut Text, With<ComboIndicator>>,
+) {
+    let count = combo.current(live_time.elapsed_seconds());
+    for mut combo_text in &mut combo_text_q {
+        let Some(section) = combo_text.sections.get_mut(0) else {
+            continue;
+        };
+
+        section.value = if count >= 2 {
+            format!("Combo x{count}")
+        } else {
+            String::new()
+        };
+    }
+}
+
+/// system that updates the wave counter with the number of mobs remaining
+/// (targets on scene plus mobs yet to be spawned by active spawners).
+///
+/// The counter is hidden while no wave is in progress.
+fn update_wave_counter(
+    mob_spawner_q: Query<&MobSpawner>,
+    target_q: Query<(), (With<Target>, Without<Collapsing>, Without<Decoy>)>,
+    mut wave_counter_q: Query<(&mut Text, &mut Style), With<WaveCounter>>,
+) {
+    let mut any_active = false;
+    let mut pending = 0;
+    for spawner in mob_spawner_q.iter().filter(|spawner| spawner.active) {
+        any_active = true;
+        pending += spawner.count as usize;
+    }
+    let remaining = pending + target_q.iter().count();
+
+    for (mut text, mut style) in &mut wave_counter_q {
+        if !any_active && remaining == 0 {
+            style.display = Display::None;
+            continue;
+        }
+        style.display = Display::Flex;
+
+        let Some(section) = text.sections.get_mut(0) else {
+            continue;
+        };
+        section.value = format!("Mobs left: {remaining}");
+    }
+}
+
 /// system which handles button presses in the paused screen
 fn paused_button_action(
     mut cmd: Commands,
     mut interaction_query: Query<
-        (&Interaction, &PausedButtonAction),
+        (&Interaction, &PausedButtonAction, &Children),
         (Changed<Interaction>, With<Button>),
     >,
+    mut text_q: Query<&mut Text>,
     mut paused_node_q: Query<&mut Style, With<PausedDiv>>,
     mut live_state: ResMut<NextState<LiveState>>,
     mut game_state: ResMut<NextState<AppState>>,
+    mut game_settings: ResMut<GameSettings>,
     audio_handles: Res<AudioHandles>,
+    (current_level, live_time, player_q, weapon_q, cheats, mut persistent_data): (
+        Res<CurrentLevel>,
+        Res<LiveTime>,
+        Query<&Health, With<Player>>,
+        Query<&PlayerWeapon>,
+        Res<Cheats>,
+        ResMut<PersistentData>,
+    ),
 ) {
-    for (interaction, pause_button_action) in &mut interaction_query {
+    for (interaction, pause_button_action, children) in &mut interaction_query {
         if *interaction == Interaction::Pressed {
             audio_handles.play_zipclick(&mut cmd);
             match pause_button_action {
@@ -644,7 +1323,25 @@ fn paused_button_action(
                     live_state.set(LiveState::Running);
                     println!("Game resumed");
                 }
+                PausedButtonAction::ToggleFocusMode => {
+                    game_settings.focus_mode = !game_settings.focus_mode;
+                    // update the button label
+                    for child in children {
+                        if let Ok(mut text) = text_q.get_mut(*child) {
+                            if let Some(section) = text.sections.get_mut(0) {
+                                section.value = focus_mode_label(game_settings.focus_mode);
+                            }
+                        }
+                    }
+                }
                 PausedButtonAction::GiveUp => {
+                    // keep the run for later, unless cheats were involved
+                    if !cheats.used_cheats {
+                        if let Ok(health) = player_q.get_single() {
+                            save::capture_run(&current_level, &live_time, health, &weapon_q)
+                                .save(&mut persistent_data);
+                        }
+                    }
                     // return to main menu
                     game_state.set(AppState::Menu);
                     println!("Giving up...");
@@ -654,6 +1351,78 @@ fn paused_button_action(
     }
 }
 
+/// system that fills in the pause screen
+/// with the weapons which the player has at the moment
+/// and a reminder of the rules
+fn update_pause_info(
+    mut pause_info_q: Query<&mut Text, With<PauseInfo>>,
+    weapon_button_q: Query<(&weapon::WeaponButton, Has<weapon::WeaponSelected>)>,
+    game_settings: Res<GameSettings>,
+) {
+    let num_style = icon::NumStyle::from(&*game_settings);
+    let Ok(mut text) = pause_info_q.get_single_mut() else {
+        return;
+    };
+
+    let mut buttons: Vec<_> = weapon_button_q.iter().collect();
+    buttons.sort_by_key(|(button, _)| button.shortcut());
+
+    let mut info = if buttons.is_empty() {
+        "Weapons: none yet".to_string()
+    } else {
+        let weapons: Vec<String> = buttons
+            .into_iter()
+            .map(|(button, selected)| {
+                if selected {
+                    format!("[{}]", num_style.format(button.num()))
+                } else {
+                    num_style.format(button.num())
+                }
+            })
+            .collect();
+        format!("Weapons: {}", weapons.join("  "))
+    };
+    info.push('\n');
+    for rule in TargetRule::PLAYABLE {
+        info.push('\n');
+        info.push_str(rule.description());
+    }
+
+    if let Some(section) = text.sections.get_mut(0) {
+        section.value = info;
+    }
+}
+
+/// Component for a pending restart of the level after defeat,
+/// so that the player can still see what happened
+#[derive(Debug, Component)]
+struct AutoRetry {
+    /// time left before restarting, in seconds
+    remaining: f32,
+}
+
+impl Default for AutoRetry {
+    fn default() -> Self {
+        AutoRetry { remaining: 1.5 }
+    }
+}
+
+/// system that restarts the level once the auto-retry pause is over
+fn process_auto_retry(
+    time: Res<Time>,
+    mut auto_retry_q: Query<&mut AutoRetry>,
+    mut live_state: ResMut<NextState<LiveState>>,
+) {
+    let Ok(mut auto_retry) = auto_retry_q.get_single_mut() else {
+        return;
+    };
+    auto_retry.remaining -= time.delta_seconds();
+    if auto_retry.remaining <= 0. {
+        info!("Retrying level");
+        live_state.set(LiveState::LoadingLevel);
+    }
+}
+
 /// system which handles button presses in the defeat screen
 fn defeat_button_action(
     mut cmd: Commands,
@@ -687,24 +1456,152 @@ fn defeat_button_action(
     }
 }
 
+/// Resource for the real time at which the game last started running,
+/// so that input from before then is not mistaken for an attack
+#[derive(Debug, Default, Resource)]
+struct RunningSince(Duration);
+
+impl RunningSince {
+    /// Whether a click may attack,
+    /// given the state of the game when it is done
+    /// and the real time at which it was pressed.
+    ///
+    /// Only clicks while the game is running may attack
+    /// (clicks and taps also advance interludes and press buttons),
+    /// and clicks which were started before the game resumed,
+    /// such as the one dismissing an interlude, are ignored.
+    fn accepts_click(&self, live_state: Option<&LiveState>, pressed_at: Duration) -> bool {
+        live_state == Some(&LiveState::Running) && pressed_at >= self.0
+    }
+}
+
+fn mark_running_since(real_time: Res<Time<Real>>, mut running_since: ResMut<RunningSince>) {
+    running_since.0 = real_time.elapsed();
+}
+
+/// system that keeps the cursor inside the window if so desired,
+/// so that aiming does not slip off to other screens
+fn confine_cursor(
+    game_settings: Res<GameSettings>,
+    mut window_q: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !game_settings.confine_cursor {
+        return;
+    }
+    if let Ok(mut window) = window_q.get_single_mut() {
+        window.cursor.grab_mode = CursorGrabMode::Confined;
+    }
+}
+
+/// system that lets the cursor go anywhere again
+fn release_cursor(mut window_q: Query<&mut Window, With<PrimaryWindow>>) {
+    if let Ok(mut window) = window_q.get_single_mut() {
+        if window.cursor.grab_mode != CursorGrabMode::None {
+            window.cursor.grab_mode = CursorGrabMode::None;
+        }
+    }
+}
+
 /// general system callback for when the player clicks on something
-pub fn callback_on_click(event: Listener<Pointer<Click>>, mut events: EventWriter<TriggerWeapon>) {
-    if event.button != PointerButton::Primary {
+pub fn callback_on_click(
+    event: Listener<Pointer<Click>>,
+    mut events: EventWriter<TriggerWeapon>,
+    charge_state: Res<ChargeState>,
+    live_state: Option<Res<State<LiveState>>>,
+    real_time: Res<Time<Real>>,
+    running_since: Res<RunningSince>,
+    game_settings: Res<GameSettings>,
+) {
+    // interludes and buttons always use the primary button,
+    // only attacks can be moved to the secondary one
+    let fire_button = if game_settings.fire_on_right_click {
+        PointerButton::Secondary
+    } else {
+        PointerButton::Primary
+    };
+    if event.button != fire_button {
+        return;
+    }
+    let pressed_at = real_time.elapsed().saturating_sub(event.duration);
+    if !running_since.accepts_click(live_state.as_deref().map(State::get), pressed_at) {
         return;
     }
     let Some(target_pos) = event.hit.position.clone() else {
         return;
     };
 
-    events.send(TriggerWeapon { target_pos });
+    events.send(TriggerWeapon {
+        target_pos,
+        charge: charge_state.charge(),
+    });
+}
+
+/// Component for the on-screen cue shown when the way ahead is clear
+#[derive(Debug, Component)]
+struct OnwardCue {
+    /// time left for the cue to disappear, in seconds
+    remaining: f32,
+}
+
+impl OnwardCue {
+    const DURATION: f32 = 1.2;
+    const ALPHA: f32 = 0.3;
+}
+
+/// spawn a soft glow around the edges of the screen
+/// to signal that the player is moving on
+fn spawn_onward_cue(cmd: &mut Commands) {
+    cmd.spawn((
+        OnLive,
+        OnwardCue {
+            remaining: OnwardCue::DURATION,
+        },
+        Pickable::IGNORE,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                border: UiRect::all(Val::Px(24.)),
+                ..default()
+            },
+            border_color: BorderColor(Color::srgba(0.8, 1., 0.85, OnwardCue::ALPHA)),
+            focus_policy: FocusPolicy::Pass,
+            z_index: ZIndex::Global(8),
+            ..default()
+        },
+    ));
+}
+
+/// system that fades out the onward cue
+fn fade_onward_cue(
+    mut cmd: Commands,
+    time: Res<Time>,
+    mut cue_q: Query<(Entity, &mut OnwardCue, &mut BorderColor)>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, mut cue, mut border_color) in &mut cue_q {
+        cue.remaining -= delta;
+        if cue.remaining <= 0. {
+            cmd.entity(entity).despawn();
+            continue;
+        }
+        border_color
+            .0
+            .set_alpha(OnwardCue::ALPHA * cue.remaining / OnwardCue::DURATION);
+    }
 }
 
 /// a system to handle game state changes when a target is destroyed
 pub fn process_target_destroyed(
+    mut cmd: Commands,
+    audio_handles: Res<AudioHandles>,
     mut target_destroyed_events: EventReader<TargetDestroyed>,
     active_mob_spawners_q: Query<Entity, (With<MobSpawner>, Without<PhaseTrigger>)>,
-    target_q: Query<Entity, (With<Target>, Without<Collapsing>)>,
-    mut player_q: Query<&mut PlayerMovement, With<Player>>,
+    target_q: Query<Entity, (With<Target>, Without<Collapsing>, Without<Decoy>)>,
+    decoy_q: Query<Entity, (With<Decoy>, With<Target>)>,
+    pending_mob_spawners_q: Query<(&MobSpawner, &PhaseTrigger)>,
+    mut player_q: Query<(&mut PlayerMovement, &Transform), With<Player>>,
 ) {
     let mut done = false;
     for _ in target_destroyed_events.read() {
@@ -723,10 +1620,32 @@ pub fn process_target_destroyed(
             continue;
         }
 
+        let (mut player_movement, player_transform) = player_q.single_mut();
+
+        // a chained wave reached by the player is about to begin
+        let wave_pending = pending_mob_spawners_q.iter().any(|(spawner, phase)| {
+            spawner.after.is_some() && phase.should_trigger(&player_transform.translation)
+        });
+        if wave_pending {
+            continue;
+        }
+
         // let's move!
-        let mut player_movement = player_q.single_mut();
         *player_movement = PlayerMovement::Walking;
         done = true;
+
+        // decoys left behind go away
+        for decoy in &decoy_q {
+            cmd.entity(decoy).remove::<Target>().insert((
+                Collapsing::default(),
+                Velocity(Vec3::new(0., 8., 6.)),
+                TimeToLive(0.5),
+            ));
+        }
+
+        // and let the player know
+        audio_handles.play_onward(&mut cmd);
+        spawn_onward_cue(&mut cmd);
     }
 }
 
@@ -743,6 +1662,9 @@ pub fn process_new_target(
     *player_movement = PlayerMovement::Halting;
 }
 
+/// how far from the fork the player stops walking
+const FORK_STOP_DISTANCE: f32 = 13.;
+
 /// system detecting that the player has reached the end of the corridor
 pub fn process_end_of_corridor(
     mut cmd: Commands,
@@ -753,30 +1675,110 @@ pub fn process_end_of_corridor(
     fork_q: Query<&Transform, With<Fork>>,
     default_font: Res<DefaultFont>,
     sizes: Res<Sizes>,
+    current_level: Res<CurrentLevel>,
+    mut live_time: ResMut<LiveTime>,
+    level_stats: Res<LevelStats>,
+    mut persistent_data: ResMut<PersistentData>,
+    mut level_deaths: ResMut<LevelDeaths>,
 ) {
     // retrieve player
     let Ok((mut player_movement, mut health, player_transform)) = player_q.get_single_mut() else {
         return;
     };
 
+    // already there
+    if matches!(*player_movement, PlayerMovement::Idle) {
+        return;
+    }
+
     // retrieve the fork
     let Ok(fork_transform) = fork_q.get_single() else {
         return;
     };
 
     let player_pos = player_transform.translation;
-    if player_pos.z + 13. >= fork_transform.translation.z {
+    if player_pos.z + FORK_STOP_DISTANCE >= fork_transform.translation.z {
         // stop walking
         *player_movement = PlayerMovement::Idle;
 
+        // no longer a first-time player
+        if current_level.id.stage == 0 {
+            persistent_data.mark_first_level_done();
+        }
+
         // heal player
+        if health.value < health.max {
+            icon::spawn_floating_text(
+                &mut cmd,
+                player_pos + Vec3::new(0., 0.5, 4.),
+                "+HP",
+                Color::srgb(0.4, 1., 0.5),
+            );
+        }
         health.replenish();
 
+        // the level is done, so stop the clock until the next one
+        live_time.pause();
+
+        // rate the player's performance
+        let time_taken = live_time.elapsed_seconds() - level_stats.started_at;
+        let par_time = current_level.spec.par_time();
+        let rating = level_stats.rating(time_taken, par_time);
+        // assisted runs do not count towards the best rating
+        let new_best = !level_stats.assisted
+            && stats::record_rating(&mut persistent_data, current_level.id, rating);
+        let best = stats::best_rating(&persistent_data, current_level.id).unwrap_or(rating);
+        let summary = format!(
+            "Time: {} (par {})\nAccuracy: {:.0}%\nRating: {rating}/3 {}",
+            format_time(time_taken),
+            format_time(par_time),
+            level_stats.accuracy() * 100.,
+            if level_stats.assisted {
+                "(assisted)".to_string()
+            } else if new_best {
+                "(new best!)".to_string()
+            } else {
+                format!("(best: {best}/3)")
+            },
+        );
+        // back to normal difficulty from now on
+        level_deaths.clear(current_level.id);
+        info!("Level {} cleared. {summary}", current_level.id);
+        spawn_level_summary(&mut cmd, default_font.0.clone(), summary);
+
         // and spawn new input arrows to select which way to go
         spawn_decision_arrows(&mut cmd, default_font, &sizes);
     }
 }
 
+/// Spawn the text summarizing how well the player did in the level
+fn spawn_level_summary(cmd: &mut Commands, font: Handle<Font>, summary: String) {
+    cmd.spawn((
+        OnLive,
+        TextBundle {
+            text: Text::from_section(
+                summary,
+                TextStyle {
+                    color: Color::WHITE,
+                    font,
+                    font_size: 28.,
+                    ..default()
+                },
+            )
+            .with_justify(JustifyText::Center),
+            focus_policy: FocusPolicy::Pass,
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(18.),
+                width: Val::Percent(100.),
+                ..default()
+            },
+            z_index: ZIndex::Global(11),
+            ..default()
+        },
+    ));
+}
+
 /// Marker component for the UI node containing the decision arrows
 #[derive(Debug, Component)]
 struct DecisionArrowsDiv;
@@ -891,3 +1893,233 @@ fn on_enter_next_level(
         break;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[derive(Default, Resource)]
+    struct Ticks(u32);
+
+    fn tick(mut ticks: ResMut<Ticks>) {
+        ticks.0 += 1;
+    }
+
+    #[test]
+    fn gameplay_stops_while_the_window_is_minimized() {
+        let mut world = World::new();
+        world.init_resource::<Ticks>();
+        let window = world.spawn((Window::default(), PrimaryWindow)).id();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(tick.run_if(window_not_minimized));
+
+        schedule.run(&mut world);
+        assert_eq!(world.resource::<Ticks>().0, 1);
+
+        // minimizing leaves the window with no size
+        world
+            .get_mut::<Window>(window)
+            .unwrap()
+            .resolution
+            .set(0., 0.);
+        assert!(!world.run_system_once(window_not_minimized));
+        schedule.run(&mut world);
+        schedule.run(&mut world);
+        assert_eq!(world.resource::<Ticks>().0, 1);
+
+        // restoring resumes one step at a time
+        world
+            .get_mut::<Window>(window)
+            .unwrap()
+            .resolution
+            .set(800., 600.);
+        schedule.run(&mut world);
+        assert_eq!(world.resource::<Ticks>().0, 2);
+    }
+
+    #[test]
+    fn projectiles_do_not_survive_a_level_transition() {
+        let mut world = World::new();
+        world.init_resource::<Assets<Mesh>>();
+        world.init_resource::<Assets<StandardMaterial>>();
+        world.init_resource::<ProjectileAssets>();
+        world.run_system_once(|mut cmd: Commands, assets: Res<ProjectileAssets>| {
+            projectile::spawn_projectile(
+                &mut cmd,
+                Vec3::ZERO,
+                Vec3::NEG_Z,
+                &weapon::PlayerWeapon::default(),
+                0.,
+                0.,
+                &assets,
+            );
+        });
+        assert_eq!(
+            world
+                .query::<&projectile::Projectile>()
+                .iter(&world)
+                .count(),
+            1
+        );
+        assert_eq!(world.query::<&PointLight>().iter(&world).count(), 1);
+
+        // the take-down done on every transition to the next level
+        world.run_system_once(despawn_all_at::<OnLive>);
+        assert_eq!(
+            world
+                .query::<&projectile::Projectile>()
+                .iter(&world)
+                .count(),
+            0
+        );
+        // including the light which goes along with it
+        assert_eq!(world.query::<&PointLight>().iter(&world).count(), 0);
+    }
+
+    #[test]
+    fn tap_dismissing_an_interlude_does_not_attack() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, bevy::state::app::StatesPlugin))
+            .insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(
+                Duration::from_millis(16),
+            ))
+            .init_state::<AppState>()
+            .add_sub_state::<LiveState>()
+            .init_resource::<RunningSince>()
+            .add_systems(OnEnter(LiveState::Running), mark_running_since);
+        let set_state = |app: &mut App, state: LiveState| {
+            app.world_mut()
+                .resource_mut::<NextState<LiveState>>()
+                .set(state);
+            app.update();
+        };
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Live);
+        app.update();
+        set_state(&mut app, LiveState::ShowingInterlude);
+        app.update();
+
+        // the tap is pressed while the interlude is shown
+        let pressed_at = app.world().resource::<Time<Real>>().elapsed();
+        let running_since = app.world().resource::<RunningSince>();
+        assert!(!running_since.accepts_click(Some(&LiveState::ShowingInterlude), pressed_at));
+
+        // and released on the frame the game resumes
+        set_state(&mut app, LiveState::Running);
+        let running_since = app.world().resource::<RunningSince>();
+        assert!(!running_since.accepts_click(Some(&LiveState::Running), pressed_at));
+
+        // the next tap attacks
+        app.update();
+        let pressed_at = app.world().resource::<Time<Real>>().elapsed();
+        let running_since = app.world().resource::<RunningSince>();
+        assert!(running_since.accepts_click(Some(&LiveState::Running), pressed_at));
+        // but not once the game is over
+        assert!(!running_since.accepts_click(None, pressed_at));
+    }
+
+    #[test]
+    fn decoys_left_behind_do_not_hold_the_player_back() {
+        let mut world = World::new();
+        world.insert_resource(AudioHandles::muted());
+        world.init_resource::<Events<TargetDestroyed>>();
+        let player = world
+            .spawn((Player, PlayerMovement::Idle, Transform::default()))
+            .id();
+        let decoy = world
+            .spawn((Target::default(), Decoy, Transform::default()))
+            .id();
+        let mob = world.spawn((Target::default(), Transform::default())).id();
+
+        // the decoy does not count as a target left to destroy
+        world.despawn(mob);
+        world.send_event(TargetDestroyed);
+        world.run_system_once(process_target_destroyed);
+
+        assert!(matches!(
+            world.get::<PlayerMovement>(player),
+            Some(PlayerMovement::Walking)
+        ));
+        // and goes away once the wave is cleared
+        assert!(world.get::<Target>(decoy).is_none());
+        assert!(world.get::<Collapsing>(decoy).is_some());
+    }
+
+    #[test]
+    fn live_time_only_counts_while_running() {
+        let step = Time::<Fixed>::default().timestep();
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, bevy::state::app::StatesPlugin))
+            .insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(step))
+            .init_state::<AppState>()
+            .add_sub_state::<LiveState>()
+            .init_resource::<LiveTime>()
+            // as in the running fixed set
+            .add_systems(
+                FixedUpdate,
+                process_live_time.run_if(in_state(LiveState::Running)),
+            );
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Live);
+        app.update();
+
+        let counted_in = |app: &mut App, state: LiveState| {
+            app.world_mut()
+                .resource_mut::<NextState<LiveState>>()
+                .set(state);
+            app.update();
+            let before = app.world().resource::<LiveTime>().elapsed_seconds();
+            for _ in 0..5 {
+                app.update();
+            }
+            app.world().resource::<LiveTime>().elapsed_seconds() - before
+        };
+
+        for state in [
+            LiveState::LoadingLevel,
+            LiveState::Paused,
+            LiveState::ShowingInterlude,
+            LiveState::Defeat,
+        ] {
+            assert_eq!(counted_in(&mut app, state.clone()), 0., "{state:?}");
+        }
+        assert!(counted_in(&mut app, LiveState::Running) > 0.);
+
+        // stopped at the end of the corridor while choosing the next path
+        app.world_mut().resource_mut::<LiveTime>().pause();
+        assert_eq!(counted_in(&mut app, LiveState::Running), 0.);
+        app.world_mut().resource_mut::<LiveTime>().resume();
+        assert!(counted_in(&mut app, LiveState::Running) > 0.);
+    }
+
+    #[test]
+    fn easy_difficulty_makes_fewer_mobs_than_normal() {
+        let adjusted = |difficulty: Difficulty, spawner: &MobSpawner| {
+            let mut spawner = spawner.clone();
+            difficulty.adjust_spawner(&mut spawner);
+            spawner
+        };
+        let spawner = MobSpawner::new(10, 2., [3, 5, 7]);
+
+        let easy = adjusted(Difficulty::Easy, &spawner);
+        let normal = adjusted(Difficulty::Normal, &spawner);
+        let hard = adjusted(Difficulty::Hard, &spawner);
+        assert!(easy.count < normal.count);
+        assert!(normal.count < hard.count);
+        assert_eq!(normal.count, spawner.count);
+        assert!(easy.spawn_interval > normal.spawn_interval);
+        assert!(hard.spawn_interval < normal.spawn_interval);
+
+        // the harder variant of a level is still harder on easy
+        let harder_spawner = MobSpawner::new(14, 1.75, [3, 5, 7]);
+        assert!(adjusted(Difficulty::Easy, &harder_spawner).count > easy.count);
+
+        // and there is always at least one mob to defeat
+        let single = MobSpawner::new(1, 2., [3]);
+        assert_eq!(adjusted(Difficulty::Easy, &single).count, 1);
+    }
+}
@@ -1,7 +1,7 @@
 //! Module for holding phase triggers.
 use bevy::prelude::*;
 
-use crate::{assets::AudioHandles, postprocess::PostProcessSettings};
+use crate::{assets::AudioHandles, effect::CameraShake, postprocess::PostProcessSettings};
 
 use super::player::{Player, PlayerMovement};
 
@@ -31,29 +31,43 @@ impl PhaseTrigger {
 
 /// Custom effect to create a sense of dread.
 #[derive(Debug, Component)]
-pub struct Dread;
+pub struct Dread {
+    /// whether to go easy on the player
+    /// (softer visual effect and no sound)
+    pub calm: bool,
+}
 
 pub fn process_approach_dread(
     mut cmd: Commands,
     mut player_q: Query<(&Transform, &mut PlayerMovement), With<Player>>,
-    trigger_q: Query<(Entity, &PhaseTrigger), With<Dread>>,
+    trigger_q: Query<(Entity, &PhaseTrigger, &Dread)>,
     mut postprocess_settings_q: Query<&mut PostProcessSettings>,
+    mut camera_shake_q: Query<&mut CameraShake>,
     audio_handles: Res<AudioHandles>,
 ) {
     let Ok((player_transform, mut player_movement)) = player_q.get_single_mut() else {
         return;
     };
 
-    for (entity, trigger) in &trigger_q {
+    for (entity, trigger, dread) in &trigger_q {
         if trigger.should_trigger(&player_transform.translation) {
-            // set postprocessing to the max
+            // set postprocessing to the max (or not quite)
             let Ok(mut postprocess_settings) = postprocess_settings_q.get_single_mut() else {
                 continue;
             };
-            postprocess_settings.intensity = 1.;
+            let trauma = if dread.calm {
+                postprocess_settings.intensity = 0.4;
+                0.3
+            } else {
+                postprocess_settings.intensity = 1.;
 
-            // play dread sound
-            audio_handles.play_dread(&mut cmd);
+                // play dread sound
+                audio_handles.play_dread(&mut cmd);
+                0.8
+            };
+            if let Ok(mut shake) = camera_shake_q.get_single_mut() {
+                shake.add_trauma(trauma);
+            }
 
             // slow the player down a bit
             *player_movement = PlayerMovement::Slower;
@@ -1,18 +1,25 @@
 //! Components, systems, and other functions specific to the player
 
-use bevy::{ecs::system::EntityCommands, prelude::*};
+use bevy::{ecs::system::EntityCommands, prelude::*, ui::FocusPolicy};
+use bevy_mod_picking::prelude::Pickable;
 
 use crate::{
     assets::AudioHandles,
     cheat::Cheats,
-    effect::{Collapsing, StaysOnFloor, TimeToLive, Velocity},
+    effect::{CameraShake, Collapsing, StaysOnFloor, TimeToLive, Velocity},
     live::Target,
-    logic::{test_attack_on, AttackTest},
+    logic::{describe_failure, describe_progress, test_attack_on, AttackTest, Num, TargetRule},
     postprocess::PostProcessSettings,
     ui::{set_meter_value, Meter},
+    CameraMarker, GameSettings,
 };
 
 use super::{
+    hitstop::HitStop,
+    icon::{spawn_feedback_text, spawn_floating_text, HasIcon, IconFlash, NumStyle},
+    memory::MemoryTarget,
+    mob::Decoy,
+    stats::LevelStats,
     weapon::{AttackCooldown, PlayerAttack},
     CooldownMeter, Health, HealthMeter, LiveState, OnLive,
 };
@@ -49,17 +56,33 @@ pub enum PlayerMovement {
     Halting,
 }
 
+/// system that moves the player along the corridor.
+///
+/// With manual movement enabled,
+/// the player only advances while holding W or the up arrow key.
 pub fn process_player_movement(
     time: Res<Time>,
+    input: Res<ButtonInput<KeyCode>>,
+    game_settings: Res<GameSettings>,
     mut query: Query<(&PlayerMovement, &mut Velocity), With<Player>>,
 ) {
     let elapsed = time.delta_seconds();
 
     const MAX_SPEED: f32 = 11.;
     const SLOW_SPEED: f32 = 5.;
+    /// how fast the player comes to a halt,
+    /// stopping in about a quarter of a second from full speed
+    const HALT_DECELERATION: f32 = 44.;
+
+    let held_back =
+        game_settings.manual_movement && !input.any_pressed([KeyCode::KeyW, KeyCode::ArrowUp]);
 
     for (movement, mut velocity) in query.iter_mut() {
         match movement {
+            PlayerMovement::Walking | PlayerMovement::Slower if held_back => {
+                // slow down just like when idle
+                velocity.0.z = (velocity.0.z * 0.78 / (1. + elapsed)).max(0.);
+            }
             PlayerMovement::Idle => {
                 // slowly decrease Z velocity
                 velocity.0.z = (velocity.0.z * 0.78 / (1. + elapsed)).max(0.);
@@ -77,15 +100,15 @@ pub fn process_player_movement(
                 }
             }
             PlayerMovement::Halting => {
-                // stop the player
-                velocity.0.z = 0.;
+                // stop the player quickly, but not instantly
+                velocity.0.z = (velocity.0.z - HALT_DECELERATION * elapsed).max(0.);
             }
         }
     }
 }
 
 /// create and spawn a new player entity
-pub fn spawn_player<'a>(cmd: &'a mut Commands, position: Vec3) -> EntityCommands<'a> {
+pub fn spawn_player<'a>(cmd: &'a mut Commands, position: Vec3, health: f32) -> EntityCommands<'a> {
     cmd.spawn(PlayerBundle {
         transform: TransformBundle {
             local: Transform::from_translation(position),
@@ -96,7 +119,7 @@ pub fn spawn_player<'a>(cmd: &'a mut Commands, position: Vec3) -> EntityCommands
             inherited_visibility: InheritedVisibility::VISIBLE,
             ..default()
         },
-        health: Health::new(8.),
+        health: Health::new(health),
         ..default()
     })
 }
@@ -104,6 +127,25 @@ pub fn spawn_player<'a>(cmd: &'a mut Commands, position: Vec3) -> EntityCommands
 #[derive(Debug, Event)]
 pub struct TargetDestroyed;
 
+/// Event for an attack which reached a target and was evaluated,
+/// whether it was effective or not
+#[derive(Debug, Event)]
+pub struct AttackResolved {
+    /// the target attacked
+    pub entity: Entity,
+    /// the outcome of the attack
+    pub result: AttackTest,
+    /// the attack number
+    pub attack: Num,
+    /// the number of the target before the attack
+    pub target_num: Num,
+    /// the rule of the target
+    pub target_rule: TargetRule,
+    /// how much of the player's cooldown was left
+    /// when the attack was made, from 0 to 1
+    pub pace: f32,
+}
+
 /// system for processing player attacks
 pub fn process_attacks(
     mut cmd: Commands,
@@ -111,16 +153,47 @@ pub fn process_attacks(
     mut events: EventReader<PlayerAttack>,
     mut damage_player_events: EventWriter<DamagePlayer>,
     mut target_destroyed_events: EventWriter<TargetDestroyed>,
-    mut target_query: Query<(&mut Target, Option<&mut Health>)>,
+    mut target_query: Query<
+        (
+            &mut Target,
+            &GlobalTransform,
+            Option<&mut Health>,
+            Option<&HasIcon>,
+            Has<Decoy>,
+        ),
+        Without<MemoryTarget>,
+    >,
+    mut attack_resolved_events: EventWriter<AttackResolved>,
+    game_settings: Res<GameSettings>,
+    mut hit_stop: ResMut<HitStop>,
 ) {
-    for PlayerAttack { entity, num } in events.read() {
+    let num_style = NumStyle::from(&*game_settings);
+    for PlayerAttack { entity, num, pace } in events.read() {
         // query entity for target information
-        let Ok((mut target, health)) = target_query.get_mut(*entity) else {
-            return;
+        let Ok((mut target, transform, health, has_icon, is_decoy)) = target_query.get_mut(*entity)
+        else {
+            continue;
         };
+        if is_decoy {
+            // the shot is wasted, but nothing else happens
+            if let Some(HasIcon(icon_entity)) = has_icon {
+                cmd.entity(*icon_entity).insert(IconFlash::default());
+            }
+            spawn_feedback_text(&mut cmd, *entity, "Decoy!", Color::srgb(0.8, 0.7, 1.));
+            audio_sources.play_equipmentclick1(&mut cmd);
+            continue;
+        }
 
         // evaluate the attack
         let attack_result = test_attack_on(&target, *num);
+        attack_resolved_events.send(AttackResolved {
+            entity: *entity,
+            result: attack_result,
+            attack: *num,
+            target_num: target.num,
+            target_rule: target.rule,
+            pace: *pace,
+        });
 
         // apply the attack
         match attack_result {
@@ -136,7 +209,17 @@ pub fn process_attacks(
                             TimeToLive(0.5),
                         ));
 
-                        audio_sources.play_hit02(&mut cmd);
+                        audio_sources.play_hit02(&mut cmd, transform.translation());
+                        spawn_floating_text(
+                            &mut cmd,
+                            transform.translation(),
+                            "Down!",
+                            Color::srgb(0.5, 1., 0.5),
+                        );
+                        // give weight to the final blow on robust targets
+                        if health.max > 1. {
+                            hit_stop.trigger(HitStop::ROBUST_TARGET);
+                        }
 
                         target_destroyed_events.send(TargetDestroyed);
                     } else {
@@ -144,6 +227,8 @@ pub fn process_attacks(
                         if let Some(num) = new_num {
                             target.num = num;
                         }
+                        // and start a new sum (or pair) if there was one
+                        target.accumulated = Num::ZERO;
                     }
                 } else {
                     // with no health, the target is destroyed
@@ -157,9 +242,39 @@ pub fn process_attacks(
                     target_destroyed_events.send(TargetDestroyed);
                 }
             }
-            AttackTest::Failed => {
+            AttackTest::Progress(sum) => {
+                // keep track of the sum (or the first factor) so far
+                target.accumulated = sum;
+                spawn_feedback_text(
+                    &mut cmd,
+                    *entity,
+                    describe_progress(&target, sum, |num| num_style.format(num)),
+                    Color::srgb(0.4, 1., 0.4),
+                );
+                audio_sources.play_equipmentclick1(&mut cmd);
+            }
+            AttackTest::Failed | AttackTest::Overshot(_) => {
+                // make it clear that the attack was wrong, and why
+                if let Some(HasIcon(icon_entity)) = has_icon {
+                    cmd.entity(*icon_entity).insert(IconFlash::default());
+                }
+                spawn_feedback_text(
+                    &mut cmd,
+                    *entity,
+                    describe_failure(&target, *num, attack_result, |num| num_style.format(num)),
+                    Color::srgb(1., 0.2, 0.2),
+                );
+
+                // going over the sum means starting over
+                if let AttackTest::Overshot(_) = attack_result {
+                    target.accumulated = Num::ZERO;
+                }
+
                 // nope, damage the player back
-                damage_player_events.send(DamagePlayer { damage: 1. });
+                damage_player_events.send(DamagePlayer {
+                    damage: 1.,
+                    source: None,
+                });
             }
         }
     }
@@ -168,6 +283,84 @@ pub fn process_attacks(
 #[derive(Debug, Event)]
 pub struct DamagePlayer {
     pub damage: f32,
+    /// where the damage came from, if it came from somewhere in particular
+    pub source: Option<Vec3>,
+}
+
+/// Component for the indicator of damage taken,
+/// shown at the edge of the screen
+#[derive(Debug, Component)]
+pub struct DamageIndicator {
+    /// time left for the indicator to disappear, in seconds
+    remaining: f32,
+}
+
+impl DamageIndicator {
+    const DURATION: f32 = 0.6;
+    const ALPHA: f32 = 0.5;
+    /// the thickness of the indicator, in pixels
+    const THICKNESS: f32 = 28.;
+}
+
+/// Spawn an indicator of damage taken,
+/// at the edge of the screen closest to the source of the damage
+/// (given in normalized device coordinates),
+/// or all around the screen if there is no source.
+fn spawn_damage_indicator(cmd: &mut Commands, source_ndc: Option<Vec3>) {
+    let thickness = Val::Px(DamageIndicator::THICKNESS);
+    let border = match source_ndc {
+        None => UiRect::all(thickness),
+        // behind the camera
+        Some(ndc) if ndc.z < 0. => UiRect::bottom(thickness),
+        Some(ndc) if ndc.x.abs() >= ndc.y.abs() => {
+            if ndc.x < 0. {
+                UiRect::left(thickness)
+            } else {
+                UiRect::right(thickness)
+            }
+        }
+        Some(ndc) if ndc.y >= 0. => UiRect::top(thickness),
+        Some(_) => UiRect::bottom(thickness),
+    };
+    cmd.spawn((
+        OnLive,
+        DamageIndicator {
+            remaining: DamageIndicator::DURATION,
+        },
+        Pickable::IGNORE,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                border,
+                ..default()
+            },
+            border_color: BorderColor(Color::srgba(1., 0., 0., DamageIndicator::ALPHA)),
+            focus_policy: FocusPolicy::Pass,
+            z_index: ZIndex::Global(9),
+            ..default()
+        },
+    ));
+}
+
+/// system that fades out damage indicators
+pub fn fade_damage_indicator(
+    mut cmd: Commands,
+    time: Res<Time>,
+    mut indicator_q: Query<(Entity, &mut DamageIndicator, &mut BorderColor)>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, mut indicator, mut border_color) in &mut indicator_q {
+        indicator.remaining -= delta;
+        if indicator.remaining <= 0. {
+            cmd.entity(entity).despawn();
+            continue;
+        }
+        border_color
+            .0
+            .set_alpha(DamageIndicator::ALPHA * indicator.remaining / DamageIndicator::DURATION);
+    }
 }
 
 pub fn process_damage_player(
@@ -178,13 +371,28 @@ pub fn process_damage_player(
     mut player_q: Query<(Entity, &mut Health), With<Player>>,
     mut postprocess_settings_q: Query<&mut PostProcessSettings>,
     mut next_state: ResMut<NextState<LiveState>>,
+    mut level_stats: ResMut<LevelStats>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<CameraMarker>>,
+    mut camera_shake_q: Query<&mut CameraShake>,
 ) {
     if cheats.invulnerability {
         return;
     }
 
-    for DamagePlayer { damage } in events.read() {
+    for DamagePlayer { damage, source } in events.read() {
         audio_sources.play_hit37(&mut cmd);
+        level_stats.damage_taken += damage;
+
+        // show where the damage came from
+        let source_ndc = source.and_then(|source| {
+            let (camera, camera_transform) = camera_q.get_single().ok()?;
+            camera.world_to_ndc(camera_transform, source)
+        });
+        spawn_damage_indicator(&mut cmd, source_ndc);
+
+        if let Ok(mut shake) = camera_shake_q.get_single_mut() {
+            shake.add_trauma(0.6);
+        }
 
         let Ok((player_entity, mut player_health)) = player_q.get_single_mut() else {
             return;
@@ -252,3 +460,178 @@ pub fn update_player_health_meter(
     let percent = 100. * health.value / health.max;
     set_meter_value(meter_query, percent);
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::live::icon::FloatingText;
+
+    /// a world with just enough for the player to attack targets
+    fn test_world() -> World {
+        let mut world = World::new();
+        world.insert_resource(AudioHandles::muted());
+        world.init_resource::<GameSettings>();
+        world.init_resource::<HitStop>();
+        world.init_resource::<Events<PlayerAttack>>();
+        world.init_resource::<Events<DamagePlayer>>();
+        world.init_resource::<Events<TargetDestroyed>>();
+        world.init_resource::<Events<AttackResolved>>();
+        world
+    }
+
+    fn attack(world: &mut World, entity: Entity, num: i16) {
+        world.send_event(PlayerAttack {
+            entity,
+            num: num.into(),
+            pace: 0.,
+        });
+        world.run_system_once(process_attacks);
+        world.resource_mut::<Events<PlayerAttack>>().clear();
+    }
+
+    #[test]
+    fn robust_mob_survives_one_effective_hit() {
+        let mut world = test_world();
+        let mob = world
+            .spawn((
+                Target {
+                    num: 12.into(),
+                    ..default()
+                },
+                GlobalTransform::default(),
+                Health { value: 2., max: 2. },
+            ))
+            .id();
+
+        attack(&mut world, mob, 2);
+        let target = world.get::<Target>(mob).expect("mob should survive");
+        assert_eq!(target.num, 6.into());
+        assert_eq!(world.get::<Health>(mob).unwrap().value, 1.);
+        assert!(world.resource::<Events<TargetDestroyed>>().is_empty());
+
+        attack(&mut world, mob, 3);
+        assert!(world.get::<Target>(mob).is_none());
+        assert_eq!(world.resource::<Events<TargetDestroyed>>().len(), 1);
+    }
+
+    #[test]
+    fn only_robust_targets_stop_the_game_when_destroyed() {
+        let mut world = test_world();
+        let spawn_mob = |world: &mut World, health: f32| {
+            world
+                .spawn((
+                    Target {
+                        num: 6.into(),
+                        ..default()
+                    },
+                    GlobalTransform::default(),
+                    Health {
+                        value: health,
+                        max: health,
+                    },
+                ))
+                .id()
+        };
+
+        let mob = spawn_mob(&mut world, 1.);
+        attack(&mut world, mob, 6);
+        assert!(world.get::<Target>(mob).is_none());
+        assert_eq!(world.resource::<HitStop>().remaining, 0.);
+
+        let robust_mob = spawn_mob(&mut world, 2.);
+        attack(&mut world, robust_mob, 2);
+        assert_eq!(world.resource::<HitStop>().remaining, 0.);
+        attack(&mut world, robust_mob, 3);
+        assert!(world.get::<Target>(robust_mob).is_none());
+        assert_eq!(
+            world.resource::<HitStop>().remaining,
+            HitStop::ROBUST_TARGET
+        );
+    }
+
+    #[test]
+    fn destroying_a_target_shows_floating_text() {
+        let mut world = test_world();
+        let floating_texts = |world: &mut World| {
+            world
+                .query_filtered::<(), With<FloatingText>>()
+                .iter(world)
+                .count()
+        };
+        let mob = world
+            .spawn((
+                Target {
+                    num: 12.into(),
+                    ..default()
+                },
+                GlobalTransform::default(),
+                Health { value: 2., max: 2. },
+            ))
+            .id();
+
+        // not on a hit which the target survives
+        attack(&mut world, mob, 2);
+        assert_eq!(floating_texts(&mut world), 0);
+
+        attack(&mut world, mob, 6);
+        assert_eq!(floating_texts(&mut world), 1);
+    }
+
+    #[test]
+    fn decoys_never_take_damage() {
+        let mut world = test_world();
+        let decoy = world
+            .spawn((
+                Target {
+                    num: 6.into(),
+                    rule: TargetRule::Invulnerable,
+                    ..default()
+                },
+                Decoy,
+                GlobalTransform::default(),
+            ))
+            .id();
+
+        for num in [6, 2, 3, 1, 7] {
+            attack(&mut world, decoy, num);
+        }
+        let target = world.get::<Target>(decoy).expect("decoy should stay");
+        assert_eq!(target.num, 6.into());
+        assert!(world.resource::<Events<TargetDestroyed>>().is_empty());
+        // wasted shots, but no harm done to the player
+        assert!(world.resource::<Events<DamagePlayer>>().is_empty());
+    }
+
+    #[test]
+    fn sum_target_adds_up_attacks_and_starts_over_when_overshot() {
+        let mut world = test_world();
+        let target = world
+            .spawn((
+                Target {
+                    num: 10.into(),
+                    rule: TargetRule::SumTo,
+                    ..default()
+                },
+                GlobalTransform::default(),
+            ))
+            .id();
+
+        // undershoot: progress is kept
+        attack(&mut world, target, 3);
+        assert_eq!(world.get::<Target>(target).unwrap().accumulated, 3.into());
+        assert!(world.resource::<Events<DamagePlayer>>().is_empty());
+
+        // overshoot: the player is hurt and the sum starts over
+        attack(&mut world, target, 9);
+        assert_eq!(world.get::<Target>(target).unwrap().accumulated, 0.into());
+        assert_eq!(world.resource::<Events<DamagePlayer>>().len(), 1);
+
+        // exact: the target is destroyed
+        attack(&mut world, target, 4);
+        attack(&mut world, target, 6);
+        assert!(world.get::<Target>(target).is_none());
+        assert_eq!(world.resource::<Events<TargetDestroyed>>().len(), 1);
+    }
+}
@@ -2,11 +2,17 @@ use bevy::{
     math::bounding::{BoundingSphere, IntersectsVolume as _},
     prelude::*,
 };
+use tinyrand::{RandRange, Seeded, SplitMix};
 
-use crate::{effect::Velocity, logic::Num};
+use crate::{
+    effect::{Collapsing, TimeToLive, Velocity},
+    logic::{test_attack_on, AttackTest, Num},
+    GameSettings,
+};
 
 use super::{
-    collision::CollidableBox,
+    collision::{CollidableBox, ZBins},
+    mob::Decoy,
     weapon::{PlayerAttack, PlayerWeapon},
     OnLive, Target,
 };
@@ -15,12 +21,19 @@ use super::{
 pub struct ProjectileAssets {
     pub mesh: Handle<Mesh>,
     pub material: Handle<StandardMaterial>,
+    /// mesh for the particles of a projectile hit
+    pub particle_mesh: Handle<Mesh>,
+    /// particle material for hits on targets which took the attack
+    pub particle_effective_material: Handle<StandardMaterial>,
+    /// particle material for hits on targets which refused the attack
+    pub particle_failed_material: Handle<StandardMaterial>,
 }
 
 impl FromWorld for ProjectileAssets {
     fn from_world(world: &mut World) -> Self {
         let mut meshes = world.resource_mut::<Assets<Mesh>>();
         let mesh: Handle<Mesh> = meshes.add(Sphere::new(0.12));
+        let particle_mesh: Handle<Mesh> = meshes.add(Sphere::new(0.04));
 
         let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
         let material = materials.add(StandardMaterial {
@@ -28,8 +41,24 @@ impl FromWorld for ProjectileAssets {
             emissive_exposure_weight: 0.0,
             ..Default::default()
         });
+        let particle_effective_material = materials.add(StandardMaterial {
+            emissive: LinearRgba::new(0.5, 1., 0.5, 1.),
+            emissive_exposure_weight: 0.0,
+            ..Default::default()
+        });
+        let particle_failed_material = materials.add(StandardMaterial {
+            emissive: LinearRgba::new(1., 0.3, 0.25, 1.),
+            emissive_exposure_weight: 0.0,
+            ..Default::default()
+        });
 
-        ProjectileAssets { mesh, material }
+        ProjectileAssets {
+            mesh,
+            material,
+            particle_mesh,
+            particle_effective_material,
+            particle_failed_material,
+        }
     }
 }
 
@@ -38,8 +67,51 @@ impl FromWorld for ProjectileAssets {
 pub struct Projectile {
     /// the number which defines the kind of attack
     pub num: Num,
+    /// the speed at which it was shot
+    pub speed: f32,
+    /// the radius within which it hits things
+    pub radius: f32,
+    /// how much of the player's cooldown was left when it was shot,
+    /// from 0 to 1, so that quick attacks are worth more points
+    pub pace: f32,
 }
 
+/// the radius within which a plain projectile hits things
+const PROJECTILE_RADIUS: f32 = 0.25;
+
+/// how much bigger a fully charged projectile is, relative to a plain one
+const CHARGE_RADIUS_BONUS: f32 = 1.;
+
+/// how much faster a fully charged projectile is, relative to a plain one
+const CHARGE_SPEED_BONUS: f32 = 0.5;
+
+/// Component for a projectile which can go through targets,
+/// holding how many more targets it can hit
+/// and which ones it has gone through already
+#[derive(Debug, Component)]
+pub struct Piercing {
+    remaining: u8,
+    hit: Vec<Entity>,
+}
+
+/// Component for a projectile which steers towards the nearest target ahead
+#[derive(Debug, Component)]
+pub struct Homing {
+    /// how quickly it turns towards the target, per second
+    pub strength: f32,
+}
+
+/// how quickly homing projectiles turn towards their target
+const HOMING_STRENGTH: f32 = 6.;
+
+/// how far a homing projectile looks for a target
+const HOMING_RADIUS: f32 = 16.;
+
+/// the cosine of the widest angle between the heading of a homing projectile
+/// and a target for it to be sought
+/// (about 35 degrees)
+const HOMING_CONE_COS: f32 = 0.82;
+
 /// Bundle for a projectile
 #[derive(Debug, Default, Bundle)]
 pub struct ProjectileBundle {
@@ -54,24 +126,34 @@ pub fn spawn_projectile(
     player_position: Vec3,
     direction: Vec3,
     weapon: &PlayerWeapon,
+    charge: f32,
+    pace: f32,
     assets: &ProjectileAssets,
 ) {
     // spawn a projectile
     let pos = player_position + Vec3::new(0.15, 0.25, 1.);
+    // charged shots are bigger and faster
+    let scale = 1. + charge * CHARGE_RADIUS_BONUS;
+    let speed = weapon.projectile_speed * (1. + charge * CHARGE_SPEED_BONUS);
 
-    cmd.spawn((
+    let mut projectile = cmd.spawn((
         OnLive,
-        Projectile { num: weapon.num },
+        Projectile {
+            num: weapon.num,
+            speed,
+            radius: PROJECTILE_RADIUS * scale,
+            pace,
+        },
         PbrBundle {
             visibility: Visibility::Visible,
-            transform: Transform::from_translation(pos),
+            transform: Transform::from_translation(pos).with_scale(Vec3::splat(scale)),
             mesh: assets.mesh.clone(),
             material: assets.material.clone(),
             ..default()
         },
-        Velocity(direction * weapon.projectile_speed),
-    ))
-    .with_children(|cmd| {
+        Velocity(direction * speed),
+    ));
+    projectile.with_children(|cmd| {
         // add a light to the projectile
         cmd.spawn(PointLightBundle {
             point_light: PointLight {
@@ -83,28 +165,208 @@ pub fn spawn_projectile(
             ..default()
         });
     });
+    if weapon.homing {
+        projectile.insert(Homing {
+            strength: HOMING_STRENGTH,
+        });
+    }
+    if weapon.pierce > 1 {
+        projectile.insert(Piercing {
+            remaining: weapon.pierce,
+            hit: vec![],
+        });
+    }
+}
+
+/// system that steers homing projectiles
+/// towards the nearest target within a cone ahead of them,
+/// keeping their speed
+pub fn steer_homing_projectiles(
+    time: Res<Time>,
+    mut projectile_q: Query<(&Transform, &mut Velocity, &Homing), With<Projectile>>,
+    target_q: Query<&GlobalTransform, (With<Target>, Without<Decoy>)>,
+) {
+    let delta = time.delta_seconds();
+    for (transform, mut velocity, homing) in &mut projectile_q {
+        let speed = velocity.0.length();
+        if speed == 0. {
+            continue;
+        }
+        let heading = velocity.0 / speed;
+
+        let nearest = target_q
+            .iter()
+            .filter_map(|target_transform| {
+                let offset = target_transform.translation() - transform.translation;
+                let distance = offset.length();
+                let direction = offset.try_normalize()?;
+                (distance <= HOMING_RADIUS && heading.dot(direction) >= HOMING_CONE_COS)
+                    .then_some((distance, direction))
+            })
+            .min_by(|(d1, _), (d2, _)| d1.total_cmp(d2));
+        let Some((_, direction)) = nearest else {
+            continue;
+        };
+
+        // turn a bit towards the target,
+        // by the same amount per second regardless of the frame rate
+        let turn = (homing.strength * delta).min(1.);
+        let new_heading = heading.lerp(direction, turn).normalize_or_zero();
+        velocity.0 = new_heading * speed;
+    }
+}
+
+/// how close to a target projectiles start slowing down
+/// (if slow shots are enabled)
+const SLOW_SHOT_RADIUS: f32 = 6.;
+
+/// the slowest a projectile can go near a target,
+/// relative to the speed at which it was shot
+/// (still faster than anything it could be chasing)
+const SLOW_SHOT_MIN_FACTOR: f32 = 0.4;
+
+/// system that slows projectiles down as they approach a target,
+/// so that the impact is easier to follow
+/// (only if enabled in the settings).
+///
+/// Targets which the projectile has already passed
+/// or which are off to the side do not slow it down.
+pub fn slow_projectiles_near_targets(
+    game_settings: Res<GameSettings>,
+    mut projectile_q: Query<(&Transform, &mut Velocity, &Projectile)>,
+    target_q: Query<&GlobalTransform, With<Target>>,
+) {
+    if !game_settings.slow_shots {
+        return;
+    }
+    for (transform, mut velocity, projectile) in &mut projectile_q {
+        let Some(heading) = velocity.0.try_normalize() else {
+            continue;
+        };
+        let nearest = target_q
+            .iter()
+            .map(|target_transform| target_transform.translation() - transform.translation)
+            .filter(|offset| offset.dot(heading) > 0.)
+            .map(Vec3::length)
+            .min_by(f32::total_cmp)
+            .unwrap_or(f32::INFINITY);
+
+        // based on the original speed,
+        // so that the slowdown does not build up over time
+        let factor = (nearest / SLOW_SHOT_RADIUS).clamp(SLOW_SHOT_MIN_FACTOR, 1.);
+        velocity.0 = heading * projectile.speed * factor;
+    }
+}
+
+/// the number of particles in the burst of a projectile hit
+const HIT_PARTICLE_COUNT: u32 = 6;
+
+/// how long the particles of a projectile hit last, in seconds
+const HIT_PARTICLE_LIFETIME: f32 = 0.4;
+
+/// spawn a small burst of glowing particles where a projectile hit something
+pub fn spawn_hit_particles(
+    cmd: &mut Commands,
+    assets: &ProjectileAssets,
+    position: Vec3,
+    material: &Handle<StandardMaterial>,
+    seed: u64,
+) {
+    let mut rng = SplitMix::seed(seed);
+    for _ in 0..HIT_PARTICLE_COUNT {
+        // spread out sideways, mostly upwards and back towards the player
+        let velocity = Vec3::new(
+            rng.next_range(0..200_u32) as f32 / 50. - 2.,
+            rng.next_range(50..250_u32) as f32 / 50.,
+            -(rng.next_range(0..100_u32) as f32) / 50.,
+        );
+        cmd.spawn((
+            OnLive,
+            PbrBundle {
+                mesh: assets.particle_mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_translation(position),
+                ..default()
+            },
+            Velocity(velocity),
+            Collapsing::default(),
+            TimeToLive(HIT_PARTICLE_LIFETIME),
+        ));
+    }
 }
 
 /// System for handling the collision of projectiles
 pub fn projectile_collision(
     mut cmd: Commands,
-    projectile_q: Query<(Entity, &Transform, &Projectile)>,
+    assets: Res<ProjectileAssets>,
+    mut projectile_q: Query<(Entity, &Transform, &Projectile, Option<&mut Piercing>)>,
     collidable_q: Query<(Entity, &CollidableBox, &Transform, Option<&Target>)>,
     mut attack_events: EventWriter<PlayerAttack>,
+    mut collidables: Local<ZBins<Entity>>,
 ) {
-    for (p_entity, p_transform, projectile) in projectile_q.iter() {
-        for (entity, collidable, t_transform, target) in collidable_q.iter() {
-            let bound = collidable.to_bound(t_transform.translation);
-            if bound.intersects(&BoundingSphere::new(p_transform.translation, 0.25)) {
+    if projectile_q.is_empty() {
+        return;
+    }
+    // only test the things close to each projectile
+    collidables.rebuild(
+        collidable_q
+            .iter()
+            .map(|(entity, collidable, transform, _)| {
+                (entity, collidable.to_bound(transform.translation))
+            }),
+    );
+
+    for (p_entity, p_transform, projectile, mut piercing) in projectile_q.iter_mut() {
+        let sphere = BoundingSphere::new(p_transform.translation, projectile.radius);
+        for &(entity, bound) in collidables.candidates(&sphere) {
+            if piercing
+                .as_ref()
+                .is_some_and(|piercing| piercing.hit.contains(&entity))
+            {
+                // went through this one already
+                continue;
+            }
+            if bound.intersects(&sphere) {
+                let target = collidable_q
+                    .get(entity)
+                    .ok()
+                    .and_then(|(_, _, _, target)| target);
+                // burst of particles colored by the outcome of the attack
+                let material = match target.map(|target| test_attack_on(target, projectile.num)) {
+                    Some(AttackTest::Effective(_) | AttackTest::Progress(_)) => {
+                        &assets.particle_effective_material
+                    }
+                    Some(AttackTest::Failed | AttackTest::Overshot(_)) => {
+                        &assets.particle_failed_material
+                    }
+                    None => &assets.material,
+                };
+                spawn_hit_particles(
+                    &mut cmd,
+                    &assets,
+                    p_transform.translation,
+                    material,
+                    p_entity.to_bits() ^ entity.to_bits(),
+                );
+
                 if target.is_some() {
                     // send event
                     attack_events.send(PlayerAttack {
                         entity,
                         num: projectile.num,
+                        pace: projectile.pace,
                     });
+
+                    // go through the target if it can take more
+                    if let Some(piercing) = piercing.as_mut() {
+                        piercing.remaining = piercing.remaining.saturating_sub(1);
+                        if piercing.remaining > 0 {
+                            piercing.hit.push(entity);
+                            continue;
+                        }
+                    }
                 }
                 // despawn the projectile (and respective light)
-                // TODO particles
                 cmd.entity(p_entity).despawn_recursive();
 
                 // should not hit any other target
@@ -113,3 +375,94 @@ pub fn projectile_collision(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    /// the speed of a projectile shot forward from the origin
+    /// after slowing down near a target at the given position
+    fn speed_near_target(target_pos: Vec3) -> f32 {
+        let mut world = World::new();
+        world.insert_resource(GameSettings {
+            slow_shots: true,
+            ..default()
+        });
+        let projectile = world
+            .spawn((
+                Transform::default(),
+                Velocity(Vec3::NEG_Z * 10.),
+                Projectile {
+                    num: Num::from_integer(2),
+                    speed: 10.,
+                    radius: PROJECTILE_RADIUS,
+                    pace: 0.,
+                },
+            ))
+            .id();
+        world.spawn((
+            Target::default(),
+            GlobalTransform::from_translation(target_pos),
+        ));
+        world.run_system_once(slow_projectiles_near_targets);
+        world.get::<Velocity>(projectile).unwrap().0.length()
+    }
+
+    #[test]
+    fn projectiles_slow_down_near_targets_ahead() {
+        let speed = speed_near_target(Vec3::new(0., 0., -3.));
+        assert!((speed - 5.).abs() < 1e-4, "{speed}");
+    }
+
+    #[test]
+    fn projectiles_ignore_targets_behind_them() {
+        assert_eq!(speed_near_target(Vec3::new(0., 0., 1.)), 10.);
+        assert_eq!(speed_near_target(Vec3::new(2., 0., 0.)), 10.);
+    }
+
+    #[test]
+    fn projectile_hit_bursts_into_particles() {
+        let mut world = World::new();
+        world.init_resource::<Assets<Mesh>>();
+        world.init_resource::<Assets<StandardMaterial>>();
+        world.init_resource::<ProjectileAssets>();
+        world.init_resource::<Events<PlayerAttack>>();
+        let projectile = world
+            .spawn((
+                Transform::default(),
+                Projectile {
+                    num: Num::from_integer(2),
+                    speed: 10.,
+                    radius: PROJECTILE_RADIUS,
+                    pace: 0.,
+                },
+            ))
+            .id();
+        world.spawn((
+            Target {
+                num: Num::from_integer(6),
+                ..default()
+            },
+            CollidableBox::new(Vec3::ONE),
+            Transform::default(),
+        ));
+
+        world.run_system_once(projectile_collision);
+
+        assert!(world.get_entity(projectile).is_none());
+        let effective_material = world
+            .resource::<ProjectileAssets>()
+            .particle_effective_material
+            .clone();
+        let particles: Vec<_> = world
+            .query_filtered::<&Handle<StandardMaterial>, With<Collapsing>>()
+            .iter(&world)
+            .collect();
+        assert_eq!(particles.len(), HIT_PARTICLE_COUNT as usize);
+        assert!(particles
+            .iter()
+            .all(|material| **material == effective_material));
+    }
+}
@@ -0,0 +1,276 @@
+//! Saving a run in progress when the player gives up,
+//! so that it can be continued later from the main menu.
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{logic::Num, persist::PersistentData};
+
+use super::{
+    levels::LevelId,
+    player::Player,
+    weapon::{install_weapon, PlayerWeapon, WeaponCube},
+    CurrentLevel, Health, LiveTime,
+};
+
+/// A snapshot of a run in progress
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveState {
+    /// the level the player was in
+    pub level: LevelId,
+    /// the player's health
+    pub health: f32,
+    /// the weapons collected
+    pub weapons: Vec<SavedWeapon>,
+    /// the time spent in the run, in seconds
+    pub time: f32,
+}
+
+/// A weapon of a saved run,
+/// with everything needed to give it back as it was
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavedWeapon {
+    pub num: Num,
+    /// the cooldown added per use
+    pub cooldown: f32,
+    /// how many targets a single shot can hit
+    pub pierce: u8,
+    /// whether shots steer towards the targets ahead
+    pub homing: bool,
+    /// how many shots were left (`None` if never used up)
+    pub ammo: Option<u32>,
+}
+
+impl From<&PlayerWeapon> for SavedWeapon {
+    fn from(weapon: &PlayerWeapon) -> Self {
+        SavedWeapon {
+            num: weapon.num,
+            cooldown: weapon.cooldown,
+            pierce: weapon.pierce,
+            homing: weapon.homing,
+            ammo: weapon.ammo,
+        }
+    }
+}
+
+impl SavedWeapon {
+    /// Read a weapon written by [`SavedWeapon::encode`].
+    ///
+    /// Runs saved with just the weapon number
+    /// get the default characteristics of that number.
+    fn decode(text: &str) -> Option<Self> {
+        let mut parts = text.split(':');
+        let num: Num = parts.next()?.parse().ok()?;
+        let Some(cooldown) = parts.next() else {
+            return Some(SavedWeapon {
+                num,
+                cooldown: PlayerWeapon::cooldown_for(num),
+                pierce: 0,
+                homing: false,
+                ammo: None,
+            });
+        };
+        let cooldown = cooldown.parse().ok()?;
+        let pierce = parts.next()?.parse().ok()?;
+        let homing = parts.next()? == "1";
+        let ammo = match parts.next()? {
+            "-" => None,
+            ammo => Some(ammo.parse().ok()?),
+        };
+        Some(SavedWeapon {
+            num,
+            cooldown,
+            pierce,
+            homing,
+            ammo,
+        })
+    }
+
+    fn encode(&self) -> String {
+        let ammo = match self.ammo {
+            Some(ammo) => ammo.to_string(),
+            None => "-".to_string(),
+        };
+        format!(
+            "{}:{}:{}:{}:{}",
+            self.num,
+            self.cooldown,
+            self.pierce,
+            u8::from(self.homing),
+            ammo
+        )
+    }
+
+    /// the weapon cube which gives this weapon back
+    fn to_cube(&self) -> WeaponCube {
+        WeaponCube {
+            num: self.num,
+            cooldown: self.cooldown,
+            pierce: self.pierce,
+            homing: self.homing,
+            ammo: self.ammo,
+        }
+    }
+}
+
+impl SaveState {
+    /// the key for persisting the saved run
+    const KEY: &'static str = "saved_run";
+
+    /// The saved run, if there is one.
+    ///
+    /// A saved run which cannot be read is ignored.
+    pub fn load(data: &PersistentData) -> Option<Self> {
+        Self::decode(data.get(Self::KEY)?)
+    }
+
+    /// Persist this run, replacing any other saved run
+    pub fn save(&self, data: &mut PersistentData) {
+        data.set(Self::KEY, self.encode());
+        data.save();
+    }
+
+    /// Discard the saved run
+    pub fn clear(data: &mut PersistentData) {
+        data.remove(Self::KEY);
+        data.save();
+    }
+
+    fn decode(text: &str) -> Option<Self> {
+        let mut parts = text.split(';');
+        let stage = parts.next()?.parse().ok()?;
+        let decisions = parts.next()?.parse().ok()?;
+        let health = parts.next()?.parse().ok()?;
+        let time = parts.next()?.parse().ok()?;
+        let weapons = match parts.next()? {
+            "" => vec![],
+            weapons => weapons
+                .split(',')
+                .map(SavedWeapon::decode)
+                .collect::<Option<_>>()?,
+        };
+        Some(SaveState {
+            level: LevelId { stage, decisions },
+            health,
+            weapons,
+            time,
+        })
+    }
+
+    fn encode(&self) -> String {
+        let weapons: Vec<_> = self.weapons.iter().map(SavedWeapon::encode).collect();
+        format!(
+            "{};{};{};{};{}",
+            self.level.stage,
+            self.level.decisions,
+            self.health,
+            self.time,
+            weapons.join(",")
+        )
+    }
+}
+
+/// Global resource for the saved run to continue
+/// when the live game starts
+#[derive(Debug, Default, Resource)]
+pub struct ResumeRun(pub Option<SaveState>);
+
+/// take a snapshot of the run in progress
+pub fn capture_run(
+    current_level: &CurrentLevel,
+    live_time: &LiveTime,
+    player_health: &Health,
+    weapon_q: &Query<&PlayerWeapon>,
+) -> SaveState {
+    SaveState {
+        level: current_level.id,
+        health: player_health.value,
+        weapons: weapon_q.iter().map(SavedWeapon::from).collect(),
+        time: live_time.elapsed_seconds(),
+    }
+}
+
+/// go back to the level and time of the saved run to continue, if any
+pub fn apply_resume_level(
+    resume_run: &ResumeRun,
+    current_level: &mut CurrentLevel,
+    live_time: &mut LiveTime,
+) {
+    if let Some(state) = &resume_run.0 {
+        current_level.jump_to(state.level);
+        live_time
+            .0
+            .set_elapsed(Duration::from_secs_f32(state.time.max(0.)));
+    }
+}
+
+/// system that gives the player back their health and weapons
+/// from the saved run to continue, once the level is set up
+pub fn restore_saved_run(
+    mut cmd: Commands,
+    mut resume_run: ResMut<ResumeRun>,
+    mut player_q: Query<&mut Health, With<Player>>,
+) {
+    let Some(state) = resume_run.0.take() else {
+        return;
+    };
+    if let Ok(mut health) = player_q.get_single_mut() {
+        health.value = state.health.clamp(1., health.max);
+    }
+    for weapon in &state.weapons {
+        install_weapon(&mut cmd, &weapon.to_cube());
+    }
+    info!("Continuing saved run at level {}", state.level);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_state_round_trips() {
+        let state = SaveState {
+            level: LevelId {
+                stage: 3,
+                decisions: 2,
+            },
+            health: 2.5,
+            weapons: vec![
+                SavedWeapon {
+                    num: 2.into(),
+                    cooldown: 1.,
+                    pierce: 0,
+                    homing: false,
+                    ammo: None,
+                },
+                SavedWeapon {
+                    num: Num::new(-3, 4),
+                    cooldown: 1.35,
+                    pierce: 3,
+                    homing: true,
+                    ammo: Some(4),
+                },
+            ],
+            time: 123.5,
+        };
+        let mut data = PersistentData::default();
+        data.set(SaveState::KEY, state.encode());
+
+        assert_eq!(SaveState::load(&data), Some(state));
+    }
+
+    #[test]
+    fn old_saves_get_default_weapons() {
+        let mut data = PersistentData::default();
+        data.set(SaveState::KEY, "1;0;3;60;2,1/8");
+
+        let state = SaveState::load(&data).unwrap();
+        let nums: Vec<Num> = state.weapons.iter().map(|weapon| weapon.num).collect();
+        assert_eq!(nums, vec![2.into(), Num::new(1, 8)]);
+        assert_eq!(
+            state.weapons[1].cooldown,
+            PlayerWeapon::cooldown_for(Num::new(1, 8))
+        );
+        assert_eq!(state.weapons[1].ammo, None);
+    }
+}
@@ -3,22 +3,44 @@ use tinyrand::{Rand, Seeded, SplitMix};
 
 use crate::{
     assets::TextureHandles,
-    effect::{Glimmers, Wobbles},
+    educator::EducatorConfig,
+    effect::{CameraShake, Glimmers, Wobbles},
     live::OnLive,
+    persist::PersistentData,
     postprocess::PostProcessSettings,
-    CameraMarker,
+    CameraMarker, GameSettings,
 };
 
 use crate::structure;
 
 use super::{
-    levels::{CurrentLevel, Thing, ThingKind},
+    icon::NumStyle,
+    interlude::Banner,
+    levels::{CurrentLevel, LevelSpec, Thing, ThingKind},
+    memory::MemoryChallenge,
     mob::{MobSpawnerBundle, Randomness},
     phase::{Dread, MoveOn, PhaseTrigger},
     player::spawn_player,
-    weapon::{spawn_weapon_cube, WeaponCubeAssets},
+    stats::LevelDeaths,
+    weapon::{spawn_weapon_cube, PlayerWeapon, WeaponCubeAssets},
+    SessionSeed,
 };
 
+/// how far along the corridor the dread should be at the earliest
+/// in a calm start
+/// (after the first wizard message)
+const CALM_DREAD_AT: f32 = 0.45;
+
+/// the player's health at the start of a level
+const PLAYER_HEALTH: f32 = 8.;
+
+/// the player's health at the start of a level eased by assist drift
+const ASSISTED_PLAYER_HEALTH: f32 = 12.;
+
+/// how much longer mob spawners wait between mobs
+/// in a level eased by assist drift
+const ASSISTED_SPAWN_SLOWDOWN: f32 = 1.5;
+
 /// set up the main 3D scene
 /// based on the current level specification
 pub fn setup_scene(
@@ -28,13 +50,24 @@ pub fn setup_scene(
     mut materials: ResMut<Assets<StandardMaterial>>,
     weapon_cube_assets: Res<WeaponCubeAssets>,
     current_level: Res<CurrentLevel>,
+    educator_config: Res<EducatorConfig>,
+    game_settings: Res<GameSettings>,
+    session_seed: Res<SessionSeed>,
+    persistent_data: Res<PersistentData>,
+    level_deaths: Res<LevelDeaths>,
 ) {
     let CurrentLevel {
-        id: _,
+        id: level_id,
         spec: level_spec,
     } = &*current_level;
     let corridor_length = level_spec.corridor_length;
 
+    // go easier on a player who keeps losing in this level
+    let assisted = level_deaths.should_assist(*level_id, &game_settings);
+    if assisted {
+        info!("Assist drift is easing this level");
+    }
+
     let wall_texture_handle = texture_handles.wall.clone();
     let floor_texture_handle = texture_handles.floor.clone();
     let ceil_texture_handle = texture_handles.ceil.clone();
@@ -124,7 +157,13 @@ pub fn setup_scene(
     );
 
     // add the player, attach a camera to it, then add a light to the camera
-    spawn_player(&mut cmd, Vec3::new(0., 2.5, 0.)).with_children(|cmd| {
+    let base_health = if assisted {
+        ASSISTED_PLAYER_HEALTH
+    } else {
+        PLAYER_HEALTH
+    };
+    let player_health = base_health * game_settings.difficulty.health_factor();
+    spawn_player(&mut cmd, Vec3::new(0., 2.5, 0.), player_health).with_children(|cmd| {
         // wobbly pivot point for the camera and light
         cmd.spawn((
             TransformBundle::default(),
@@ -132,78 +171,122 @@ pub fn setup_scene(
             Wobbles::default(),
         ))
         .with_children(|cmd| {
-            // camera
+            // shaking node for the camera, separate from the wobble
             cmd.spawn((
-                CameraMarker,
-                IsDefaultUiCamera,
-                Camera3dBundle {
-                    camera: Camera {
-                        clear_color: ClearColorConfig::Custom(Color::BLACK),
-                        ..default()
-                    },
-                    transform: Transform::from_translation(Vec3::new(0., 0.5, 0.5))
-                        .looking_to(Dir3::Z, Dir3::Y),
-                    // slightly lower exposure from default,
-                    // which results in a bit more brightness and less color saturation
-                    exposure: Exposure { ev100: 9.1 },
-                    ..default()
-                },
-                InheritedVisibility::HIDDEN,
-                FogSettings {
-                    color: Color::BLACK,
-                    falloff: FogFalloff::Linear {
-                        start: 66.,
-                        end: 72.,
-                    },
-                    ..default()
-                },
-                PostProcessSettings {
-                    oscillate: 0.,
-                    ..default()
-                },
+                TransformBundle::default(),
+                VisibilityBundle::default(),
+                CameraShake::new(game_settings.screen_shake),
             ))
             .with_children(|cmd| {
-                // light
+                // camera
                 cmd.spawn((
-                    PointLightBundle {
-                        point_light: PointLight {
-                            color: Color::srgba_u8(255, 255, 224, 255),
-                            shadows_enabled: true,
-                            intensity: 4_400_000.,
-                            range: 62.,
-                            shadow_depth_bias: 0.1,
+                    CameraMarker,
+                    IsDefaultUiCamera,
+                    // hear spatial sounds from the point of view of the camera
+                    SpatialListener::new(0.5),
+                    Camera3dBundle {
+                        camera: Camera {
+                            clear_color: ClearColorConfig::Custom(Color::BLACK),
                             ..default()
                         },
-                        transform: Transform::from_xyz(0., 1., 4.0),
+                        transform: Transform::from_translation(Vec3::new(0., 0.5, 0.5))
+                            .looking_to(Dir3::Z, Dir3::Y),
+                        // slightly lower exposure from default,
+                        // which results in a bit more brightness and less color saturation
+                        exposure: Exposure { ev100: 9.1 },
                         ..default()
                     },
-                    Glimmers {
-                        amplitude_min: 48.,
-                        amplitude_max: 64.,
+                    InheritedVisibility::HIDDEN,
+                    FogSettings {
+                        color: Color::BLACK,
+                        falloff: FogFalloff::Linear {
+                            start: 66.,
+                            end: 72.,
+                        },
+                        ..default()
                     },
-                ));
+                    PostProcessSettings {
+                        oscillate: 0.,
+                        ..default()
+                    },
+                ))
+                .with_children(|cmd| {
+                    // light
+                    cmd.spawn((
+                        PointLightBundle {
+                            point_light: PointLight {
+                                color: level_spec.light_color,
+                                shadows_enabled: true,
+                                intensity: level_spec.light_intensity,
+                                range: 62.,
+                                shadow_depth_bias: 0.1,
+                                ..default()
+                            },
+                            transform: Transform::from_xyz(0., 1., 4.0),
+                            ..default()
+                        },
+                        Glimmers {
+                            amplitude_min: 48.,
+                            amplitude_max: 64.,
+                        },
+                    ));
+                });
             });
         });
     });
 
     // set up base RNG
 
-    let mut rng = SplitMix::seed(level_spec.rng_seed);
+    let mut rng = SplitMix::seed(session_seed.level_seed(level_spec.rng_seed, &game_settings));
 
-    // add things in the level
+    // add things in the level,
+    // then the bonus room if enabled
+    // (last, so that it takes nothing from the RNG of the others)
 
-    for Thing { at, what } in &level_spec.things {
+    let bonus_room = LevelSpec::bonus_room(*level_id).filter(|_| game_settings.bonus_rooms);
+    for Thing { at, what } in level_spec.things.iter().chain(&bonus_room) {
         match what {
-            ThingKind::WeaponCube { x, num } => {
+            ThingKind::WeaponCube {
+                x,
+                num,
+                cooldown,
+                pierce,
+                homing,
+                ammo,
+            } => {
+                if !educator_config.allows_weapon(*num) {
+                    continue;
+                }
                 spawn_weapon_cube(
                     &mut cmd,
                     &weapon_cube_assets,
                     &mut materials,
                     Vec3::new(*x, 1.75, *at * corridor_length),
                     *num,
+                    cooldown.unwrap_or_else(|| PlayerWeapon::cooldown_for(*num)),
+                    *pierce,
+                    *homing,
+                    *ammo,
+                    NumStyle::from(&*game_settings),
                 );
             }
             ThingKind::MobSpawner(spawner) => {
+                // always take the seed, so that the others stay the same
+                let seed = rng.next_u64();
+
+                let mut spawner = spawner.clone();
+                spawner
+                    .target_options
+                    .retain(|num| educator_config.allows_target(*num));
+                if spawner.target_options.is_empty() {
+                    // no numbers left for this wave
+                    continue;
+                }
+                game_settings.difficulty.adjust_spawner(&mut spawner);
+                if assisted {
+                    spawner.spawn_interval *= ASSISTED_SPAWN_SLOWDOWN;
+                }
+
                 cmd.spawn(MobSpawnerBundle {
                     phase_trigger: PhaseTrigger::new_by_corridor(corridor_length, *at),
                     transform: Transform::from_translation(Vec3::new(
@@ -212,12 +295,29 @@ pub fn setup_scene(
                         *at * corridor_length,
                     )),
                     random: Randomness {
-                        rng: SplitMix::seed(rng.next_u64()),
+                        rng: SplitMix::seed(seed),
                     },
-                    spawner: spawner.clone(),
+                    spawner,
                     on_live: OnLive,
                 });
             }
+            ThingKind::MemoryChallenge { count } => {
+                // always take the seed, so that the others stay the same
+                let seed = rng.next_u64();
+
+                if !game_settings.bonus_rooms {
+                    continue;
+                }
+                cmd.spawn((
+                    OnLive,
+                    PhaseTrigger::new_by_corridor(corridor_length, *at),
+                    Transform::from_translation(Vec3::new(0., 4., *at * corridor_length)),
+                    Randomness {
+                        rng: SplitMix::seed(seed),
+                    },
+                    MemoryChallenge { count: *count },
+                ));
+            }
             ThingKind::Interlude(spec) => {
                 cmd.spawn((
                     OnLive,
@@ -225,12 +325,26 @@ pub fn setup_scene(
                     spec.clone(),
                 ));
             }
-            ThingKind::Dread => {
-                // a custom effect which happens in the first level
+            ThingKind::Banner { text, duration } => {
                 cmd.spawn((
                     OnLive,
                     PhaseTrigger::new_by_corridor(corridor_length, *at),
-                    Dread,
+                    Banner {
+                        text: text.clone(),
+                        duration: *duration,
+                    },
+                ));
+            }
+            ThingKind::Dread => {
+                // a custom effect which happens in the first level,
+                // which is toned down and delayed until after the tutorial
+                // for first-time players who prefer a calm start
+                let calm = game_settings.calm_start && persistent_data.is_first_run();
+                let at = if calm { at.max(CALM_DREAD_AT) } else { *at };
+                cmd.spawn((
+                    OnLive,
+                    PhaseTrigger::new_by_corridor(corridor_length, at),
+                    Dread { calm },
                 ));
             }
             ThingKind::MoveOn => {
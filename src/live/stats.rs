@@ -0,0 +1,557 @@
+//! Statistics about the player's performance
+
+use std::collections::{HashMap, VecDeque};
+
+use bevy::prelude::*;
+
+use super::{
+    hitstop::HitStop,
+    levels::{Ending, LevelId},
+    player::AttackResolved,
+    weapon::PlayerWeapon,
+    LiveTime,
+};
+use crate::{
+    logic::{can_damage, target_score, AttackTest, Num, TargetRule},
+    persist::PersistentData,
+    postprocess::PostProcessSettings,
+    GameSettings,
+};
+
+/// Resource for statistics of the level being played
+#[derive(Debug, Default, Resource)]
+pub struct LevelStats {
+    /// live time at the start of the level, in seconds
+    pub started_at: f32,
+    /// number of attacks which hit a target
+    pub attacks: u32,
+    /// number of those attacks which failed
+    pub failed_attacks: u32,
+    /// total damage taken by the player
+    pub damage_taken: f32,
+    /// whether the level was made easier by assist drift
+    pub assisted: bool,
+}
+
+impl LevelStats {
+    /// start counting from scratch
+    pub fn reset(&mut self, started_at: f32) {
+        *self = LevelStats {
+            started_at,
+            ..default()
+        };
+    }
+
+    /// The fraction of attacks which were effective
+    /// (1 if there were no attacks at all)
+    pub fn accuracy(&self) -> f32 {
+        if self.attacks == 0 {
+            return 1.;
+        }
+        1. - self.failed_attacks as f32 / self.attacks as f32
+    }
+
+    /// Rate the level from 1 to 3 stars,
+    /// given the time taken and the par time.
+    ///
+    /// Starting from 3 stars,
+    /// one star is lost for going over par,
+    /// and another for poor accuracy or taking too much damage.
+    pub fn rating(&self, time_taken: f32, par_time: f32) -> u8 {
+        let mut stars = 3;
+        if time_taken > par_time {
+            stars -= 1;
+        }
+        if self.accuracy() < 0.75 || self.damage_taken >= 3. {
+            stars -= 1;
+        }
+        stars
+    }
+}
+
+/// Resource for statistics of the whole run,
+/// from the start of the game until an ending is reached
+#[derive(Debug, Default, Resource)]
+pub struct RunStats {
+    /// number of attacks which hit a target
+    attacks: u32,
+    /// number of those attacks which failed
+    failed_attacks: u32,
+}
+
+impl RunStats {
+    pub fn reset(&mut self) {
+        *self = RunStats::default();
+    }
+
+    /// The fraction of attacks which were effective
+    /// (1 if there were no attacks at all)
+    pub fn accuracy(&self) -> f32 {
+        if self.attacks == 0 {
+            return 1.;
+        }
+        1. - self.failed_attacks as f32 / self.attacks as f32
+    }
+}
+
+/// The outcome of a run which reached an ending
+#[derive(Debug, Copy, Clone)]
+pub struct RunSummary {
+    pub ending: Ending,
+    /// live time taken, in seconds
+    pub time: f32,
+    /// the fraction of attacks which were effective
+    pub accuracy: f32,
+    /// whether cheats were used during the run
+    pub used_cheats: bool,
+}
+
+/// system that records the outcome of each resolved attack
+/// into the level and run statistics, the recent attacks,
+/// and the targets failed
+pub fn record_resolved_attacks(
+    mut events: EventReader<AttackResolved>,
+    mut level_stats: ResMut<LevelStats>,
+    mut run_stats: ResMut<RunStats>,
+    mut recent_attacks: ResMut<RecentAttacks>,
+    mut failed_targets: ResMut<FailedTargets>,
+) {
+    for AttackResolved {
+        entity,
+        result,
+        attack,
+        target_num,
+        target_rule,
+        ..
+    } in events.read()
+    {
+        debug!("Attack of {attack} on {entity:?} ({target_num}): {result:?}");
+        let failed = matches!(result, AttackTest::Failed | AttackTest::Overshot(_));
+        level_stats.attacks += 1;
+        run_stats.attacks += 1;
+        if failed {
+            level_stats.failed_attacks += 1;
+            run_stats.failed_attacks += 1;
+            failed_targets.push(*target_num, *target_rule);
+        }
+        recent_attacks.push(!failed);
+    }
+}
+
+/// Resource for the player's score in the current run
+#[derive(Debug, Default, Resource)]
+pub struct Score(pub u32);
+
+impl Score {
+    pub fn reset(&mut self) {
+        self.0 = 0;
+    }
+}
+
+/// Resource for the effective attacks made in a row,
+/// multiplying the points of each attack
+#[derive(Debug, Default, Resource)]
+pub struct Combo {
+    /// the number of effective attacks in a row
+    pub count: u32,
+    /// live time of the last effective attack, in seconds
+    pub last_hit_time: f32,
+}
+
+impl Combo {
+    /// how long the combo lasts without another effective attack, in seconds
+    const TIMEOUT: f32 = 4.;
+    /// every how many hits in a row the combo is celebrated
+    const MILESTONE: u32 = 5;
+    /// how much the multiplier grows with each hit after the first
+    const MULTIPLIER_STEP: f32 = 0.1;
+    /// the highest multiplier of the points of an attack
+    const MAX_MULTIPLIER: f32 = 3.;
+
+    pub fn reset(&mut self) {
+        *self = Combo::default();
+    }
+
+    /// the number of hits in a row at the given live time,
+    /// which is 0 if the combo timed out
+    pub fn current(&self, time: f32) -> u32 {
+        if time - self.last_hit_time > Self::TIMEOUT {
+            0
+        } else {
+            self.count
+        }
+    }
+
+    /// Count an effective attack at the given live time,
+    /// returning whether the combo reached a milestone.
+    pub fn hit(&mut self, time: f32) -> bool {
+        self.count = self.current(time) + 1;
+        self.last_hit_time = time;
+        self.count % Self::MILESTONE == 0
+    }
+
+    /// end the combo after a failed attack
+    pub fn miss(&mut self) {
+        self.count = 0;
+    }
+
+    /// how much the points of an attack are multiplied
+    pub fn multiplier(&self) -> f32 {
+        let bonus = self.count.saturating_sub(1) as f32 * Self::MULTIPLIER_STEP;
+        (1. + bonus).min(Self::MAX_MULTIPLIER)
+    }
+}
+
+/// system that adds points to the score for every effective attack,
+/// with bonuses for attacking in quick succession
+/// (while the player's cooldown was still high when shooting)
+/// and for effective attacks in a row
+pub fn score_attacks(
+    mut events: EventReader<AttackResolved>,
+    mut score: ResMut<Score>,
+    mut combo: ResMut<Combo>,
+    live_time: Res<LiveTime>,
+    mut postprocess_settings_q: Query<&mut PostProcessSettings>,
+    mut hit_stop: ResMut<HitStop>,
+) {
+    let time = live_time.elapsed_seconds();
+    for AttackResolved {
+        result,
+        target_num,
+        pace,
+        ..
+    } in events.read()
+    {
+        match result {
+            AttackTest::Effective(_) => {
+                let milestone = combo.hit(time);
+                let points = target_score(*target_num) as f32 * (1. + *pace) * combo.multiplier();
+                score.0 += points.round() as u32;
+                if milestone {
+                    // a brief flash to celebrate
+                    if let Ok(mut settings) = postprocess_settings_q.get_single_mut() {
+                        settings.add_intensity(0.2);
+                    }
+                    hit_stop.trigger(HitStop::COMBO_MILESTONE);
+                }
+            }
+            AttackTest::Failed | AttackTest::Overshot(_) => combo.miss(),
+            AttackTest::Progress(_) => {}
+        }
+    }
+}
+
+/// Resource keeping the targets which the player recently failed to attack
+/// during this session,
+/// so that they can be given a second chance at them
+/// (see [`MobSpawner::reusing_failed`](super::mob::MobSpawner::reusing_failed)).
+///
+/// Whether each target can be damaged with the player's weapons
+/// is worked out when it is recorded or when the weapons change,
+/// not every time a mob is spawned.
+#[derive(Debug, Default, Resource)]
+pub struct FailedTargets {
+    /// the targets, along with whether they can be damaged
+    targets: VecDeque<(Num, TargetRule, bool)>,
+    /// the numbers of the player's weapons
+    weapons: Vec<Num>,
+}
+
+impl FailedTargets {
+    /// how many failed targets are kept
+    const CAPACITY: usize = 8;
+
+    /// record a target which the player failed to attack
+    pub fn push(&mut self, num: Num, rule: TargetRule) {
+        if self
+            .targets
+            .iter()
+            .any(|&(other_num, other_rule, _)| (other_num, other_rule) == (num, rule))
+        {
+            return;
+        }
+        if self.targets.len() >= Self::CAPACITY {
+            self.targets.pop_front();
+        }
+        let solvable = can_damage(rule, num, &self.weapons);
+        self.targets.push_back((num, rule, solvable));
+    }
+
+    /// update the numbers of the player's weapons,
+    /// working out again which targets can be damaged if they changed
+    pub fn set_weapons(&mut self, mut weapons: Vec<Num>) {
+        weapons.sort_unstable();
+        weapons.dedup();
+        if weapons == self.weapons {
+            return;
+        }
+        self.weapons = weapons;
+        for (num, rule, solvable) in &mut self.targets {
+            *solvable = can_damage(*rule, *num, &self.weapons);
+        }
+    }
+
+    /// Take out the most recent failed target
+    /// which can be damaged with the player's weapons
+    pub fn take_solvable(&mut self) -> Option<(Num, TargetRule)> {
+        let index = self
+            .targets
+            .iter()
+            .rposition(|&(_, _, solvable)| solvable)?;
+        self.targets.remove(index).map(|(num, rule, _)| (num, rule))
+    }
+}
+
+/// system that keeps the failed targets up to date
+/// with the weapons which the player has
+pub fn update_failed_target_weapons(
+    weapon_q: Query<&PlayerWeapon>,
+    mut failed_targets: ResMut<FailedTargets>,
+) {
+    let weapons: Vec<Num> = weapon_q.iter().map(|weapon| weapon.num).collect();
+    failed_targets.set_weapons(weapons);
+}
+
+/// Resource keeping whether the most recent attacks were effective,
+/// for grading the player as they play
+#[derive(Debug, Default, Resource)]
+pub struct RecentAttacks {
+    outcomes: VecDeque<bool>,
+}
+
+impl RecentAttacks {
+    /// how many attacks are considered for the grade
+    const WINDOW: usize = 20;
+
+    /// record the outcome of an attack
+    pub fn push(&mut self, effective: bool) {
+        if self.outcomes.len() >= Self::WINDOW {
+            self.outcomes.pop_front();
+        }
+        self.outcomes.push_back(effective);
+    }
+
+    pub fn reset(&mut self) {
+        self.outcomes.clear();
+    }
+
+    /// The letter grade (A to F) of the recent attacks,
+    /// or `None` if there were no attacks yet
+    pub fn grade(&self) -> Option<char> {
+        if self.outcomes.is_empty() {
+            return None;
+        }
+        let effective = self.outcomes.iter().filter(|effective| **effective).count();
+        let accuracy = effective as f32 / self.outcomes.len() as f32;
+        let grade = if accuracy >= 0.9 {
+            'A'
+        } else if accuracy >= 0.8 {
+            'B'
+        } else if accuracy >= 0.7 {
+            'C'
+        } else if accuracy >= 0.6 {
+            'D'
+        } else {
+            'F'
+        };
+        Some(grade)
+    }
+}
+
+/// Resource counting how many times
+/// the player was defeated in each level
+/// since last clearing it
+#[derive(Debug, Default, Resource)]
+pub struct LevelDeaths {
+    counts: HashMap<LevelId, u32>,
+}
+
+impl LevelDeaths {
+    /// number of defeats after which assist drift eases the level
+    const ASSIST_THRESHOLD: u32 = 3;
+
+    /// number of defeats after which the defeat screen offers a hint
+    const HINT_THRESHOLD: u32 = 2;
+
+    /// record a defeat in the given level
+    pub fn record(&mut self, level: LevelId) {
+        *self.counts.entry(level).or_default() += 1;
+    }
+
+    /// forget about previous defeats in the given level,
+    /// usually because it was cleared
+    pub fn clear(&mut self, level: LevelId) {
+        self.counts.remove(&level);
+    }
+
+    /// the number of defeats in the given level since last clearing it
+    pub fn count(&self, level: LevelId) -> u32 {
+        self.counts.get(&level).copied().unwrap_or(0)
+    }
+
+    /// A hint for the defeat screen
+    /// after being defeated a few times in the given level,
+    /// empty otherwise
+    pub fn retry_hint(&self, level: LevelId, settings: &GameSettings) -> String {
+        let count = self.count(level);
+        if count < Self::HINT_THRESHOLD {
+            String::new()
+        } else if settings.assist_drift && count >= Self::ASSIST_THRESHOLD {
+            format!("Defeated {count} times here. The next attempt will be easier.")
+        } else {
+            format!("Defeated {count} times here. Take your time, and check each number before attacking.")
+        }
+    }
+
+    /// Whether the given level should be made easier
+    /// (only if assist drift is enabled)
+    pub fn should_assist(&self, level: LevelId, settings: &GameSettings) -> bool {
+        settings.assist_drift && self.count(level) >= Self::ASSIST_THRESHOLD
+    }
+}
+
+/// The best rating ever obtained in the given level
+pub fn best_rating(data: &PersistentData, level: LevelId) -> Option<u8> {
+    data.get(&rating_key(level))?.parse().ok()
+}
+
+/// Record a new rating for a level,
+/// saving it only if it is better than the previous one.
+///
+/// Returns whether it was a new best.
+pub fn record_rating(data: &mut PersistentData, level: LevelId, rating: u8) -> bool {
+    if best_rating(data, level).is_some_and(|best| best >= rating) {
+        return false;
+    }
+    data.set(rating_key(level), rating);
+    data.save();
+    true
+}
+
+fn rating_key(level: LevelId) -> String {
+    format!("rating.{}.{}", level.stage, level.decisions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn divide(num: i16) -> (Num, TargetRule) {
+        (Num::from_integer(num), TargetRule::Factorize)
+    }
+
+    #[test]
+    fn failed_targets_follow_the_player_weapons() {
+        let mut failed = FailedTargets::default();
+        failed.set_weapons(vec![Num::from_integer(2)]);
+        for (num, rule) in [divide(9), divide(8), divide(15)] {
+            failed.push(num, rule);
+        }
+
+        // 15 is the most recent, but it cannot be divided by 2
+        assert_eq!(failed.take_solvable(), Some(divide(8)));
+        assert_eq!(failed.take_solvable(), None);
+
+        // picking up a 3 makes the others solvable
+        failed.set_weapons(vec![Num::from_integer(3), Num::from_integer(2)]);
+        assert_eq!(failed.take_solvable(), Some(divide(15)));
+        assert_eq!(failed.take_solvable(), Some(divide(9)));
+        assert_eq!(failed.take_solvable(), None);
+    }
+
+    #[test]
+    fn failed_targets_are_recorded_once() {
+        let mut failed = FailedTargets::default();
+        failed.set_weapons(vec![Num::from_integer(2)]);
+        failed.push(divide(4).0, divide(4).1);
+        failed.push(divide(4).0, divide(4).1);
+        assert_eq!(failed.take_solvable(), Some(divide(4)));
+        assert_eq!(failed.take_solvable(), None);
+    }
+
+    #[test]
+    fn level_deaths_are_counted_per_level() {
+        let first = LevelId::new(1, 0).unwrap();
+        let second = LevelId::new(1, 1).unwrap();
+        let mut deaths = LevelDeaths::default();
+        deaths.record(first);
+        deaths.record(second);
+        deaths.record(first);
+        assert_eq!(deaths.count(first), 2);
+        assert_eq!(deaths.count(second), 1);
+
+        // clearing a level only forgets its own defeats
+        deaths.clear(second);
+        assert_eq!(deaths.count(first), 2);
+        assert_eq!(deaths.count(second), 0);
+    }
+
+    #[test]
+    fn retry_hint_shows_after_repeated_defeats() {
+        let level = LevelId::new(2, 1).unwrap();
+        let mut settings = GameSettings::default();
+        let mut deaths = LevelDeaths::default();
+        deaths.record(level);
+        assert_eq!(deaths.retry_hint(level, &settings), "");
+
+        deaths.record(level);
+        let hint = deaths.retry_hint(level, &settings);
+        assert!(hint.starts_with("Defeated 2 times here"), "{hint}");
+
+        deaths.record(level);
+        assert!(!deaths.should_assist(level, &settings));
+        settings.assist_drift = true;
+        assert!(deaths.should_assist(level, &settings));
+        let hint = deaths.retry_hint(level, &settings);
+        assert!(hint.ends_with("The next attempt will be easier."), "{hint}");
+
+        // defeats elsewhere do not count
+        let other = LevelId::new(2, 0).unwrap();
+        assert_eq!(deaths.retry_hint(other, &settings), "");
+    }
+
+    #[test]
+    fn combo_grows_with_effective_attacks_in_a_row() {
+        let mut combo = Combo::default();
+        assert_eq!(combo.multiplier(), 1.);
+
+        let milestones: Vec<_> = (1..=5).map(|i| combo.hit(i as f32)).collect();
+        assert_eq!(milestones, vec![false, false, false, false, true]);
+        assert_eq!(combo.current(5.), 5);
+        assert!(combo.multiplier() > 1.);
+
+        // the multiplier does not grow forever
+        for i in 6..100 {
+            combo.hit(i as f32);
+        }
+        assert_eq!(combo.multiplier(), Combo::MAX_MULTIPLIER);
+    }
+
+    #[test]
+    fn combo_times_out_without_attacks() {
+        let mut combo = Combo::default();
+        combo.hit(1.);
+        combo.hit(2.);
+        assert_eq!(combo.current(2. + Combo::TIMEOUT), 2);
+        assert_eq!(combo.current(2.5 + Combo::TIMEOUT), 0);
+
+        // the next hit starts over
+        combo.hit(2.5 + Combo::TIMEOUT);
+        assert_eq!(combo.count, 1);
+        assert_eq!(combo.multiplier(), 1.);
+    }
+
+    #[test]
+    fn combo_ends_on_a_failed_attack() {
+        let mut combo = Combo::default();
+        combo.hit(1.);
+        combo.hit(2.);
+        combo.miss();
+        assert_eq!(combo.current(2.), 0);
+        assert_eq!(combo.multiplier(), 1.);
+
+        combo.hit(3.);
+        combo.reset();
+        assert_eq!(combo.current(3.), 0);
+    }
+}
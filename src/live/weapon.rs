@@ -1,6 +1,7 @@
 use bevy::{
     input::{
         keyboard::{Key, KeyboardInput},
+        mouse::MouseWheel,
         ButtonState,
     },
     prelude::*,
@@ -9,13 +10,15 @@ use bevy_mod_picking::prelude::*;
 
 use crate::{
     assets::AudioHandles,
-    effect::{Rotating, TimeToLive, Velocity},
+    effect::{CameraShake, FadesAway, Rotating, TimeToLive, Velocity},
     logic::Num,
     postprocess::PostProcessSettings,
+    ui::Toasts,
+    GameSettings,
 };
 
 use super::{
-    icon::spawn_icon,
+    icon::{spawn_icon, spawn_num_text, FractionBar, NumStyle},
     player::Player,
     projectile::{spawn_projectile, ProjectileAssets},
     OnLive, WeaponListNode,
@@ -31,15 +34,60 @@ pub struct PlayerWeapon {
     pub projectile_speed: f32,
     /// the amount of cooldown added per use
     pub cooldown: f32,
+    /// how many targets a single shot can hit
+    /// (0 or 1 for just one)
+    pub pierce: u8,
+    /// whether shots steer towards the targets ahead
+    pub homing: bool,
+    /// how many shots are left before the weapon is used up
+    /// (`None` if it is never used up)
+    pub ammo: Option<u32>,
 }
 
 impl PlayerWeapon {
-    pub fn new(num: Num) -> Self {
+    /// the cooldown added per use of a weapon of 2
+    pub const DEFAULT_COOLDOWN: f32 = 1.;
+
+    /// the cooldown added per use for each unit
+    /// of numerator plus denominator above that of 2
+    const COOLDOWN_PER_MAGNITUDE: f32 = 0.05;
+
+    /// the bounds of the cooldown added per use
+    /// (always below the maximum cooldown of the player,
+    /// so that a single attack never locks the weapon)
+    const COOLDOWN_RANGE: (f32, f32) = (0.8, 1.6);
+
+    /// The cooldown added per use of a weapon with the given number,
+    /// unless the level says otherwise.
+    ///
+    /// Bigger and more complex numbers are more powerful,
+    /// so they take a bit longer to cool down:
+    /// a 1/8 weapon takes longer than a 2.
+    pub fn cooldown_for(num: Num) -> f32 {
+        let num = num.reduced();
+        let magnitude =
+            f32::from(num.numer().unsigned_abs()) + f32::from(num.denom().unsigned_abs());
+        let (min, max) = Self::COOLDOWN_RANGE;
+        (Self::DEFAULT_COOLDOWN + (magnitude - 3.) * Self::COOLDOWN_PER_MAGNITUDE).clamp(min, max)
+    }
+
+    pub fn new(num: Num, cooldown: f32) -> Self {
         Self {
             num,
+            cooldown,
             ..Default::default()
         }
     }
+
+    /// Refresh the ammo of this weapon as it is picked up again,
+    /// keeping whichever has more shots
+    /// (never used up if either is never used up)
+    pub fn refresh_ammo(&mut self, ammo: Option<u32>) {
+        self.ammo = match (self.ammo, ammo) {
+            (Some(left), Some(new)) => Some(left.max(new)),
+            _ => None,
+        };
+    }
 }
 
 impl Default for PlayerWeapon {
@@ -47,13 +95,33 @@ impl Default for PlayerWeapon {
         Self {
             projectile_speed: 30.,
             num: 0.into(),
-            cooldown: 1.,
+            cooldown: Self::DEFAULT_COOLDOWN,
+            pierce: 0,
+            homing: false,
+            ammo: None,
         }
     }
 }
 
-pub fn install_weapon(cmd: &mut Commands, num: Num) {
-    cmd.spawn((OnLive, PlayerWeapon::new(num)));
+/// give the player the weapon of the given cube
+pub fn install_weapon(cmd: &mut Commands, cube: &WeaponCube) {
+    cmd.spawn((
+        OnLive,
+        PlayerWeapon {
+            pierce: cube.pierce,
+            homing: cube.homing,
+            ammo: cube.ammo,
+            ..PlayerWeapon::new(cube.num, cube.cooldown)
+        },
+    ));
+}
+
+/// look for the weapon in the player's arsenal with the given number
+fn find_weapon<'a>(
+    weapon_q: &'a mut Query<&mut PlayerWeapon>,
+    num: Num,
+) -> Option<Mut<'a, PlayerWeapon>> {
+    weapon_q.iter_mut().find(|weapon| weapon.num == num)
 }
 
 /// Marker component representing the weapon currently wielded by the player.
@@ -62,38 +130,83 @@ pub fn install_weapon(cmd: &mut Commands, num: Num) {
 #[derive(Debug, Default, Component)]
 pub struct WeaponSelected;
 
+/// Resource for the number of the weapon last selected by the player,
+/// so that it can be selected again once it is picked up in a new level.
+#[derive(Debug, Default, Resource)]
+pub struct PreferredWeapon(pub Option<Num>);
+
 /// system that processes the addition of new weapons
+///
+/// The selection is decided once for all weapons added in the same frame,
+/// so that only one of them ends up selected.
 pub fn process_new_weapon(
     mut cmd: Commands,
     weapon_q: Query<(Entity, &PlayerWeapon), Added<PlayerWeapon>>,
-    mut weapon_list_node_q: Query<(Entity, Option<&Children>), With<WeaponListNode>>,
+    weapon_list_node_q: Query<Entity, With<WeaponListNode>>,
+    button_q: Query<(Entity, &WeaponButton, Has<WeaponSelected>)>,
+    preferred_weapon: Res<PreferredWeapon>,
+    mut change_weapon: EventWriter<ChangeWeapon>,
+    game_settings: Res<GameSettings>,
 ) {
-    for (weapon_entity, weapon) in weapon_q.iter() {
-        // add a new weapon to the list
-        let (entity, weapon_buttons) = weapon_list_node_q
-            .get_single_mut()
-            .expect("No weapon list node found! This is likely a bug");
-
-        let mut first = false;
-        let shortcut = if let Some(weapon_buttons) = weapon_buttons {
-            if weapon_buttons.is_empty() {
-                // automatically select the first weapon
-                cmd.entity(weapon_entity).insert(WeaponSelected);
-                first = true;
+    let new_weapons: Vec<_> = weapon_q.iter().collect();
+    if new_weapons.is_empty() {
+        return;
+    }
+    let list_entity = weapon_list_node_q
+        .get_single()
+        .expect("No weapon list node found! This is likely a bug");
+
+    let first = button_q.is_empty();
+    let selected = if first {
+        // automatically select the first weapon
+        let (weapon_entity, _) = new_weapons[0];
+        cmd.entity(weapon_entity).insert(WeaponSelected);
+        Some(weapon_entity)
+    } else if game_settings.auto_switch_weapon {
+        // switch to the preferred weapon as soon as it shows up,
+        // unless the player would rather not change weapons on pickup
+        let preferred = new_weapons
+            .iter()
+            .find(|(_, weapon)| preferred_weapon.0 == Some(weapon.num))
+            .map(|(weapon_entity, _)| *weapon_entity);
+        if let Some(weapon_entity) = preferred {
+            for (button_entity, _, selected) in &button_q {
+                if selected {
+                    cmd.entity(button_entity).remove::<WeaponSelected>();
+                }
             }
+            change_weapon.send(ChangeWeapon {
+                weapon: weapon_entity,
+            });
+        }
+        preferred
+    } else {
+        None
+    };
 
-            weapon_buttons.len() as u8 + 1
-        } else {
-            // automatically select the first weapon
-            cmd.entity(weapon_entity).insert(WeaponSelected);
-            first = true;
-            1
-        };
+    // take the shortcuts after the last one
+    // (weapons used up may have left gaps)
+    let last_shortcut = button_q
+        .iter()
+        .map(|(_, button, _)| button.shortcut)
+        .max()
+        .unwrap_or(0);
 
-        cmd.entity(entity).with_children(|root| {
-            spawn_weapon_button(root, weapon.num, shortcut, first);
-        });
-    }
+    let num_style = NumStyle::from(&*game_settings);
+    cmd.entity(list_entity).with_children(|root| {
+        for (shortcut, (weapon_entity, weapon)) in (last_shortcut + 1..).zip(new_weapons) {
+            spawn_weapon_button(
+                root,
+                weapon_entity,
+                weapon.num,
+                weapon.ammo,
+                shortcut,
+                selected == Some(weapon_entity),
+                num_style,
+                game_settings.simple_hotbar,
+            );
+        }
+    });
 }
 
 /// Component for implementing a timeout before
@@ -132,19 +245,71 @@ pub fn update_cooldown(time: Res<Time>, mut q: Query<&mut AttackCooldown>) {
 #[derive(Debug, Event)]
 pub struct TriggerWeapon {
     pub target_pos: Vec3,
+    /// how charged the shot is, from 0 to 1
+    /// (see [`ChargeState`])
+    pub charge: f32,
+}
+
+/// Resource for the charge of the next shot,
+/// built up by holding down the fire button before letting go
+#[derive(Debug, Default, Resource)]
+pub struct ChargeState {
+    /// how long the fire button has been held, in seconds
+    held: f32,
+}
+
+impl ChargeState {
+    /// how long the button must be held before the shot starts charging,
+    /// so that plain clicks are not charged
+    const MIN_HOLD: f32 = 0.25;
+    /// how long the button must be held for a full charge
+    const FULL_HOLD: f32 = 1.25;
+
+    /// The charge of the shot,
+    /// from 0 (a plain click) to 1 (fully charged)
+    pub fn charge(&self) -> f32 {
+        ((self.held - Self::MIN_HOLD) / (Self::FULL_HOLD - Self::MIN_HOLD)).clamp(0., 1.)
+    }
+}
+
+/// system that charges the next shot while the fire button is held
+pub fn update_charge(
+    time: Res<Time>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    game_settings: Res<GameSettings>,
+    mut charge_state: ResMut<ChargeState>,
+) {
+    let fire_button = if game_settings.fire_on_right_click {
+        MouseButton::Right
+    } else {
+        MouseButton::Left
+    };
+    if mouse_input.just_pressed(fire_button) {
+        charge_state.held = 0.;
+    } else if mouse_input.pressed(fire_button) {
+        // no need to count past a full charge
+        charge_state.held = (charge_state.held + time.delta_seconds()).min(ChargeState::FULL_HOLD);
+    }
 }
 
 /// System that reacts to events for triggering the weapon.
+///
+/// Weapons with limited ammo are used up after their last shot,
+/// in which case another weapon is selected.
 pub fn trigger_weapon(
     mut cmd: Commands,
     projectile_assets: Res<ProjectileAssets>,
     audio_handles: Res<AudioHandles>,
     mut trigger_weapon_events: EventReader<TriggerWeapon>,
-    mut weapon_q: Query<&PlayerWeapon, With<WeaponSelected>>,
+    mut weapon_q: Query<(Entity, &mut PlayerWeapon), With<WeaponSelected>>,
     mut player_q: Query<(&GlobalTransform, &mut AttackCooldown), With<Player>>,
+    button_q: Query<(Entity, &WeaponButton)>,
+    mut change_weapon: EventWriter<ChangeWeapon>,
+    mut flash_q: Query<(&mut Transform, &mut TimeToLive), With<MuzzleFlash>>,
+    mut camera_shake_q: Query<&mut CameraShake>,
 ) {
     for trigger_weapon in trigger_weapon_events.read() {
-        let Ok(weapon) = weapon_q.get_single_mut() else {
+        let Ok((weapon_entity, mut weapon)) = weapon_q.get_single_mut() else {
             return;
         };
 
@@ -158,29 +323,109 @@ pub fn trigger_weapon(
         let player_position = player_transform.translation();
 
         // play sound effect
-        audio_handles.play_fireball(&mut cmd);
+        audio_handles.play_fireball(&mut cmd, player_position);
 
         let direction = trigger_weapon.target_pos - player_position;
         let direction = direction.normalize();
 
-        // spawn a projectile
+        // spawn a projectile,
+        // remembering how quickly it came after the previous ones
+        let pace = if cooldown.max > 0. {
+            (cooldown.value / cooldown.max).clamp(0., 1.)
+        } else {
+            0.
+        };
         spawn_projectile(
             &mut cmd,
             player_position,
             direction,
-            weapon,
+            &weapon,
+            trigger_weapon.charge,
+            pace,
             &projectile_assets,
         );
 
+        // light up the surroundings for a moment,
+        // reusing the flash of the previous shot if it is still there
+        let flash_position = player_position + direction * MUZZLE_FLASH_DISTANCE;
+        if let Ok((mut transform, mut ttl)) = flash_q.get_single_mut() {
+            transform.translation = flash_position;
+            ttl.0 = MUZZLE_FLASH_DURATION;
+        } else {
+            spawn_muzzle_flash(&mut cmd, flash_position);
+        }
+
+        // and kick back
+        if let Ok(mut shake) = camera_shake_q.get_single_mut() {
+            shake.add_kick(0.5);
+        }
+
         // apply cooldown
         cooldown.value = cooldown.value + weapon.cooldown;
         if cooldown.value >= cooldown.max {
             cooldown.value = cooldown.max;
             cooldown.locked = true;
         }
+
+        // spend ammo
+        let Some(ammo) = &mut weapon.ammo else {
+            continue;
+        };
+        *ammo = ammo.saturating_sub(1);
+        if *ammo > 0 {
+            continue;
+        }
+
+        // used up, remove the weapon and its button
+        cmd.entity(weapon_entity).despawn();
+        let mut remaining = vec![];
+        for (entity, button) in &button_q {
+            if button.weapon == weapon_entity {
+                cmd.entity(entity).despawn_recursive();
+            } else {
+                remaining.push((button.shortcut, entity, button.weapon));
+            }
+        }
+
+        // select the weapon with the first shortcut
+        remaining.sort_by_key(|(shortcut, ..)| *shortcut);
+        if let Some((_, entity, weapon)) = remaining.first() {
+            cmd.entity(*entity).insert(WeaponSelected);
+            change_weapon.send(ChangeWeapon { weapon: *weapon });
+        }
+        // no more shots until the weapon change is done
+        return;
     }
 }
 
+/// Marker for the brief light of a weapon being fired
+#[derive(Debug, Component)]
+pub struct MuzzleFlash;
+
+/// how long a muzzle flash lasts, in seconds
+const MUZZLE_FLASH_DURATION: f32 = 0.08;
+
+/// how far ahead of the player the muzzle flash is
+const MUZZLE_FLASH_DISTANCE: f32 = 0.6;
+
+fn spawn_muzzle_flash(cmd: &mut Commands, position: Vec3) {
+    cmd.spawn((
+        OnLive,
+        MuzzleFlash,
+        PointLightBundle {
+            point_light: PointLight {
+                color: Color::srgb(1., 0.85, 0.6),
+                intensity: 40_000.,
+                range: 8.,
+                ..default()
+            },
+            transform: Transform::from_translation(position),
+            ..default()
+        },
+        TimeToLive(MUZZLE_FLASH_DURATION),
+    ));
+}
+
 /// An event fired when a player projectile hits a target.
 #[derive(Debug, Event)]
 pub struct PlayerAttack {
@@ -188,14 +433,21 @@ pub struct PlayerAttack {
     pub entity: Entity,
     /// the number of the attack
     pub num: Num,
+    /// how much of the player's cooldown was left
+    /// when the attack was made, from 0 to 1
+    pub pace: f32,
 }
 
 /// create a new button
 pub fn spawn_weapon_button(
     cmd: &mut ChildBuilder<'_>,
+    weapon: Entity,
     attack_num: Num,
+    ammo: Option<u32>,
     shortcut: u8,
     selected: bool,
+    num_style: NumStyle,
+    simplified: bool,
 ) {
     let (back_color, front_color) = if selected {
         (Color::WHITE, Color::BLACK)
@@ -206,6 +458,7 @@ pub fn spawn_weapon_button(
     let bundle = (
         OnLive,
         WeaponButton {
+            weapon,
             num: attack_num,
             shortcut,
         },
@@ -237,42 +490,134 @@ pub fn spawn_weapon_button(
     }
     // insert button
     .with_children(|parent| {
-        // shortcut
-        parent.spawn(TextBundle {
-            style: Style {
-                position_type: PositionType::Absolute,
-                left: Val::Px(1.),
-                top: Val::Px(1.),
-                ..default()
-            },
-            text: Text::from_section(
-                shortcut.to_string(),
-                TextStyle {
-                    font_size: 14.,
-                    color: front_color,
+        // shortcut (left out in the simplified hotbar,
+        // and for weapons only reachable by cycling)
+        if let Some(key) = key_of_shortcut(shortcut).filter(|_| !simplified) {
+            parent.spawn(TextBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(1.),
+                    top: Val::Px(1.),
                     ..default()
                 },
-            ),
-            ..Default::default()
-        });
+                text: Text::from_section(
+                    key.to_string(),
+                    TextStyle {
+                        font_size: 14.,
+                        color: front_color,
+                        ..default()
+                    },
+                ),
+                ..Default::default()
+            });
+        }
 
-        // the actual number of the attack
-        parent.spawn(TextBundle {
-            style: Style {
-                align_self: AlignSelf::Center,
-                margin: UiRect::all(Val::Auto),
-                ..default()
-            },
-            text: Text::from_section(
-                attack_num.to_string(),
-                TextStyle {
-                    font_size: 36.,
-                    color: front_color,
+        // shots left, for weapons which can be used up
+        if let Some(ammo) = ammo {
+            parent.spawn((
+                AmmoCounter(weapon),
+                TextBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        right: Val::Px(2.),
+                        bottom: Val::Px(1.),
+                        ..default()
+                    },
+                    text: Text::from_section(
+                        ammo.to_string(),
+                        TextStyle {
+                            font_size: 14.,
+                            color: front_color,
+                            ..default()
+                        },
+                    ),
                     ..default()
                 },
-            ),
-            ..default()
-        });
+            ));
+        }
+
+        // the actual number of the attack
+        let font_size = match (num_style.is_stacked(attack_num), simplified) {
+            (true, false) => 22.,
+            (true, true) => 26.,
+            (false, false) => 36.,
+            (false, true) => 44.,
+        };
+        // shrink long numbers (such as in binary) to fit the button
+        let text_len = num_style.format(attack_num).len();
+        let font_size = if !num_style.is_stacked(attack_num) && text_len > 3 {
+            font_size * 3. / text_len as f32
+        } else {
+            font_size
+        };
+        spawn_num_text(parent, attack_num, font_size, front_color, num_style);
+    });
+}
+
+/// Component for the text showing the shots left
+/// in the button of the given weapon entity
+#[derive(Debug, Component)]
+pub struct AmmoCounter(Entity);
+
+/// system that keeps the ammo counters in the weapon buttons up to date
+pub fn update_ammo_counters(
+    weapon_q: Query<(Entity, &PlayerWeapon), Changed<PlayerWeapon>>,
+    mut counter_q: Query<(&AmmoCounter, &mut Text)>,
+) {
+    for (weapon_entity, weapon) in &weapon_q {
+        let Some(ammo) = weapon.ammo else {
+            continue;
+        };
+        for (counter, mut text) in &mut counter_q {
+            if counter.0 == weapon_entity {
+                text.sections[0].value = ammo.to_string();
+            }
+        }
+    }
+}
+
+/// system that rebuilds the weapon buttons when the game settings change,
+/// so that they are portrayed accordingly
+pub fn rebuild_weapon_buttons(
+    mut cmd: Commands,
+    game_settings: Res<GameSettings>,
+    weapon_list_node_q: Query<Entity, With<WeaponListNode>>,
+    button_q: Query<(Entity, &WeaponButton, Has<WeaponSelected>)>,
+    weapon_q: Query<&PlayerWeapon>,
+) {
+    let Ok(list_entity) = weapon_list_node_q.get_single() else {
+        return;
+    };
+
+    let mut buttons: Vec<_> = button_q
+        .iter()
+        .map(|(entity, button, selected)| {
+            (entity, button.weapon, button.num, button.shortcut, selected)
+        })
+        .collect();
+    if buttons.is_empty() {
+        return;
+    }
+    buttons.sort_by_key(|(.., shortcut, _)| *shortcut);
+
+    let num_style = NumStyle::from(&*game_settings);
+    for (entity, ..) in &buttons {
+        cmd.entity(*entity).despawn_recursive();
+    }
+    cmd.entity(list_entity).with_children(|root| {
+        for (_, weapon, num, shortcut, selected) in buttons {
+            let ammo = weapon_q.get(weapon).ok().and_then(|weapon| weapon.ammo);
+            spawn_weapon_button(
+                root,
+                weapon,
+                num,
+                ammo,
+                shortcut,
+                selected,
+                num_style,
+                game_settings.simple_hotbar,
+            );
+        }
     });
 }
 
@@ -280,18 +625,29 @@ pub fn spawn_weapon_button(
 #[derive(Debug, Default, Component)]
 pub struct WeaponCube {
     pub num: Num,
+    /// the cooldown of the weapon once installed
+    pub cooldown: f32,
+    /// how many targets a shot of the weapon can hit
+    pub pierce: u8,
+    /// whether shots of the weapon steer towards targets
+    pub homing: bool,
+    /// how many shots the weapon has before it is used up
+    pub ammo: Option<u32>,
 }
 
 #[derive(Debug, Resource)]
 pub struct WeaponCubeAssets {
     pub mesh: Handle<Mesh>,
+    /// unit length mesh for the beam shown when a cube is absorbed
+    pub beam_mesh: Handle<Mesh>,
 }
 
 impl FromWorld for WeaponCubeAssets {
     fn from_world(world: &mut World) -> Self {
         let mut meshes = world.get_resource_mut::<Assets<Mesh>>().unwrap();
         let mesh = meshes.add(Mesh::from(Cuboid::from_length(0.75)));
-        Self { mesh }
+        let beam_mesh = meshes.add(Mesh::from(Cuboid::new(0.06, 0.06, 1.)));
+        Self { mesh, beam_mesh }
     }
 }
 
@@ -301,11 +657,22 @@ pub fn spawn_weapon_cube(
     materials: &mut Assets<StandardMaterial>,
     position: Vec3,
     num: Num,
+    cooldown: f32,
+    pierce: u8,
+    homing: bool,
+    ammo: Option<u32>,
+    num_style: NumStyle,
 ) -> Entity {
     let entity = cmd
         .spawn((
             OnLive,
-            WeaponCube { num },
+            WeaponCube {
+                num,
+                cooldown,
+                pierce,
+                homing,
+                ammo,
+            },
             Rotating(0.5),
             PbrBundle {
                 transform: Transform::from_translation(position),
@@ -320,28 +687,89 @@ pub fn spawn_weapon_cube(
         .id();
 
     // add an icon for it
-    spawn_icon(cmd, entity, num, Color::srgb(0., 1., 1.));
+    spawn_icon(cmd, entity, num, Color::srgb(0., 1., 1.), num_style);
 
     entity
 }
 
+/// Spawn a short-lived beam between the two given points,
+/// to show that the player absorbed a weapon cube.
+fn spawn_absorb_beam(
+    cmd: &mut Commands,
+    assets: &WeaponCubeAssets,
+    materials: &mut Assets<StandardMaterial>,
+    from: Vec3,
+    to: Vec3,
+) {
+    let length = from.distance(to);
+    let mut transform = Transform::from_translation((from + to) / 2.).looking_at(to, Vec3::Y);
+    transform.scale = Vec3::new(1., 1., length);
+
+    cmd.spawn((
+        OnLive,
+        PbrBundle {
+            transform,
+            mesh: assets.beam_mesh.clone(),
+            material: materials.add(StandardMaterial {
+                base_color: Color::srgba(0.5, 1., 1., 0.75),
+                emissive: LinearRgba::new(0., 0.8, 0.8, 1.),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            }),
+            ..default()
+        },
+        FadesAway::default(),
+        TimeToLive(0.5),
+    ));
+}
+
+/// how close the player must be to a weapon cube to absorb it
+const PICKUP_DISTANCE: f32 = 9.5;
+
+/// how close the player must be to a weapon cube to absorb it
+/// when the cube magnet is enabled
+const MAGNET_PICKUP_DISTANCE: f32 = 14.;
+
+/// how close the player must be to a weapon cube
+/// for it to start moving towards the player
+/// when the cube magnet is enabled
+const MAGNET_PULL_DISTANCE: f32 = 24.;
+
+/// how fast weapon cubes are pulled towards the player
+const MAGNET_PULL_SPEED: f32 = 4.;
+
+/// system that makes the player absorb weapon cubes nearby
+/// (and pulls them closer if the cube magnet is enabled)
 pub fn process_approach_weapon_cube(
     mut cmd: Commands,
+    game_settings: Res<GameSettings>,
     player_q: Query<&Transform, With<Player>>,
+    weapon_cube_assets: Res<WeaponCubeAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
     audio_handles: Res<AudioHandles>,
     mut postprocess_settings_q: Query<&mut PostProcessSettings>,
     mut weapon_cube_q: Query<(Entity, &Transform, &WeaponCube, &mut Rotating)>,
+    mut weapon_q: Query<&mut PlayerWeapon>,
+    mut toasts: ResMut<Toasts>,
 ) {
     let Ok(player_transform) = player_q.get_single() else {
         return;
     };
     let player_corridor_pos = player_transform.translation.z;
+    let pickup_distance = if game_settings.magnet_cubes {
+        MAGNET_PICKUP_DISTANCE
+    } else {
+        PICKUP_DISTANCE
+    };
+    // weapons installed during this run of the system
+    let mut installed: Vec<Num> = vec![];
 
     for (entity, weapon_transform, weapon_cube, mut rotating) in weapon_cube_q.iter_mut() {
         let weapon_corridor_pos = weapon_transform.translation.z;
         let distance = (player_corridor_pos - weapon_corridor_pos).abs();
 
-        if distance < 9.5 {
+        if distance < pickup_distance {
             // make an effect
             cmd.entity(entity).insert(Velocity(Vec3::new(0., 1., 0.)));
             // increase rotation speed
@@ -350,19 +778,68 @@ pub fn process_approach_weapon_cube(
             cmd.entity(entity).insert(TimeToLive(0.6));
             // remove weapon cube marker
             cmd.entity(entity).remove::<WeaponCube>();
-            install_weapon(&mut cmd, weapon_cube.num);
+            // do not clutter the arsenal with the same number twice
+            if let Some(mut weapon) = find_weapon(&mut weapon_q, weapon_cube.num) {
+                weapon.refresh_ammo(weapon_cube.ammo);
+            } else if !installed.contains(&weapon_cube.num) {
+                install_weapon(&mut cmd, weapon_cube);
+                installed.push(weapon_cube.num);
+            }
+
+            // connect the cube to the player's staff
+            spawn_absorb_beam(
+                &mut cmd,
+                &weapon_cube_assets,
+                &mut materials,
+                player_transform.translation + Vec3::new(0.15, 0.25, 1.),
+                weapon_transform.translation,
+            );
 
             // play sound
             audio_handles.play_pickup(&mut cmd);
 
+            toasts.push_toast(
+                format!(
+                    "Picked up {}",
+                    NumStyle::from(&*game_settings).format(weapon_cube.num)
+                ),
+                2.,
+            );
+
             // add a visual effect
             if let Ok(mut settings) = postprocess_settings_q.get_single_mut() {
                 settings.add_intensity(0.05);
             }
+        } else if game_settings.magnet_cubes && distance < MAGNET_PULL_DISTANCE {
+            // drift towards the player
+            let direction =
+                (player_transform.translation - weapon_transform.translation).normalize_or_zero();
+            cmd.entity(entity)
+                .insert(Velocity(direction * MAGNET_PULL_SPEED));
         }
     }
 }
 
+/// The weapon shortcut for the given key:
+/// 1 to 9 for the first nine weapons, 0 for the tenth
+fn shortcut_of_key(c: char) -> Option<u8> {
+    match c {
+        '1'..='9' => Some(c as u8 - b'0'),
+        '0' => Some(10),
+        _ => None,
+    }
+}
+
+/// The key of the given weapon shortcut, if it has one
+/// (the inverse of [`shortcut_of_key`])
+fn key_of_shortcut(shortcut: u8) -> Option<char> {
+    match shortcut {
+        1..=9 => Some((b'0' + shortcut) as char),
+        10 => Some('0'),
+        _ => None,
+    }
+}
+
 /// system to check keypresses for weapon shortcuts
 pub fn weapon_keyboard_input(
     mut cmd: Commands,
@@ -378,54 +855,149 @@ pub fn weapon_keyboard_input(
 
         if let (Key::Character(c), ButtonState::Pressed) = (logical_key, state) {
             let Some(c) = c.chars().next() else { continue };
-            if ('1'..='9').contains(&c) {
-                let shortcut = (c as u8 - b'0') as u8;
-
-                // look for the weapon button matching the shortcut
-                let weapon_entity =
-                    weapon_button_q
-                        .iter()
-                        .find_map(|(entity, weapon_button, is_selected)| {
-                            if weapon_button.shortcut == shortcut {
-                                if is_selected {
-                                    // no change is needed, stop here
-                                    return Some(None);
-                                }
-                                let num = weapon_button.num;
-                                cmd.entity(entity).insert(WeaponSelected);
-
-                                // perform weapon selection
-                                change_weapon.send(ChangeWeapon { num });
-
-                                // play sound
-                                audio_handles.play_equipmentclick1(&mut cmd);
-                                return Some(Some(entity));
-                            }
-                            None
-                        });
-
-                if let Some(Some(weapon_entity)) = weapon_entity {
-                    // unselect all other weapon buttons
-                    for (entity, _, _) in &weapon_button_q {
-                        if entity != weapon_entity {
-                            cmd.entity(entity).remove::<WeaponSelected>();
-                        }
-                    }
+            let Some(shortcut) = shortcut_of_key(c) else {
+                continue;
+            };
+            // look for the weapon button matching the shortcut
+            let Some((weapon_entity, weapon_button, was_selected)) = weapon_button_q
+                .iter()
+                .find(|(_, weapon_button, _)| weapon_button.shortcut == shortcut)
+            else {
+                continue;
+            };
+            let others_selected = weapon_button_q
+                .iter()
+                .any(|(entity, _, is_selected)| entity != weapon_entity && is_selected);
+            if was_selected && !others_selected {
+                // no change is needed
+                continue;
+            }
+
+            // traverse all buttons so that exactly one is selected
+            for (entity, _, _) in &weapon_button_q {
+                if entity == weapon_entity {
+                    cmd.entity(entity).insert(WeaponSelected);
+                } else {
+                    cmd.entity(entity).remove::<WeaponSelected>();
                 }
             }
+
+            if !was_selected {
+                // perform weapon selection
+                change_weapon.send(ChangeWeapon {
+                    weapon: weapon_button.weapon,
+                });
+
+                // play sound
+                audio_handles.play_equipmentclick1(&mut cmd);
+            }
         }
     }
 }
 
+/// system that selects the next weapon in the hotbar with E,
+/// or the previous one with Q,
+/// wrapping around at either end
+pub fn cycle_weapon_selection(
+    mut cmd: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    weapon_button_q: Query<(Entity, &WeaponButton, Has<WeaponSelected>)>,
+    mut change_weapon: EventWriter<ChangeWeapon>,
+    audio_handles: Res<AudioHandles>,
+) {
+    let forward = input.just_pressed(KeyCode::KeyE);
+    if !forward && !input.just_pressed(KeyCode::KeyQ) {
+        return;
+    }
+    if select_adjacent_weapon(&mut cmd, &weapon_button_q, &mut change_weapon, forward) {
+        audio_handles.play_equipmentclick1(&mut cmd);
+    }
+}
+
+/// system that selects the next weapon in the hotbar
+/// when scrolling down with the mouse wheel,
+/// or the previous one when scrolling up
+pub fn scroll_weapon_selection(
+    mut cmd: Commands,
+    mut mouse_wheel: EventReader<MouseWheel>,
+    weapon_button_q: Query<(Entity, &WeaponButton, Has<WeaponSelected>)>,
+    mut change_weapon: EventWriter<ChangeWeapon>,
+    audio_handles: Res<AudioHandles>,
+) {
+    // one step per frame at most, however fast the wheel turns
+    let scrolled: f32 = mouse_wheel.read().map(|ev| ev.y).sum();
+    if scrolled == 0. {
+        return;
+    }
+    let forward = scrolled < 0.;
+    if select_adjacent_weapon(&mut cmd, &weapon_button_q, &mut change_weapon, forward) {
+        audio_handles.play_equipmentclick1(&mut cmd);
+    }
+}
+
+/// Move the weapon selection to the next button in the hotbar
+/// (or the previous one if not `forward`),
+/// wrapping around at either end.
+///
+/// Returns whether the selection changed,
+/// which is not the case with fewer than two weapons.
+fn select_adjacent_weapon(
+    cmd: &mut Commands,
+    weapon_button_q: &Query<(Entity, &WeaponButton, Has<WeaponSelected>)>,
+    change_weapon: &mut EventWriter<ChangeWeapon>,
+    forward: bool,
+) -> bool {
+    let mut buttons: Vec<_> = weapon_button_q.iter().collect();
+    if buttons.len() < 2 {
+        return false;
+    }
+    buttons.sort_by_key(|(_, button, _)| button.shortcut);
+    let count = buttons.len();
+    let next = match buttons.iter().position(|(.., selected)| *selected) {
+        Some(i) if forward => (i + 1) % count,
+        Some(i) => (i + count - 1) % count,
+        None => 0,
+    };
+
+    for (i, (entity, button, selected)) in buttons.into_iter().enumerate() {
+        if i == next {
+            cmd.entity(entity).insert(WeaponSelected);
+            change_weapon.send(ChangeWeapon {
+                weapon: button.weapon,
+            });
+        } else if selected {
+            cmd.entity(entity).remove::<WeaponSelected>();
+        }
+    }
+    true
+}
+
 /// Component for a weapon button
-#[derive(Debug, Default, Component)]
+#[derive(Debug, Component)]
 pub struct WeaponButton {
+    /// the weapon entity (with [`PlayerWeapon`]) which the button selects
+    weapon: Entity,
     /// the attack number
     num: Num,
-    /// an integer between 1-9, representing the shorcut button
+    /// the position of the button in the hotbar, starting at 1
+    /// (see [`shortcut_of_key`] for the keys which select them)
     shortcut: u8,
 }
 
+impl WeaponButton {
+    pub fn weapon(&self) -> Entity {
+        self.weapon
+    }
+
+    pub fn num(&self) -> Num {
+        self.num
+    }
+
+    pub fn shortcut(&self) -> u8 {
+        self.shortcut
+    }
+}
+
 /// system callback for when the player clicks on a weapon button
 /// (as an alternative to using the shortcut keys)
 pub fn weapon_button_action(
@@ -463,7 +1035,7 @@ pub fn weapon_button_action(
 
         // change weapon
         events.send(ChangeWeapon {
-            num: weapon_button.num,
+            weapon: weapon_button.weapon,
         });
     }
 }
@@ -471,65 +1043,572 @@ pub fn weapon_button_action(
 /// Event which requests for a change in the selected weapon
 #[derive(Debug, Event)]
 pub struct ChangeWeapon {
-    pub num: Num,
+    /// the weapon entity (with [`PlayerWeapon`]) to wield
+    pub weapon: Entity,
 }
 
+/// system that moves the weapon selection to the requested weapon,
+/// so that its own characteristics (such as cooldown and ammo) apply
 pub fn process_weapon_change(
+    mut cmd: Commands,
     mut events: EventReader<ChangeWeapon>,
-    mut weapon_q: Query<&mut PlayerWeapon, With<WeaponSelected>>,
+    weapon_q: Query<(Entity, &PlayerWeapon, Has<WeaponSelected>)>,
+    mut preferred_weapon: ResMut<PreferredWeapon>,
 ) {
-    for ChangeWeapon { num, .. } in events.read() {
-        // update the weapon characteristics
-        let Ok(mut weapon) = weapon_q.get_single_mut() else {
-            return;
+    for ChangeWeapon { weapon } in events.read() {
+        let Ok((_, chosen, _)) = weapon_q.get(*weapon) else {
+            // used up in the meantime
+            continue;
         };
-        weapon.num = *num;
+        // remember the choice for the following levels
+        preferred_weapon.0 = Some(chosen.num);
+
+        for (entity, _, selected) in &weapon_q {
+            if entity == *weapon {
+                cmd.entity(entity).insert(WeaponSelected);
+            } else if selected {
+                cmd.entity(entity).remove::<WeaponSelected>();
+            }
+        }
     }
 }
 
 /// system that updates the style of the selected button
 pub fn process_weapon_button_selected(
     mut weapon_button_q: Query<
-        (&mut BackgroundColor, &Children),
+        (Entity, &mut BackgroundColor),
         (With<WeaponButton>, Added<WeaponSelected>),
     >,
+    children_q: Query<&Children>,
     mut weapon_button_text_q: Query<&mut Text>,
+    mut fraction_bar_q: Query<&mut BackgroundColor, (With<FractionBar>, Without<WeaponButton>)>,
 ) {
-    for (mut background_color, children) in &mut weapon_button_q {
+    for (entity, mut background_color) in &mut weapon_button_q {
         background_color.0 = Color::WHITE;
 
-        for child in children {
-            let Ok(mut text) = weapon_button_text_q.get_mut(*child) else {
-                continue;
-            };
-            for section in &mut text.sections {
-                section.style.color = Color::BLACK;
-            }
-        }
+        set_weapon_button_front_color(
+            entity,
+            Color::BLACK,
+            &children_q,
+            &mut weapon_button_text_q,
+            &mut fraction_bar_q,
+        );
     }
 }
 
 /// system that updates the style of the selected button
 pub fn process_weapon_button_deselected(
     mut removals: RemovedComponents<WeaponSelected>,
-    mut weapon_button_q: Query<(&mut BackgroundColor, &Children), With<WeaponButton>>,
+    mut weapon_button_q: Query<&mut BackgroundColor, With<WeaponButton>>,
+    children_q: Query<&Children>,
     mut weapon_button_text_q: Query<&mut Text>,
+    mut fraction_bar_q: Query<&mut BackgroundColor, (With<FractionBar>, Without<WeaponButton>)>,
 ) {
     for entity in removals.read() {
         // see if entity still exists
-        let Ok((mut background_color, children)) = weapon_button_q.get_mut(entity) else {
+        let Ok(mut background_color) = weapon_button_q.get_mut(entity) else {
             continue;
         };
 
         background_color.0 = Color::BLACK;
 
-        for child in children {
-            let Ok(mut text) = weapon_button_text_q.get_mut(*child) else {
-                continue;
-            };
+        set_weapon_button_front_color(
+            entity,
+            Color::WHITE,
+            &children_q,
+            &mut weapon_button_text_q,
+            &mut fraction_bar_q,
+        );
+    }
+}
+
+/// update the color of the texts and lines inside a weapon button
+fn set_weapon_button_front_color(
+    button_entity: Entity,
+    color: Color,
+    children_q: &Query<&Children>,
+    text_q: &mut Query<&mut Text>,
+    fraction_bar_q: &mut Query<&mut BackgroundColor, (With<FractionBar>, Without<WeaponButton>)>,
+) {
+    for descendant in children_q.iter_descendants(button_entity) {
+        if let Ok(mut text) = text_q.get_mut(descendant) {
             for section in &mut text.sections {
-                section.style.color = Color::WHITE;
+                section.style.color = color;
             }
+        } else if let Ok(mut bar_color) = fraction_bar_q.get_mut(descendant) {
+            bar_color.0 = color;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::live::projectile::{Homing, Piercing, Projectile};
+
+    /// a world with just enough for the player to fire weapons
+    fn test_world() -> World {
+        let mut world = World::new();
+        world.init_resource::<Assets<Mesh>>();
+        world.init_resource::<Assets<StandardMaterial>>();
+        world.init_resource::<ProjectileAssets>();
+        world.insert_resource(AudioHandles::muted());
+        world.init_resource::<Events<TriggerWeapon>>();
+        world.init_resource::<Events<ChangeWeapon>>();
+        world.init_resource::<PreferredWeapon>();
+        world.spawn((
+            Player,
+            GlobalTransform::default(),
+            AttackCooldown::default(),
+        ));
+        world
+    }
+
+    /// fire the selected weapon once,
+    /// returning the cooldown it added
+    fn fire(world: &mut World) -> f32 {
+        world.send_event(TriggerWeapon {
+            target_pos: Vec3::Z,
+            charge: 0.,
+        });
+        world.run_system_once(trigger_weapon);
+        world.resource_mut::<Events<TriggerWeapon>>().clear();
+
+        let mut cooldown = world
+            .query_filtered::<&mut AttackCooldown, With<Player>>()
+            .single_mut(world);
+        let added = cooldown.value;
+        *cooldown = AttackCooldown::default();
+        added
+    }
+
+    /// fire the selected weapon once,
+    /// returning whether the shot pierces and whether it homes in
+    fn fire_shot(world: &mut World) -> (bool, bool) {
+        fire(world);
+        let (entity, pierces, homes) = world
+            .query_filtered::<(Entity, Has<Piercing>, Has<Homing>), With<Projectile>>()
+            .single(world);
+        world.entity_mut(entity).despawn_recursive();
+        (pierces, homes)
+    }
+
+    fn change_weapon(world: &mut World, weapon: Entity) {
+        world.send_event(ChangeWeapon { weapon });
+        world.run_system_once(process_weapon_change);
+        world.resource_mut::<Events<ChangeWeapon>>().clear();
+    }
+
+    #[test]
+    fn charge_builds_up_while_held_and_stops_at_full() {
+        let mut world = World::new();
+        world.init_resource::<GameSettings>();
+        world.init_resource::<ChargeState>();
+        let mut time = Time::<()>::default();
+        time.advance_by(std::time::Duration::from_millis(200));
+        world.insert_resource(time);
+        let mut mouse_input = ButtonInput::<MouseButton>::default();
+        mouse_input.press(MouseButton::Left);
+        world.insert_resource(mouse_input);
+
+        world.run_system_once(update_charge);
+        assert_eq!(world.resource::<ChargeState>().charge(), 0.);
+
+        // held for a frame: still a plain click
+        world
+            .resource_mut::<ButtonInput<MouseButton>>()
+            .clear_just_pressed(MouseButton::Left);
+        world.run_system_once(update_charge);
+        assert_eq!(world.resource::<ChargeState>().charge(), 0.);
+
+        // held for much longer than needed
+        for _ in 0..50 {
+            world.run_system_once(update_charge);
+        }
+        let charge_state = world.resource::<ChargeState>();
+        assert_eq!(charge_state.charge(), 1.);
+        assert_eq!(charge_state.held, ChargeState::FULL_HOLD);
+
+        // pressing again starts over
+        world
+            .resource_mut::<ButtonInput<MouseButton>>()
+            .release(MouseButton::Left);
+        world
+            .resource_mut::<ButtonInput<MouseButton>>()
+            .press(MouseButton::Left);
+        world.run_system_once(update_charge);
+        assert_eq!(world.resource::<ChargeState>().charge(), 0.);
+    }
+
+    #[test]
+    fn firing_flashes_briefly_and_kicks_back() {
+        let mut world = test_world();
+        world.spawn((PlayerWeapon::new(2.into(), 0.1), WeaponSelected));
+        let camera = world
+            .spawn((Transform::IDENTITY, CameraShake::new(1.)))
+            .id();
+
+        // rapid fire keeps to a single flash
+        fire(&mut world);
+        fire(&mut world);
+
+        let flashes: Vec<_> = world
+            .query_filtered::<&TimeToLive, With<MuzzleFlash>>()
+            .iter(&world)
+            .map(|ttl| ttl.0)
+            .collect();
+        assert_eq!(flashes, vec![MUZZLE_FLASH_DURATION]);
+        assert!(MUZZLE_FLASH_DURATION < 0.25);
+        assert!(world.get::<CameraShake>(camera).unwrap().kick > 0.);
+    }
+
+    #[test]
+    fn switching_weapons_changes_cooldown_applied() {
+        let mut world = test_world();
+        let light = world
+            .spawn((PlayerWeapon::new(2.into(), 0.8), WeaponSelected))
+            .id();
+        let heavy = world.spawn(PlayerWeapon::new(3.into(), 1.5)).id();
+
+        assert_eq!(fire(&mut world), 0.8);
+
+        change_weapon(&mut world, heavy);
+        assert_eq!(fire(&mut world), 1.5);
+
+        change_weapon(&mut world, light);
+        assert_eq!(fire(&mut world), 0.8);
+    }
+
+    #[test]
+    fn switching_weapons_applies_cooldown_for_their_numbers() {
+        let mut world = test_world();
+        let two: Num = 2.into();
+        let eighth = Num::new(1, 8);
+        world.spawn((
+            PlayerWeapon::new(two, PlayerWeapon::cooldown_for(two)),
+            WeaponSelected,
+        ));
+        let complex = world
+            .spawn(PlayerWeapon::new(
+                eighth,
+                PlayerWeapon::cooldown_for(eighth),
+            ))
+            .id();
+
+        assert_eq!(fire(&mut world), PlayerWeapon::cooldown_for(two));
+
+        change_weapon(&mut world, complex);
+        assert_eq!(fire(&mut world), PlayerWeapon::cooldown_for(eighth));
+        assert!(PlayerWeapon::cooldown_for(eighth) > PlayerWeapon::cooldown_for(two));
+    }
+
+    #[test]
+    fn switching_weapons_changes_pierce_applied() {
+        let mut world = test_world();
+        let plain = world
+            .spawn((PlayerWeapon::new(2.into(), 1.), WeaponSelected))
+            .id();
+        let piercing = world
+            .spawn(PlayerWeapon {
+                pierce: 3,
+                ..PlayerWeapon::new(3.into(), 1.)
+            })
+            .id();
+
+        assert!(!fire_shot(&mut world).0);
+
+        change_weapon(&mut world, piercing);
+        assert!(fire_shot(&mut world).0);
+
+        change_weapon(&mut world, plain);
+        assert!(!fire_shot(&mut world).0);
+    }
+
+    #[test]
+    fn switching_weapons_changes_homing_applied() {
+        let mut world = test_world();
+        let plain = world
+            .spawn((PlayerWeapon::new(2.into(), 1.), WeaponSelected))
+            .id();
+        let homing = world
+            .spawn(PlayerWeapon {
+                homing: true,
+                ..PlayerWeapon::new(3.into(), 1.)
+            })
+            .id();
+
+        assert!(!fire_shot(&mut world).1);
+
+        change_weapon(&mut world, homing);
+        assert!(fire_shot(&mut world).1);
+
+        change_weapon(&mut world, plain);
+        assert!(!fire_shot(&mut world).1);
+    }
+
+    #[test]
+    fn using_up_a_weapon_only_removes_its_own_button() {
+        let mut world = test_world();
+        // the same number twice, told apart by their entities
+        let limited = world
+            .spawn((
+                PlayerWeapon {
+                    ammo: Some(1),
+                    ..PlayerWeapon::new(2.into(), 1.)
+                },
+                WeaponSelected,
+            ))
+            .id();
+        let unlimited = world.spawn(PlayerWeapon::new(2.into(), 1.)).id();
+        let limited_button = world
+            .spawn((
+                WeaponButton {
+                    weapon: limited,
+                    num: 2.into(),
+                    shortcut: 1,
+                },
+                WeaponSelected,
+            ))
+            .id();
+        let unlimited_button = world
+            .spawn(WeaponButton {
+                weapon: unlimited,
+                num: 2.into(),
+                shortcut: 2,
+            })
+            .id();
+
+        fire(&mut world);
+
+        assert!(world.get_entity(limited).is_none());
+        assert!(world.get_entity(limited_button).is_none());
+        assert!(world.get::<WeaponSelected>(unlimited_button).is_some());
+
+        let events = world.resource::<Events<ChangeWeapon>>();
+        let requested: Vec<_> = events
+            .get_reader()
+            .read(events)
+            .map(|ev| ev.weapon)
+            .collect();
+        assert_eq!(requested, vec![unlimited]);
+    }
+
+    #[test]
+    fn weapons_added_together_select_just_one() {
+        let mut world = test_world();
+        world.init_resource::<GameSettings>();
+        world.spawn((WeaponListNode, NodeBundle::default()));
+        world.spawn(PlayerWeapon::new(2.into(), 1.));
+        world.spawn(PlayerWeapon::new(3.into(), 1.));
+
+        world.run_system_once(process_new_weapon);
+
+        let selected_weapons = world
+            .query_filtered::<(), (With<PlayerWeapon>, With<WeaponSelected>)>()
+            .iter(&world)
+            .count();
+        assert_eq!(selected_weapons, 1);
+        let mut buttons: Vec<_> = world
+            .query::<(&WeaponButton, Has<WeaponSelected>)>()
+            .iter(&world)
+            .map(|(button, selected)| (button.shortcut, selected))
+            .collect();
+        buttons.sort();
+        assert_eq!(buttons, vec![(1, true), (2, false)]);
+    }
+
+    /// spawn a weapon cube right where the player stands
+    fn spawn_cube_at_player(world: &mut World, num: Num, ammo: Option<u32>) {
+        world.spawn((
+            Transform::default(),
+            Rotating(1.),
+            WeaponCube {
+                num,
+                cooldown: 1.,
+                pierce: 0,
+                homing: false,
+                ammo,
+            },
+        ));
+    }
+
+    #[test]
+    fn picking_up_the_same_number_twice_keeps_one_weapon() {
+        let mut world = test_world();
+        world.init_resource::<GameSettings>();
+        world.init_resource::<WeaponCubeAssets>();
+        world.init_resource::<Toasts>();
+        let player = world
+            .query_filtered::<Entity, With<Player>>()
+            .single(&world);
+        world.entity_mut(player).insert(Transform::default());
+
+        // two cubes of the same number in the same frame
+        spawn_cube_at_player(&mut world, 2.into(), Some(3));
+        spawn_cube_at_player(&mut world, 2.into(), Some(3));
+        world.run_system_once(process_approach_weapon_cube);
+
+        // and another one later on
+        spawn_cube_at_player(&mut world, 2.into(), Some(6));
+        world.run_system_once(process_approach_weapon_cube);
+
+        let weapons: Vec<_> = world
+            .query::<&PlayerWeapon>()
+            .iter(&world)
+            .map(|weapon| (weapon.num, weapon.ammo))
+            .collect();
+        assert_eq!(weapons, vec![(2.into(), Some(6))]);
+    }
+
+    /// fill the hotbar with the given number of weapons,
+    /// the first one selected
+    fn spawn_hotbar(world: &mut World, count: u8) -> Vec<Entity> {
+        (1..=count)
+            .map(|shortcut| {
+                let num = Num::from(shortcut as i16);
+                let weapon = world.spawn(PlayerWeapon::new(num, 1.)).id();
+                let button = world
+                    .spawn(WeaponButton {
+                        weapon,
+                        num,
+                        shortcut,
+                    })
+                    .id();
+                if shortcut == 1 {
+                    world.entity_mut(weapon).insert(WeaponSelected);
+                    world.entity_mut(button).insert(WeaponSelected);
+                }
+                weapon
+            })
+            .collect()
+    }
+
+    /// the shortcuts of the weapon buttons currently selected
+    fn selected_shortcuts(world: &mut World) -> Vec<u8> {
+        world
+            .query_filtered::<&WeaponButton, With<WeaponSelected>>()
+            .iter(world)
+            .map(|button| button.shortcut)
+            .collect()
+    }
+
+    /// the weapons requested since the last call
+    fn requested_weapons(world: &mut World) -> Vec<Entity> {
+        world
+            .resource_mut::<Events<ChangeWeapon>>()
+            .drain()
+            .map(|ev| ev.weapon)
+            .collect()
+    }
+
+    fn press_key(world: &mut World, key: KeyCode) {
+        let mut input = world.resource_mut::<ButtonInput<KeyCode>>();
+        input.release_all();
+        input.clear();
+        input.press(key);
+        world.run_system_once(cycle_weapon_selection);
+    }
+
+    #[test]
+    fn cycling_weapons_wraps_around() {
+        let mut world = test_world();
+        world.init_resource::<ButtonInput<KeyCode>>();
+        let weapons = spawn_hotbar(&mut world, 3);
+
+        let mut visited = vec![];
+        for _ in 0..3 {
+            press_key(&mut world, KeyCode::KeyE);
+            visited.extend(selected_shortcuts(&mut world));
         }
+        assert_eq!(visited, vec![2, 3, 1]);
+        assert_eq!(
+            requested_weapons(&mut world),
+            vec![weapons[1], weapons[2], weapons[0]]
+        );
+
+        press_key(&mut world, KeyCode::KeyQ);
+        assert_eq!(selected_shortcuts(&mut world), vec![3]);
+        assert_eq!(requested_weapons(&mut world), vec![weapons[2]]);
+    }
+
+    fn type_key(world: &mut World, c: char) {
+        world.send_event(KeyboardInput {
+            key_code: KeyCode::Digit0,
+            logical_key: Key::Character(c.to_string().into()),
+            state: ButtonState::Pressed,
+            window: Entity::PLACEHOLDER,
+        });
+        world.run_system_once(weapon_keyboard_input);
+        world.resource_mut::<Events<KeyboardInput>>().clear();
+    }
+
+    #[test]
+    fn zero_selects_the_tenth_weapon() {
+        let mut world = test_world();
+        world.init_resource::<Events<KeyboardInput>>();
+        let weapons = spawn_hotbar(&mut world, 10);
+
+        type_key(&mut world, '0');
+
+        assert_eq!(selected_shortcuts(&mut world), vec![10]);
+        assert_eq!(requested_weapons(&mut world), vec![weapons[9]]);
+    }
+
+    #[test]
+    fn shortcuts_leave_exactly_one_button_selected() {
+        let mut world = test_world();
+        world.init_resource::<Events<KeyboardInput>>();
+        let weapons = spawn_hotbar(&mut world, 3);
+
+        type_key(&mut world, '3');
+        assert_eq!(selected_shortcuts(&mut world), vec![3]);
+
+        // the button selected last comes after the one pressed
+        type_key(&mut world, '1');
+        assert_eq!(selected_shortcuts(&mut world), vec![1]);
+        assert_eq!(requested_weapons(&mut world), vec![weapons[2], weapons[0]]);
+    }
+
+    fn scroll(world: &mut World, y: f32) {
+        world.send_event(MouseWheel {
+            unit: bevy::input::mouse::MouseScrollUnit::Line,
+            x: 0.,
+            y,
+            window: Entity::PLACEHOLDER,
+        });
+        world.run_system_once(scroll_weapon_selection);
+        world.resource_mut::<Events<MouseWheel>>().clear();
+    }
+
+    #[test]
+    fn scrolling_weapons_wraps_around() {
+        let mut world = test_world();
+        world.init_resource::<Events<MouseWheel>>();
+        let weapons = spawn_hotbar(&mut world, 3);
+
+        // scrolling up from the first weapon goes to the last one
+        scroll(&mut world, 1.);
+        assert_eq!(selected_shortcuts(&mut world), vec![3]);
+        // and scrolling down from there goes back to the first
+        scroll(&mut world, -1.);
+        assert_eq!(selected_shortcuts(&mut world), vec![1]);
+        assert_eq!(requested_weapons(&mut world), vec![weapons[2], weapons[0]]);
+    }
+
+    #[test]
+    fn scrolling_with_fewer_than_two_weapons_does_nothing() {
+        let mut world = test_world();
+        world.init_resource::<Events<MouseWheel>>();
+
+        scroll(&mut world, -1.);
+        assert!(requested_weapons(&mut world).is_empty());
+
+        spawn_hotbar(&mut world, 1);
+        scroll(&mut world, -1.);
+        scroll(&mut world, 1.);
+        assert_eq!(selected_shortcuts(&mut world), vec![1]);
+        assert!(requested_weapons(&mut world).is_empty());
     }
 }
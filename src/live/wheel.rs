@@ -0,0 +1,214 @@
+//! The weapon wheel,
+//! a radial weapon selector shown while holding Tab,
+//! as an alternative to the weapon hotbar.
+use std::f32::consts::TAU;
+
+use bevy::{prelude::*, ui::FocusPolicy, window::PrimaryWindow};
+use bevy_mod_picking::prelude::Pickable;
+
+use crate::{assets::AudioHandles, logic::Num, GameSettings};
+
+use super::{
+    icon::{spawn_num_text, NumStyle},
+    weapon::{ChangeWeapon, WeaponButton, WeaponSelected},
+    OnLive,
+};
+
+/// Component for the root UI node of the weapon wheel
+#[derive(Debug, Component)]
+pub struct WeaponWheel {
+    /// the weapons in the wheel, clockwise from the top
+    weapons: Vec<(Entity, Num)>,
+    /// the index of the weapon under the cursor, if any
+    highlighted: Option<usize>,
+}
+
+/// Component for each weapon in the weapon wheel
+#[derive(Debug, Component)]
+pub struct WheelSegment(usize);
+
+/// how far the weapons are from the center of the wheel, in pixels
+const WHEEL_RADIUS: f32 = 110.;
+
+/// the size of each weapon in the wheel, in pixels
+const SEGMENT_SIZE: f32 = 56.;
+
+/// how far the cursor must be from the center to select a weapon
+const DEAD_ZONE: f32 = 30.;
+
+/// how fast the game goes while the wheel is open
+const WHEEL_TIME_SPEED: f32 = 0.25;
+
+/// system that opens the weapon wheel when the player presses Tab
+/// (only if enabled in the settings)
+pub fn open_weapon_wheel(
+    mut cmd: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    game_settings: Res<GameSettings>,
+    wheel_q: Query<(), With<WeaponWheel>>,
+    weapon_button_q: Query<(Entity, &WeaponButton)>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    if !game_settings.weapon_wheel || !input.just_pressed(KeyCode::Tab) || !wheel_q.is_empty() {
+        return;
+    }
+    let Ok(window) = window_q.get_single() else {
+        return;
+    };
+
+    let mut weapons: Vec<_> = weapon_button_q
+        .iter()
+        .map(|(entity, button)| (button.shortcut(), entity, button.num()))
+        .collect();
+    if weapons.len() < 2 {
+        // nothing to choose from
+        return;
+    }
+    weapons.sort_by_key(|(shortcut, ..)| *shortcut);
+    let weapons: Vec<_> = weapons
+        .into_iter()
+        .map(|(_, entity, num)| (entity, num))
+        .collect();
+
+    let center = Vec2::new(window.width(), window.height()) / 2.;
+    let num_style = NumStyle::from(&*game_settings);
+    let count = weapons.len();
+    cmd.spawn((
+        OnLive,
+        Pickable::IGNORE,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                ..default()
+            },
+            focus_policy: FocusPolicy::Pass,
+            z_index: ZIndex::Global(12),
+            ..default()
+        },
+    ))
+    .with_children(|cmd| {
+        for (i, (_, num)) in weapons.iter().enumerate() {
+            let pos = center + segment_direction(i, count) * WHEEL_RADIUS;
+            cmd.spawn((
+                WheelSegment(i),
+                Pickable::IGNORE,
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(pos.x - SEGMENT_SIZE / 2.),
+                        top: Val::Px(pos.y - SEGMENT_SIZE / 2.),
+                        width: Val::Px(SEGMENT_SIZE),
+                        height: Val::Px(SEGMENT_SIZE),
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    background_color: BackgroundColor(Color::BLACK.with_alpha(0.85)),
+                    border_radius: BorderRadius::MAX,
+                    focus_policy: FocusPolicy::Pass,
+                    ..default()
+                },
+            ))
+            .with_children(|cmd| {
+                spawn_num_text(cmd, *num, 28., Color::WHITE, num_style);
+            });
+        }
+    })
+    .insert(WeaponWheel {
+        weapons,
+        highlighted: None,
+    });
+
+    // slow the game down while choosing
+    time.set_relative_speed(WHEEL_TIME_SPEED);
+}
+
+/// the screen direction of the segment at the given index,
+/// clockwise from the top
+fn segment_direction(index: usize, count: usize) -> Vec2 {
+    let angle = index as f32 * TAU / count as f32;
+    Vec2::new(angle.sin(), -angle.cos())
+}
+
+/// system that highlights the weapon under the cursor
+/// and selects it once the player lets go of Tab
+pub fn update_weapon_wheel(
+    mut cmd: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    audio_handles: Res<AudioHandles>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    mut wheel_q: Query<(Entity, &mut WeaponWheel)>,
+    mut segment_q: Query<(&WheelSegment, &mut BackgroundColor)>,
+    weapon_button_q: Query<(Entity, &WeaponButton, Has<WeaponSelected>)>,
+    mut change_weapon: EventWriter<ChangeWeapon>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    let Ok((wheel_entity, mut wheel)) = wheel_q.get_single_mut() else {
+        return;
+    };
+
+    // find the weapon in the direction of the cursor
+    let highlighted = window_q.get_single().ok().and_then(|window| {
+        let center = Vec2::new(window.width(), window.height()) / 2.;
+        let offset = window.cursor_position()? - center;
+        if offset.length() < DEAD_ZONE {
+            return None;
+        }
+        let count = wheel.weapons.len();
+        // angle clockwise from the top, from 0 to 1 turn
+        let turns = offset.x.atan2(-offset.y).rem_euclid(TAU) / TAU;
+        Some((turns * count as f32).round() as usize % count)
+    });
+    if wheel.highlighted != highlighted {
+        wheel.highlighted = highlighted;
+        for (segment, mut background_color) in &mut segment_q {
+            background_color.0 = if Some(segment.0) == highlighted {
+                Color::srgb(0.35, 0.35, 0.8)
+            } else {
+                Color::BLACK.with_alpha(0.85)
+            };
+        }
+    }
+
+    if !input.just_released(KeyCode::Tab) {
+        return;
+    }
+
+    // select the highlighted weapon, if it is not selected already
+    if let Some((button_entity, _)) = highlighted.map(|i| wheel.weapons[i]) {
+        // the button may be gone if its weapon was used up meanwhile
+        if let Ok((_, button, false)) = weapon_button_q.get(button_entity) {
+            for (entity, ..) in &weapon_button_q {
+                if entity == button_entity {
+                    cmd.entity(entity).insert(WeaponSelected);
+                } else {
+                    cmd.entity(entity).remove::<WeaponSelected>();
+                }
+            }
+            change_weapon.send(ChangeWeapon {
+                weapon: button.weapon(),
+            });
+            audio_handles.play_equipmentclick1(&mut cmd);
+        }
+    }
+
+    cmd.entity(wheel_entity).despawn_recursive();
+    time.set_relative_speed(1.);
+}
+
+/// system that closes the weapon wheel without selecting anything,
+/// so that it does not linger when the game stops running
+pub fn close_weapon_wheel(
+    mut cmd: Commands,
+    wheel_q: Query<Entity, With<WeaponWheel>>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    for entity in &wheel_q {
+        cmd.entity(entity).despawn_recursive();
+    }
+    if time.relative_speed() != 1. {
+        time.set_relative_speed(1.);
+    }
+}
@@ -11,21 +11,128 @@
 //! Otherwise,
 //! in special cases where the target is more robust,
 //! a new number may be regenerated.
+use std::collections::HashSet;
+
+use serde::Deserialize;
+
 use crate::live::Target;
 
 pub type Num = num_rational::Ratio<i16>;
 
-/// The rule for damaging the target.
+/// How fractions are shown to the player.
+///
+/// Like [`NumberBase`], this is purely representational.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NumDisplay {
+    /// as a numerator and a denominator (`1/4`)
+    #[default]
+    Fraction,
+    /// as a decimal number (`0.25`)
+    Decimal,
+}
+
+impl NumDisplay {
+    /// how many decimal places are written at most
+    const DECIMAL_PLACES: usize = 3;
+
+    pub fn next(self) -> Self {
+        match self {
+            NumDisplay::Fraction => NumDisplay::Decimal,
+            NumDisplay::Decimal => NumDisplay::Fraction,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NumDisplay::Fraction => "1/4",
+            NumDisplay::Decimal => "0.25",
+        }
+    }
+
+    /// Write a fraction in this mode,
+    /// or `None` if the number is whole
+    /// (whole numbers are written in the number base instead).
+    ///
+    /// Decimals are rounded to a few places,
+    /// so 1/3 is written as `0.333`.
+    pub fn format(self, num: Num) -> Option<String> {
+        if num.is_integer() {
+            return None;
+        }
+        match self {
+            NumDisplay::Fraction => Some(num.to_string()),
+            NumDisplay::Decimal => {
+                let value = f64::from(*num.numer()) / f64::from(*num.denom());
+                let text = format!("{:.*}", Self::DECIMAL_PLACES, value);
+                // no trailing zeros, 3/4 is just 0.75
+                Some(text.trim_end_matches('0').trim_end_matches('.').to_string())
+            }
+        }
+    }
+}
+
+/// The base in which whole numbers are shown to the player.
+///
+/// This is purely representational,
+/// the rules of attack work on the values.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NumberBase {
+    #[default]
+    Decimal,
+    Binary,
+    Hexadecimal,
+}
+
+impl NumberBase {
+    pub fn next(self) -> Self {
+        match self {
+            NumberBase::Decimal => NumberBase::Binary,
+            NumberBase::Binary => NumberBase::Hexadecimal,
+            NumberBase::Hexadecimal => NumberBase::Decimal,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NumberBase::Decimal => "Decimal",
+            NumberBase::Binary => "Binary",
+            NumberBase::Hexadecimal => "Hex",
+        }
+    }
+
+    /// Write a number in this base
+    /// (fractions are always written in decimal)
+    pub fn format(self, num: Num) -> String {
+        if !num.is_integer() {
+            return num.to_string();
+        }
+        let n = num.to_integer();
+        // keep the sign apart,
+        // so that negative numbers are not shown in two's complement
+        let sign = if n < 0 { "-" } else { "" };
+        let n = n.unsigned_abs();
+        match self {
+            NumberBase::Decimal => format!("{sign}{n}"),
+            NumberBase::Binary => format!("{sign}{n:b}"),
+            NumberBase::Hexadecimal => format!("{sign}{n:X}"),
+        }
+    }
+}
+
+/// The rule for damaging the target.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
 pub enum TargetRule {
     /// The default rule for most cases.
     /// The attack number must be a factor of the target,
     /// further decomposing the target number until it reaches 1.
     ///
-    /// If the target number is 1,
+    /// If the target number is 1 (or -1),
     /// any attack will damage it.
-    /// Otherwise, an attack of 1, 0, or a non-whole number
+    /// Otherwise, an attack of 1, -1, 0, or a non-whole number
     /// is a failed attack.
+    ///
+    /// Negative numbers follow the same rules,
+    /// so -6 can be decomposed by 2 (into -3) or by -2 (into 3).
     #[default]
     Factorize,
     /// The number must be exactly equal to the target.
@@ -35,6 +142,75 @@ pub enum TargetRule {
     /// Any attack will fail.
     /// This is usually a temporary state or a rule for temporary obstacles.
     Invulnerable,
+    /// The attacks must add up to the target number.
+    ///
+    /// Each attack adds to a running sum kept by the target,
+    /// which is damaged once the sum is exactly the target number.
+    /// Going over the target number is a failed attack.
+    SumTo,
+    /// The number must be strictly greater than the target.
+    GreaterThan,
+    /// The remainder of dividing the number by the divisor
+    /// must be the given remainder,
+    /// like on a clock.
+    ///
+    /// If the divisor is a whole number,
+    /// a non-whole attack is a failed attack.
+    ModEquals { divisor: Num, remainder: Num },
+    /// Like [`Factorize`](TargetRule::Factorize),
+    /// but the attack number must also be a prime number.
+    PrimeFactor,
+    /// The target must be hit with a pair of factors
+    /// which multiply into the target number,
+    /// one after the other (12 can take 3 and then 4, or 2 and then 6).
+    ///
+    /// The first factor is kept by the target
+    /// and must be a whole number dividing the target,
+    /// other than 1 or the target itself.
+    /// A wrong second factor is a failed attack,
+    /// but the first factor is kept.
+    FactorPair,
+}
+
+impl TargetRule {
+    /// The rules which the player may have to follow
+    /// (with an example clock, as only its description is of use here)
+    pub const PLAYABLE: [TargetRule; 7] = [
+        TargetRule::Factorize,
+        TargetRule::Equal,
+        TargetRule::SumTo,
+        TargetRule::GreaterThan,
+        TargetRule::ModEquals {
+            divisor: Num::new_raw(4, 1),
+            remainder: Num::new_raw(3, 1),
+        },
+        TargetRule::PrimeFactor,
+        TargetRule::FactorPair,
+    ];
+
+    /// A short reminder of how to attack a target with this rule
+    pub fn description(self) -> &'static str {
+        match self {
+            TargetRule::Factorize => {
+                "Divide: attack with a whole number which divides the target, until it reaches 1"
+            }
+            TargetRule::Equal => "Match: attack with a number equal to the target",
+            TargetRule::Invulnerable => "Invulnerable: nothing works, better wait",
+            TargetRule::SumTo => {
+                "Sum: attack with numbers adding up to the target, without going over"
+            }
+            TargetRule::GreaterThan => "Exceed: attack with a number greater than the target",
+            TargetRule::ModEquals { .. } => {
+                "Clock: attack with a number leaving the right remainder when divided"
+            }
+            TargetRule::PrimeFactor => {
+                "Prime: attack with a prime number which divides the target, until it reaches 1"
+            }
+            TargetRule::FactorPair => {
+                "Pair: attack with two numbers which multiply into the target, one after the other"
+            }
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -43,22 +219,51 @@ pub enum AttackTest {
     /// the target becomes the given number (`Some`)
     /// or is damaged (`None`).
     Effective(Option<Num>),
+    /// The attack brought the target closer to being damaged,
+    /// the running sum (or the first factor of a pair)
+    /// becomes the given number.
+    Progress(Num),
     /// The attack was ineffective.
     Failed,
+    /// The attack was ineffective
+    /// because the running sum went over the target number,
+    /// becoming the given number.
+    Overshot(Num),
 }
 
 #[inline]
 pub fn test_attack_on(target: &Target, attack: Num) -> AttackTest {
-    test_attack(target.rule, attack, target.num)
+    test_attack_with_progress(target.rule, attack, target.accumulated, target.num)
+}
+
+/// Test an attack on a target
+/// given the progress made on it so far
+/// (the running sum, or the first factor of a pair).
+fn test_attack_with_progress(
+    rule: TargetRule,
+    attack: Num,
+    accumulated: Num,
+    target: Num,
+) -> AttackTest {
+    match rule {
+        TargetRule::SumTo => test_sum_attack(attack, accumulated, target),
+        TargetRule::FactorPair => test_factor_pair_attack(attack, accumulated, target),
+        _ => test_attack(rule, attack, target),
+    }
 }
 
 /// Test an attack to see what effect it has on the target.
 pub fn test_attack(rule: TargetRule, attack: Num, target: Num) -> AttackTest {
     match rule {
         TargetRule::Factorize => {
-            if target == Num::ONE || target == attack {
+            if target == Num::ONE || target == -Num::ONE || target == attack {
                 AttackTest::Effective(None)
-            } else if !attack.reduced().is_integer() {
+            } else if !attack.reduced().is_integer()
+                || attack == Num::ZERO
+                || attack == Num::ONE
+                || attack == -Num::ONE
+            {
+                // dividing by these takes the target nowhere
                 AttackTest::Failed
             } else if target % attack == Num::ZERO {
                 AttackTest::Effective(Some(target / attack))
@@ -74,5 +279,549 @@ pub fn test_attack(rule: TargetRule, attack: Num, target: Num) -> AttackTest {
             }
         }
         TargetRule::Invulnerable => AttackTest::Failed,
+        TargetRule::SumTo => test_sum_attack(attack, Num::ZERO, target),
+        TargetRule::GreaterThan => {
+            if attack > target {
+                AttackTest::Effective(None)
+            } else {
+                AttackTest::Failed
+            }
+        }
+        TargetRule::ModEquals { divisor, remainder } => {
+            if divisor == Num::ZERO
+                || (divisor.reduced().is_integer() && !attack.reduced().is_integer())
+            {
+                AttackTest::Failed
+            } else if rem_euclid(attack, divisor) == remainder {
+                AttackTest::Effective(None)
+            } else {
+                AttackTest::Failed
+            }
+        }
+        TargetRule::PrimeFactor => {
+            let attack = attack.reduced();
+            if attack.is_integer() && is_prime(*attack.numer()) {
+                test_attack(TargetRule::Factorize, attack, target)
+            } else {
+                AttackTest::Failed
+            }
+        }
+        TargetRule::FactorPair => test_factor_pair_attack(attack, Num::ZERO, target),
+    }
+}
+
+/// The remainder of dividing the number by the divisor,
+/// never negative, like going around a clock
+/// (so -1 mod 4 is 3, not -1).
+fn rem_euclid(num: Num, divisor: Num) -> Num {
+    let remainder = num % divisor;
+    if remainder < Num::ZERO && divisor < Num::ZERO {
+        remainder - divisor
+    } else if remainder < Num::ZERO {
+        remainder + divisor
+    } else {
+        remainder
+    }
+}
+
+/// Check whether a whole number is prime.
+fn is_prime(n: i16) -> bool {
+    let n = i32::from(n);
+    if n < 2 {
+        return false;
+    }
+    (2..).take_while(|d| d * d <= n).all(|d| n % d != 0)
+}
+
+/// Test an attack on a target with the [`TargetRule::SumTo`] rule,
+/// given the sum of the attacks so far.
+pub fn test_sum_attack(attack: Num, accumulated: Num, target: Num) -> AttackTest {
+    if attack <= Num::ZERO {
+        return AttackTest::Failed;
+    }
+    let sum = accumulated + attack;
+    if sum == target {
+        AttackTest::Effective(None)
+    } else if sum < target {
+        AttackTest::Progress(sum)
+    } else {
+        AttackTest::Overshot(sum)
+    }
+}
+
+/// Test an attack on a target with the [`TargetRule::FactorPair`] rule,
+/// given the first factor of the pair (zero if there is none yet).
+fn test_factor_pair_attack(attack: Num, first: Num, target: Num) -> AttackTest {
+    if first != Num::ZERO {
+        return if first * attack == target {
+            AttackTest::Effective(None)
+        } else {
+            AttackTest::Failed
+        };
+    }
+
+    let trivial = [Num::ZERO, Num::ONE, -Num::ONE, target, -target];
+    if !attack.reduced().is_integer() || trivial.contains(&attack) || target % attack != Num::ZERO {
+        AttackTest::Failed
+    } else {
+        AttackTest::Progress(attack)
+    }
+}
+
+/// Check whether a target could be damaged
+/// using only the given attack numbers,
+/// possibly over multiple attacks if the rule requires so.
+pub fn can_damage(rule: TargetRule, target: Num, weapons: &[Num]) -> bool {
+    /// how many attacks to try in a row at most
+    const MAX_ATTACKS: u8 = 16;
+
+    // go through the progress which can be made one attack at a time,
+    // so that each bit of progress is only looked into once,
+    // and with as many attacks left as possible
+    let mut seen = HashSet::from([Num::ZERO]);
+    let mut frontier = vec![Num::ZERO];
+    for _ in 0..MAX_ATTACKS {
+        let mut next = vec![];
+        for &accumulated in &frontier {
+            for &attack in weapons {
+                match test_attack_with_progress(rule, attack, accumulated, target) {
+                    AttackTest::Effective(_) => return true,
+                    AttackTest::Progress(sum) => {
+                        if seen.insert(sum) {
+                            next.push(sum);
+                        }
+                    }
+                    AttackTest::Failed | AttackTest::Overshot(_) => {}
+                }
+            }
+        }
+        if next.is_empty() {
+            return false;
+        }
+        frontier = next;
+    }
+    false
+}
+
+/// The points for an effective attack on a target with the given number.
+///
+/// Numbers which take more thought are worth more:
+/// bigger numbers, numbers with more prime factors, and fractions.
+pub fn target_score(target: Num) -> u32 {
+    /// the number of prime factors of a whole number, with repetition
+    fn prime_factor_count(mut n: u32) -> u32 {
+        let mut count = 0;
+        let mut d = 2;
+        while d * d <= n {
+            while n % d == 0 {
+                n /= d;
+                count += 1;
+            }
+            d += 1;
+        }
+        if n > 1 {
+            count += 1;
+        }
+        count
+    }
+
+    let target = target.reduced();
+    let numer = u32::from(target.numer().unsigned_abs());
+    let denom = u32::from(target.denom().unsigned_abs());
+    // roughly the number of binary digits of the biggest part
+    let size = u32::BITS - numer.max(denom).leading_zeros();
+    let factors = prime_factor_count(numer) + prime_factor_count(denom);
+    let fraction_bonus = if denom > 1 { 25 } else { 0 };
+    10 + 5 * size + 5 * factors + fraction_bonus
+}
+
+/// A short description of the progress made on a target,
+/// for showing to the player,
+/// with numbers written by `format`.
+pub fn describe_progress(target: &Target, progress: Num, format: impl Fn(Num) -> String) -> String {
+    if target.rule == TargetRule::FactorPair {
+        format!("{} x ? = {}", format(progress), format(target.num))
+    } else {
+        format!("{} of {}", format(progress), format(target.num))
+    }
+}
+
+/// A short explanation of why an attack failed,
+/// for showing to the player,
+/// with numbers written by `format`.
+pub fn describe_failure(
+    target: &Target,
+    attack: Num,
+    result: AttackTest,
+    format: impl Fn(Num) -> String,
+) -> String {
+    match result {
+        AttackTest::Overshot(_) if target.accumulated == Num::ZERO => {
+            format!("{} > {}", format(attack), format(target.num))
+        }
+        AttackTest::Overshot(sum) => {
+            format!(
+                "{} + {} = {} > {}",
+                format(target.accumulated),
+                format(attack),
+                format(sum),
+                format(target.num)
+            )
+        }
+        AttackTest::Failed => match target.rule {
+            TargetRule::GreaterThan => {
+                format!("{} is not > {}", format(attack), format(target.num))
+            }
+            TargetRule::ModEquals { divisor, remainder }
+                if divisor != Num::ZERO && attack.reduced().is_integer() =>
+            {
+                format!(
+                    "{} mod {} = {}, not {}",
+                    format(attack),
+                    format(divisor),
+                    format(rem_euclid(attack, divisor)),
+                    format(remainder)
+                )
+            }
+            TargetRule::PrimeFactor
+                if attack.reduced().is_integer() && !is_prime(*attack.reduced().numer()) =>
+            {
+                format!("{} is not prime", format(attack))
+            }
+            TargetRule::FactorPair if target.accumulated != Num::ZERO => {
+                format!(
+                    "{} x {} is not {}",
+                    format(target.accumulated),
+                    format(attack),
+                    format(target.num)
+                )
+            }
+            _ => "X wrong!".to_string(),
+        },
+        _ => "X wrong!".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock(divisor: i16, remainder: i16) -> TargetRule {
+        TargetRule::ModEquals {
+            divisor: divisor.into(),
+            remainder: remainder.into(),
+        }
+    }
+
+    #[test]
+    fn mod_equals_goes_around_the_clock() {
+        let rule = clock(4, 3);
+        for attack in [3, 7, 11, -1, -5] {
+            assert_eq!(
+                test_attack(rule, Num::from_integer(attack), 3.into()),
+                AttackTest::Effective(None),
+                "{attack} mod 4 should be 3"
+            );
+        }
+        for attack in [0, 1, 2, 4, -3, -4] {
+            assert_eq!(
+                test_attack(rule, Num::from_integer(attack), 3.into()),
+                AttackTest::Failed,
+                "{attack} mod 4 should not be 3"
+            );
+        }
+    }
+
+    #[test]
+    fn mod_equals_fails_on_fractions_with_whole_divisors() {
+        assert_eq!(
+            test_attack(clock(4, 3), Num::new(7, 2), 3.into()),
+            AttackTest::Failed
+        );
+    }
+
+    #[test]
+    fn playable_rules_include_mod_equals() {
+        assert!(TargetRule::PLAYABLE
+            .iter()
+            .any(|rule| matches!(rule, TargetRule::ModEquals { .. })));
+    }
+
+    #[test]
+    fn negative_targets_can_be_factorized() {
+        let factorize = |attack: i16, target: i16| {
+            test_attack(TargetRule::Factorize, attack.into(), target.into())
+        };
+        assert_eq!(factorize(-2, -6), AttackTest::Effective(Some(3.into())));
+        assert_eq!(factorize(2, -6), AttackTest::Effective(Some((-3).into())));
+        assert_eq!(factorize(-2, 6), AttackTest::Effective(Some((-3).into())));
+        assert_eq!(factorize(-6, -6), AttackTest::Effective(None));
+        assert_eq!(factorize(5, -1), AttackTest::Effective(None));
+        assert_eq!(factorize(4, -6), AttackTest::Failed);
+
+        let equal =
+            |attack: i16, target: i16| test_attack(TargetRule::Equal, attack.into(), target.into());
+        assert_eq!(equal(-6, -6), AttackTest::Effective(None));
+        assert_eq!(equal(6, -6), AttackTest::Failed);
+    }
+
+    #[test]
+    fn attacks_which_divide_nothing_out_fail() {
+        for target in [6, -6] {
+            for attack in [0, 1, -1] {
+                assert_eq!(
+                    test_attack(TargetRule::Factorize, attack.into(), target.into()),
+                    AttackTest::Failed,
+                    "attacking {target} with {attack} should fail"
+                );
+            }
+        }
+        // and they do not count as a way to damage the target
+        assert!(!can_damage(
+            TargetRule::Factorize,
+            35.into(),
+            &nums(&[1, -1])
+        ));
+        // the target can still be finished by matching it
+        assert_eq!(
+            test_attack(TargetRule::Factorize, Num::ONE, Num::ONE),
+            AttackTest::Effective(None)
+        );
+    }
+
+    #[test]
+    fn prime_factors_only_take_prime_attacks() {
+        let prime_factor = |attack: Num| test_attack(TargetRule::PrimeFactor, attack, 12.into());
+        assert_eq!(
+            prime_factor(2.into()),
+            AttackTest::Effective(Some(6.into()))
+        );
+        assert_eq!(
+            prime_factor(3.into()),
+            AttackTest::Effective(Some(4.into()))
+        );
+        // a factor, but not a prime one
+        assert_eq!(prime_factor(4.into()), AttackTest::Failed);
+        // a prime, but not a factor
+        assert_eq!(prime_factor(5.into()), AttackTest::Failed);
+        // fractions are never prime
+        assert_eq!(prime_factor(Num::new(1, 2)), AttackTest::Failed);
+        assert_eq!(prime_factor(Num::new(5, 2)), AttackTest::Failed);
+        assert_eq!(
+            test_attack(TargetRule::PrimeFactor, Num::new(1, 2), Num::new(1, 2)),
+            AttackTest::Failed
+        );
+    }
+
+    #[test]
+    fn negative_numbers_keep_their_sign_in_every_base() {
+        let num = Num::from_integer(-26);
+        assert_eq!(NumberBase::Decimal.format(num), "-26");
+        assert_eq!(NumberBase::Binary.format(num), "-11010");
+        assert_eq!(NumberBase::Hexadecimal.format(num), "-1A");
+    }
+
+    #[test]
+    fn fractions_are_written_in_the_chosen_display() {
+        let third = Num::new(1, 3);
+        let three_quarters = Num::new(3, 4);
+        assert_eq!(NumDisplay::Fraction.format(third).as_deref(), Some("1/3"));
+        assert_eq!(
+            NumDisplay::Fraction.format(three_quarters).as_deref(),
+            Some("3/4")
+        );
+        assert_eq!(NumDisplay::Decimal.format(third).as_deref(), Some("0.333"));
+        assert_eq!(
+            NumDisplay::Decimal.format(three_quarters).as_deref(),
+            Some("0.75")
+        );
+        // whole numbers are left to the number base
+        for display in [NumDisplay::Fraction, NumDisplay::Decimal] {
+            assert_eq!(display.format(Num::from_integer(4)), None);
+            assert_eq!(display.format(Num::new(8, 2)), None);
+        }
+    }
+
+    #[test]
+    fn greater_than_needs_a_strictly_bigger_number() {
+        let greater =
+            |attack: Num, target: Num| test_attack(TargetRule::GreaterThan, attack, target);
+        // whole numbers
+        assert_eq!(greater(5.into(), 4.into()), AttackTest::Effective(None));
+        assert_eq!(greater(4.into(), 4.into()), AttackTest::Failed);
+        assert_eq!(greater(3.into(), 4.into()), AttackTest::Failed);
+        assert_eq!(
+            greater((-2).into(), (-3).into()),
+            AttackTest::Effective(None)
+        );
+        // fractions
+        assert_eq!(
+            greater(Num::new(3, 4), Num::new(1, 2)),
+            AttackTest::Effective(None)
+        );
+        assert_eq!(greater(Num::new(1, 2), Num::new(3, 4)), AttackTest::Failed);
+        assert_eq!(greater(Num::new(2, 4), Num::new(1, 2)), AttackTest::Failed);
+        assert_eq!(
+            greater(1.into(), Num::new(3, 4)),
+            AttackTest::Effective(None)
+        );
+    }
+
+    #[test]
+    fn sum_attacks_add_up_to_the_target() {
+        let ten = Num::from_integer(10);
+        // exact
+        assert_eq!(
+            test_sum_attack(7.into(), 3.into(), ten),
+            AttackTest::Effective(None)
+        );
+        // undershoot
+        assert_eq!(
+            test_sum_attack(3.into(), Num::ZERO, ten),
+            AttackTest::Progress(3.into())
+        );
+        assert_eq!(
+            test_sum_attack(Num::new(1, 2), Num::new(5, 2), ten),
+            AttackTest::Progress(3.into())
+        );
+        // overshoot
+        assert_eq!(
+            test_sum_attack(8.into(), 3.into(), ten),
+            AttackTest::Overshot(11.into())
+        );
+        // nothing to add
+        assert_eq!(
+            test_sum_attack(Num::ZERO, 3.into(), ten),
+            AttackTest::Failed
+        );
+    }
+
+    #[test]
+    fn target_score_rewards_harder_numbers() {
+        let score = |numer: i16, denom: i16| target_score(Num::new(numer, denom));
+        assert_eq!(score(1, 1), 15);
+        assert_eq!(score(2, 1), 25);
+        assert_eq!(score(12, 1), 45);
+        assert_eq!(score(1, 2), 50);
+        // bigger numbers
+        assert!(score(64, 1) > score(2, 1));
+        // more prime factors, at the same size
+        assert!(score(12, 1) > score(13, 1));
+        // fractions
+        assert!(score(1, 2) > score(2, 1));
+    }
+
+    #[test]
+    fn target_score_ignores_sign_and_representation() {
+        assert_eq!(
+            target_score(Num::new(-12, 1)),
+            target_score(Num::new(12, 1))
+        );
+        assert_eq!(
+            target_score(Num::new_raw(6, 4)),
+            target_score(Num::new(3, 2))
+        );
+        assert_eq!(target_score(Num::new(3, -2)), target_score(Num::new(3, 2)));
+    }
+
+    fn nums(values: &[i16]) -> Vec<Num> {
+        values.iter().map(|&v| Num::from_integer(v)).collect()
+    }
+
+    #[test]
+    fn can_damage_with_a_single_attack() {
+        let weapons = nums(&[2, 3]);
+        assert!(can_damage(TargetRule::Factorize, 12.into(), &weapons));
+        assert!(!can_damage(TargetRule::Factorize, 35.into(), &weapons));
+        assert!(can_damage(TargetRule::Equal, 3.into(), &weapons));
+        assert!(!can_damage(TargetRule::Equal, 4.into(), &weapons));
+        assert!(!can_damage(TargetRule::Invulnerable, 2.into(), &weapons));
+    }
+
+    #[test]
+    fn can_damage_over_multiple_attacks() {
+        assert!(can_damage(TargetRule::SumTo, 10.into(), &nums(&[3, 4])));
+        assert!(!can_damage(TargetRule::SumTo, 5.into(), &nums(&[2, 4])));
+        assert!(can_damage(
+            TargetRule::FactorPair,
+            12.into(),
+            &nums(&[3, 4])
+        ));
+        assert!(!can_damage(
+            TargetRule::FactorPair,
+            12.into(),
+            &nums(&[5, 7])
+        ));
+        // more attacks than can be made in a row
+        assert!(!can_damage(TargetRule::SumTo, 17.into(), &nums(&[1])));
+        assert!(can_damage(TargetRule::SumTo, 16.into(), &nums(&[1])));
+    }
+
+    #[test]
+    fn factor_pairs_multiply_into_the_target() {
+        let pair_target = |accumulated: i16| Target {
+            num: 12.into(),
+            rule: TargetRule::FactorPair,
+            accumulated: accumulated.into(),
+        };
+        // a valid pair
+        assert_eq!(
+            test_attack_on(&pair_target(0), 3.into()),
+            AttackTest::Progress(3.into())
+        );
+        assert_eq!(
+            test_attack_on(&pair_target(3), 4.into()),
+            AttackTest::Effective(None)
+        );
+        // an invalid pair
+        assert_eq!(
+            test_attack_on(&pair_target(3), 3.into()),
+            AttackTest::Failed
+        );
+        assert_eq!(
+            test_attack_on(&pair_target(0), 5.into()),
+            AttackTest::Failed
+        );
+        // the target itself is not a factor of a pair
+        assert_eq!(
+            test_attack_on(&pair_target(0), 12.into()),
+            AttackTest::Failed
+        );
+        assert_eq!(
+            test_attack_on(&pair_target(0), Num::ONE),
+            AttackTest::Failed
+        );
+    }
+
+    #[test]
+    fn can_damage_gives_up_quickly_on_unreachable_sums() {
+        // trying every sequence of attacks would take forever
+        let weapons = nums(&[1, 2, 3, 4, 5, 6, 7]);
+        assert!(!can_damage(TargetRule::SumTo, 1000.into(), &weapons));
+        let fractions: Vec<Num> = (1..=7).map(|d| Num::new(1, d)).collect();
+        assert!(!can_damage(TargetRule::SumTo, 100.into(), &fractions));
+    }
+
+    #[test]
+    fn descriptions_use_the_given_format() {
+        let hex = |num: Num| NumberBase::Hexadecimal.format(num);
+        let target = Target {
+            num: Num::from_integer(26),
+            rule: TargetRule::SumTo,
+            accumulated: Num::from_integer(12),
+        };
+        assert_eq!(
+            describe_progress(&target, Num::from_integer(20), hex),
+            "14 of 1A"
+        );
+        assert_eq!(
+            describe_failure(
+                &target,
+                Num::from_integer(15),
+                AttackTest::Overshot(Num::from_integer(27)),
+                hex
+            ),
+            "C + F = 1B > 1A"
+        );
     }
 }
@@ -2,22 +2,28 @@ use assets::{AudioHandles, DefaultFont, TextureHandles};
 use bevy::{
     asset::AssetMetaCheck,
     prelude::*,
-    window::{WindowMode, WindowResized, WindowResolution},
+    window::{WindowBackendScaleFactorChanged, WindowMode, WindowResized, WindowResolution},
 };
 use bevy_mod_picking::DefaultPickingPlugins;
 use cheat::{Cheats, TextBuffer};
-use live::LiveActionPlugin;
+use educator::EducatorConfig;
+use live::{Difficulty, InterludeMode, LiveActionPlugin};
+use logic::{NumDisplay, NumberBase};
 use menu::MenuPlugin;
+use persist::PersistentData;
 use postprocess::PostProcessPlugin;
-use ui::{update_buttons_on_window_resize, Sizes};
+use ui::{update_buttons_on_window_resize, Sizes, Toasts};
 
 mod assets;
 mod cheat;
+mod educator;
 mod effect;
 mod live;
 mod logic;
 mod menu;
+mod persist;
 mod postprocess;
+mod runcard;
 mod structure;
 mod ui;
 
@@ -38,24 +44,136 @@ pub enum AppState {
 pub struct GameSettings {
     /// whether to show the amount of time the player is taking
     show_timer: bool,
-    /// whether to skip interludes
+    /// whether to show the distance left to the end of the corridor
+    show_distance: bool,
+    /// whether to show interludes in full, move on from them quickly,
+    /// or skip them
     /// (it will not skip the ones ending the game at the end of the sequence)
-    skip_interludes: bool,
+    interlude_mode: InterludeMode,
+    /// whether the game is in practice mode,
+    /// which enables a few extra controls over the pacing of the game
+    practice_mode: bool,
+    /// whether to highlight the target which should be attacked first
+    highlight_urgent: bool,
+    /// whether to portray fractions with a horizontal bar
+    /// (numerator over denominator)
+    fraction_bar: bool,
+    /// whether to switch to a newly picked up weapon
+    /// if it is the one last chosen by the player
+    /// (the first weapon is always selected)
+    auto_switch_weapon: bool,
+    /// whether to restart the level right away on defeat,
+    /// instead of showing the defeat screen
+    auto_retry: bool,
+    /// whether the numbers in each level should be shuffled
+    /// differently in every session
+    /// (otherwise they are the same in every playthrough)
+    shuffle_numbers: bool,
+    /// whether to show a letter grade
+    /// based on the accuracy of the player's recent attacks
+    show_grade: bool,
+    /// whether to keep the mouse cursor inside the window
+    /// while the game is running
+    confine_cursor: bool,
+    /// whether to tone down the scares of the first level
+    /// when playing for the first time
+    calm_start: bool,
+    /// whether to preview what the selected weapon
+    /// would do to the target under the cursor
+    preview_attack: bool,
+    /// whether to color mobs by their value,
+    /// so that equivalent fractions look alike
+    fraction_colors: bool,
+    /// whether the player only walks forward while holding W or up
+    manual_movement: bool,
+    /// whether weapon cubes are absorbed from further away
+    /// and drift towards the player
+    magnet_cubes: bool,
+    /// whether projectiles slow down when close to a target,
+    /// so that the impact is easier to follow
+    slow_shots: bool,
+    /// whether holding Tab shows a weapon wheel
+    weapon_wheel: bool,
+    /// whether to dim the scenery so that the things which matter stand out
+    focus_mode: bool,
+    /// how much the camera shakes when the player is hurt
+    screen_shake: f32,
+    /// the base in which whole numbers are shown
+    number_base: NumberBase,
+    /// how fractions are shown
+    number_display: NumDisplay,
+    /// how many mobs come and how much health the player has
+    difficulty: Difficulty,
+    /// whether to make a level easier
+    /// after the player is defeated in it a few times in a row
+    assist_drift: bool,
+    /// whether to include bonus challenges in some levels
+    bonus_rooms: bool,
+    /// whether to fire with the right mouse button instead of the left one
+    /// (the left button is still used for everything else)
+    fire_on_right_click: bool,
+    /// whether to show weapon buttons without keyboard shortcuts
+    /// and with bigger numbers
+    simple_hotbar: bool,
 }
 
 impl Default for GameSettings {
     fn default() -> Self {
         Self {
             show_timer: false,
-            skip_interludes: false,
+            show_distance: false,
+            interlude_mode: InterludeMode::Full,
+            practice_mode: false,
+            highlight_urgent: false,
+            fraction_bar: false,
+            auto_switch_weapon: true,
+            auto_retry: false,
+            shuffle_numbers: false,
+            show_grade: false,
+            confine_cursor: false,
+            calm_start: true,
+            simple_hotbar: false,
+            preview_attack: false,
+            fire_on_right_click: false,
+            bonus_rooms: false,
+            difficulty: Difficulty::Normal,
+            assist_drift: false,
+            number_base: NumberBase::Decimal,
+            number_display: NumDisplay::Fraction,
+            fraction_colors: false,
+            focus_mode: false,
+            screen_shake: 1.,
+            manual_movement: false,
+            weapon_wheel: false,
+            magnet_cubes: false,
+            slow_shots: false,
         }
     }
 }
 
-/// Marker for the main camera
+/// Marker for the main camera of the live game
+/// (there should be exactly one while playing,
+/// since UI anchors are placed relative to it,
+/// so the menu camera does not have it)
 #[derive(Component)]
 pub struct CameraMarker;
 
+/// system to report when there is not exactly one main camera,
+/// which would make UI anchors misbehave
+fn check_main_camera(
+    camera_q: Query<(), With<CameraMarker>>,
+    mut last_count: Local<Option<usize>>,
+) {
+    let count = camera_q.iter().count();
+    if *last_count == Some(count) {
+        return;
+    }
+    *last_count = Some(count);
+    if count != 1 {
+        warn!("Expected exactly one main camera, found {count}");
+    }
+}
+
 fn main() {
     App::new()
         .add_plugins((
@@ -98,15 +216,28 @@ fn main() {
                 postprocess::fadeout_dithering,
                 cheat::cheat_input,
                 (update_ui_sizes_on_resize, update_buttons_on_window_resize).chain(),
+                (ui::show_toasts, ui::update_toasts).chain(),
+                check_main_camera.run_if(in_state(AppState::Live)),
+                assets::update_background_music,
+                runcard::capture_run_card,
             ),
         )
         .add_systems(PostUpdate, (effect::apply_glimmer,))
+        // background music for each part of the game
+        .add_systems(OnEnter(AppState::Menu), assets::start_menu_music)
+        .add_systems(OnExit(AppState::Menu), assets::stop_music)
+        .add_systems(OnEnter(AppState::Live), assets::start_live_music)
+        .add_systems(OnExit(AppState::Live), assets::stop_music)
         // add resources which are used globally
         .init_resource::<DefaultFont>()
         .init_resource::<Sizes>()
         .init_resource::<GameSettings>()
+        .init_resource::<EducatorConfig>()
         .init_resource::<Cheats>()
         .init_resource::<TextBuffer>()
+        .init_resource::<Toasts>()
+        .init_resource::<runcard::RunCardOutcomes>()
+        .insert_resource(PersistentData::load())
         // add resources which we want to be able to load early
         .init_resource::<TextureHandles>()
         .init_resource::<AudioHandles>()
@@ -135,17 +266,89 @@ fn init_ui_sizes(mut sizes: ResMut<Sizes>, window_q: Query<&Window>) {
     }
 }
 
-/// Startup system to set up the UI sizes based on the window size.
-/// Should also be called when the window is resized.
+/// System to update the UI sizes when the window is resized.
+///
+/// A change in the scale factor of the window
+/// (such as when zooming the page on the web)
+/// also changes its logical size without a resize event,
+/// so it is handled here as well.
+/// Cursor positions, UI nodes, and picking all work in logical pixels,
+/// so the crosshair stays aligned with what gets hit
+/// as long as the UI is laid out for the current logical size.
 fn update_ui_sizes_on_resize(
-    mut sizes: ResMut<Sizes>,
+    sizes: ResMut<Sizes>,
     mut resize_reader: EventReader<WindowResized>,
+    mut scale_factor_reader: EventReader<WindowBackendScaleFactorChanged>,
+    window_q: Query<&Window>,
 ) {
-    if let Some(ev) = resize_reader.read().next() {
-        if ev.width < 600. || ev.height < 480. {
-            *sizes = Sizes::SMALL;
-        } else {
-            *sizes = Sizes::default();
-        }
+    let resized = resize_reader.read().last().is_some();
+    let rescaled = scale_factor_reader.read().last().is_some();
+    if resized || rescaled {
+        init_ui_sizes(sizes, window_q);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    fn window_world(width: f32, height: f32) -> (World, Entity) {
+        let mut world = World::new();
+        world.init_resource::<Sizes>();
+        world.init_resource::<Events<WindowResized>>();
+        world.init_resource::<Events<WindowBackendScaleFactorChanged>>();
+        let mut window = Window::default();
+        window.resolution.set(width, height);
+        let window = world.spawn(window).id();
+        (world, window)
+    }
+
+    #[test]
+    fn ui_sizes_follow_window_resizes() {
+        let (mut world, window) = window_world(1280., 720.);
+        world.run_system_once(update_ui_sizes_on_resize);
+        assert_eq!(
+            world.resource::<Sizes>().button_font_size,
+            Sizes::default().button_font_size
+        );
+
+        world
+            .get_mut::<Window>(window)
+            .unwrap()
+            .resolution
+            .set(500., 400.);
+        world.send_event(WindowResized {
+            window,
+            width: 500.,
+            height: 400.,
+        });
+        world.run_system_once(update_ui_sizes_on_resize);
+        assert_eq!(
+            world.resource::<Sizes>().button_font_size,
+            Sizes::SMALL.button_font_size
+        );
+    }
+
+    #[test]
+    fn ui_sizes_follow_scale_factor_changes() {
+        let (mut world, window) = window_world(1280., 720.);
+
+        // zooming in halves the logical size without a resize event
+        world
+            .get_mut::<Window>(window)
+            .unwrap()
+            .resolution
+            .set_scale_factor_override(Some(2.));
+        world.send_event(WindowBackendScaleFactorChanged {
+            window,
+            scale_factor: 2.,
+        });
+        world.run_system_once(update_ui_sizes_on_resize);
+        assert_eq!(
+            world.resource::<Sizes>().button_font_size,
+            Sizes::SMALL.button_font_size
+        );
     }
 }
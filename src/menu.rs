@@ -6,9 +6,12 @@ use crate::{
     assets::{AudioHandles, DefaultFont},
     cheat::Cheats,
     despawn_all_at,
-    live::LiveTime,
+    educator::EducatorConfig,
+    effect::CameraShake,
+    live::{format_time, Achievement, Ending, LevelId, LevelWarp, LiveTime, ResumeRun, SaveState},
+    persist::PersistentData,
     ui::{button_system, spawn_button, Sizes},
-    AppState, CameraMarker, GameSettings,
+    AppState, GameSettings,
 };
 
 #[derive(SubStates, Debug, Default, Clone, Eq, Hash, PartialEq)]
@@ -21,6 +24,8 @@ enum MenuState {
     Main,
     /// A separate section for the settings screen
     Settings,
+    /// A screen for jumping straight to any level (development only)
+    LevelSelect,
     /// Disabled
     Disabled,
 }
@@ -34,28 +39,86 @@ impl Plugin for MenuPlugin {
             .add_systems(OnEnter(MenuState::Main), main_menu_setup)
             .add_systems(OnExit(MenuState::Main), despawn_all_at::<OnMainMenu>)
             .add_systems(OnEnter(MenuState::Settings), settings_menu_setup)
+            .add_systems(
+                Update,
+                settings_menu_setup
+                    .run_if(in_state(MenuState::Settings))
+                    .run_if(resource_changed::<SettingsPage>),
+            )
             .add_systems(
                 OnExit(MenuState::Settings),
                 despawn_all_at::<OnSettingsMenu>,
             )
+            .add_systems(OnEnter(MenuState::LevelSelect), level_select_setup)
+            .add_systems(
+                Update,
+                level_select_setup
+                    .run_if(in_state(MenuState::LevelSelect))
+                    .run_if(resource_changed::<LevelSelection>),
+            )
+            .add_systems(
+                OnExit(MenuState::LevelSelect),
+                despawn_all_at::<OnLevelSelectMenu>,
+            )
             .add_systems(OnExit(AppState::Menu), despawn_all_at::<MenuScreen>)
             .add_systems(
                 Update,
                 (menu_action, button_system::<Button>).run_if(in_state(AppState::Menu)),
-            );
+            )
+            .init_resource::<SettingsPage>()
+            .init_resource::<LevelSelection>();
     }
 }
 
-#[derive(Debug, Component)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Component)]
 enum MenuButtonAction {
     // - main -
     Start,
+    Continue,
     Settings,
+    LevelSelect,
     Exit,
     // - options -
-    ToggleSound,
+    CycleAudioProfile,
+    CycleVolume,
+    CycleSfxVolume,
+    CycleMusicVolume,
+    ToggleSpatialAudio,
     ToggleTimer,
-    ToggleInterludes,
+    CycleInterludeMode,
+    TogglePracticeMode,
+    ToggleUrgentHighlight,
+    ToggleFractionBar,
+    ToggleAutoSwitchWeapon,
+    ToggleAutoRetry,
+    ToggleShuffleNumbers,
+    ToggleGrade,
+    ToggleConfineCursor,
+    ToggleCalmStart,
+    ToggleSimpleHotbar,
+    ToggleDistance,
+    ToggleAttackPreview,
+    ToggleFireOnRightClick,
+    ToggleBonusRooms,
+    ToggleAssistDrift,
+    ToggleFocusMode,
+    ToggleManualMovement,
+    ToggleWeaponWheel,
+    ToggleMagnetCubes,
+    ToggleSlowShots,
+    CycleScreenShake,
+    CycleNumberPreset,
+    CycleNumberBase,
+    CycleNumberDisplay,
+    CycleDifficulty,
+    ToggleFractionColors,
+    /// go to the next page of settings
+    NextSettingsPage,
+    // - level select -
+    NextStage,
+    NextDecisions,
+    /// start the game at the selected level
+    Warp,
     /// return to main menu
     BackToMainMenu,
 }
@@ -132,13 +195,8 @@ fn menu_setup(
         });
     });
 
-    // Camera
-    cmd.spawn((
-        MenuScreen,
-        CameraMarker,
-        IsDefaultUiCamera,
-        Camera2dBundle::default(),
-    ));
+    // Camera (not the main camera, which belongs to the live game)
+    cmd.spawn((MenuScreen, IsDefaultUiCamera, Camera2dBundle::default()));
 
     next_state.set(MenuState::Main);
 }
@@ -153,6 +211,7 @@ pub fn main_menu_setup(
     sizes: Res<Sizes>,
     time: Res<LiveTime>,
     cheats: Res<Cheats>,
+    persistent_data: Res<PersistentData>,
 ) {
     // division for main buttons
     cmd.spawn((
@@ -179,6 +238,16 @@ pub fn main_menu_setup(
         let font = &default_font.0;
         // button to start the game
         spawn_button(cmd, &sizes, font.clone(), "Start", MenuButtonAction::Start);
+        // pick up the run given up on before
+        if SaveState::load(&persistent_data).is_some() {
+            spawn_button(
+                cmd,
+                &sizes,
+                font.clone(),
+                "Continue",
+                MenuButtonAction::Continue,
+            );
+        }
         // open options
         spawn_button(
             cmd,
@@ -187,6 +256,16 @@ pub fn main_menu_setup(
             "Settings",
             MenuButtonAction::Settings,
         );
+        // jump to any level (only for development)
+        if cfg!(feature = "dev_mode") {
+            spawn_button(
+                cmd,
+                &sizes,
+                font.clone(),
+                "Level Select",
+                MenuButtonAction::LevelSelect,
+            );
+        }
         // button to exit the game
         spawn_button(cmd, &sizes, font.clone(), "Exit", MenuButtonAction::Exit);
 
@@ -210,6 +289,77 @@ pub fn main_menu_setup(
             ..default()
         });
 
+        // the endings discovered so far,
+        // with the best times to reach them (fastest first)
+        let endings_found = Ending::discovered(&persistent_data).len();
+        if endings_found > 0 {
+            let mut text = format!("Endings found: {}/{}", endings_found, Ending::ALL.len());
+            let mut best_times: Vec<_> = Ending::ALL
+                .into_iter()
+                .filter_map(|ending| Some((ending, ending.best_time(&persistent_data)?)))
+                .collect();
+            best_times.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+            for (ending, time) in best_times {
+                text.push_str(&format!("\n{}: {}", ending.name(), format_time(time)));
+            }
+            cmd.spawn(TextBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(4.),
+                    top: Val::Px(2.),
+                    ..default()
+                },
+                text: Text::from_section(
+                    text,
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: sizes.interlude_font_size,
+                        color: Color::WHITE,
+                    },
+                ),
+                ..default()
+            });
+        }
+
+        // the achievements unlocked so far
+        let unlocked = Achievement::unlocked(&persistent_data);
+        if !unlocked.is_empty() {
+            let mut text = format!(
+                "Achievements: {}/{}",
+                unlocked.len(),
+                Achievement::ALL.len()
+            );
+            for achievement in Achievement::ALL {
+                let mark = if unlocked.contains(&achievement) {
+                    "[x]"
+                } else {
+                    "[ ]"
+                };
+                text.push_str(&format!(
+                    "\n{mark} {}: {}",
+                    achievement.name(),
+                    achievement.description()
+                ));
+            }
+            cmd.spawn(TextBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(16.),
+                    top: Val::Px(2.),
+                    ..default()
+                },
+                text: Text::from_section(
+                    text,
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: sizes.interlude_font_size,
+                        color: Color::WHITE,
+                    },
+                ),
+                ..default()
+            });
+        }
+
         // the game time of the last session
         if time.elapsed_seconds() > 0. {
             let with_cheats = if cheats.used_cheats {
@@ -242,14 +392,198 @@ pub fn main_menu_setup(
 #[derive(Debug, Component)]
 pub struct OnSettingsMenu;
 
-/// system to spawn the main menu UI
-pub fn settings_menu_setup(
+/// Resource for the page of the settings screen currently shown
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Resource)]
+enum SettingsPage {
+    #[default]
+    Game,
+    Display,
+    Learning,
+    Comfort,
+}
+
+impl SettingsPage {
+    fn next(self) -> Self {
+        match self {
+            SettingsPage::Game => SettingsPage::Display,
+            SettingsPage::Display => SettingsPage::Learning,
+            SettingsPage::Learning => SettingsPage::Comfort,
+            SettingsPage::Comfort => SettingsPage::Game,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            SettingsPage::Game => "Game",
+            SettingsPage::Display => "Display",
+            SettingsPage::Learning => "Learning",
+            SettingsPage::Comfort => "Comfort",
+        }
+    }
+
+    /// the settings available in this page
+    fn actions(self) -> &'static [MenuButtonAction] {
+        match self {
+            SettingsPage::Game => &[
+                MenuButtonAction::CycleAudioProfile,
+                MenuButtonAction::CycleVolume,
+                MenuButtonAction::CycleSfxVolume,
+                MenuButtonAction::CycleMusicVolume,
+                MenuButtonAction::ToggleSpatialAudio,
+                MenuButtonAction::CycleDifficulty,
+                MenuButtonAction::CycleInterludeMode,
+                MenuButtonAction::TogglePracticeMode,
+                MenuButtonAction::ToggleAutoSwitchWeapon,
+                MenuButtonAction::ToggleAutoRetry,
+                MenuButtonAction::ToggleBonusRooms,
+            ],
+            SettingsPage::Display => &[
+                MenuButtonAction::ToggleTimer,
+                MenuButtonAction::ToggleDistance,
+                MenuButtonAction::ToggleUrgentHighlight,
+                MenuButtonAction::ToggleFractionBar,
+                MenuButtonAction::ToggleConfineCursor,
+                MenuButtonAction::ToggleSimpleHotbar,
+                MenuButtonAction::ToggleFocusMode,
+            ],
+            SettingsPage::Learning => &[
+                MenuButtonAction::CycleNumberPreset,
+                MenuButtonAction::CycleNumberBase,
+                MenuButtonAction::CycleNumberDisplay,
+                MenuButtonAction::ToggleShuffleNumbers,
+                MenuButtonAction::ToggleGrade,
+                MenuButtonAction::ToggleAttackPreview,
+                MenuButtonAction::ToggleFractionColors,
+            ],
+            SettingsPage::Comfort => &[
+                MenuButtonAction::ToggleCalmStart,
+                MenuButtonAction::ToggleFireOnRightClick,
+                MenuButtonAction::ToggleAssistDrift,
+                MenuButtonAction::ToggleManualMovement,
+                MenuButtonAction::ToggleWeaponWheel,
+                MenuButtonAction::ToggleMagnetCubes,
+                MenuButtonAction::ToggleSlowShots,
+                MenuButtonAction::CycleScreenShake,
+            ],
+        }
+    }
+}
+
+/// The text of a settings button, which also shows its current value.
+///
+/// Returns `None` if the action is not a setting.
+fn setting_label(
+    action: MenuButtonAction,
+    settings: &GameSettings,
+    audio_handles: &AudioHandles,
+    educator_config: &EducatorConfig,
+) -> Option<String> {
+    fn on_off(name: &str, value: bool) -> String {
+        if value {
+            format!("{name}: ON")
+        } else {
+            format!("{name}: OFF")
+        }
+    }
+
+    let label = match action {
+        MenuButtonAction::CycleAudioProfile => {
+            format!("Sound: {}", audio_handles.profile.name())
+        }
+        MenuButtonAction::CycleVolume => {
+            format!("Volume: {:.0}%", audio_handles.volume * 100.)
+        }
+        MenuButtonAction::CycleSfxVolume => {
+            format!("Effects Volume: {:.0}%", audio_handles.sfx_volume * 100.)
+        }
+        MenuButtonAction::CycleMusicVolume => {
+            format!("Music Volume: {:.0}%", audio_handles.music_volume * 100.)
+        }
+        MenuButtonAction::ToggleSpatialAudio => on_off("Positional Sound", audio_handles.spatial),
+        MenuButtonAction::ToggleTimer => on_off("Show Timer", settings.show_timer),
+        MenuButtonAction::CycleInterludeMode => {
+            format!("Interludes: {}", settings.interlude_mode.name())
+        }
+        MenuButtonAction::TogglePracticeMode => on_off("Practice Mode", settings.practice_mode),
+        MenuButtonAction::ToggleUrgentHighlight => {
+            on_off("Highlight Nearest", settings.highlight_urgent)
+        }
+        MenuButtonAction::ToggleFractionBar => on_off("Fraction Bars", settings.fraction_bar),
+        MenuButtonAction::ToggleAutoSwitchWeapon => {
+            on_off("Auto-Switch Weapon", settings.auto_switch_weapon)
+        }
+        MenuButtonAction::ToggleAutoRetry => on_off("Auto-Retry", settings.auto_retry),
+        MenuButtonAction::ToggleShuffleNumbers => {
+            on_off("Shuffle Numbers", settings.shuffle_numbers)
+        }
+        MenuButtonAction::ToggleGrade => on_off("Show Grade", settings.show_grade),
+        MenuButtonAction::ToggleConfineCursor => on_off("Confine Cursor", settings.confine_cursor),
+        MenuButtonAction::ToggleCalmStart => on_off("Calm Start", settings.calm_start),
+        MenuButtonAction::ToggleSimpleHotbar => on_off("Simple Hotbar", settings.simple_hotbar),
+        MenuButtonAction::ToggleDistance => on_off("Show Distance", settings.show_distance),
+        MenuButtonAction::ToggleAttackPreview => on_off("Attack Preview", settings.preview_attack),
+        MenuButtonAction::ToggleBonusRooms => on_off("Bonus Rooms", settings.bonus_rooms),
+        MenuButtonAction::ToggleAssistDrift => on_off("Assist Drift", settings.assist_drift),
+        MenuButtonAction::ToggleFocusMode => on_off("Focus Mode", settings.focus_mode),
+        MenuButtonAction::ToggleManualMovement => {
+            on_off("Manual Movement", settings.manual_movement)
+        }
+        MenuButtonAction::ToggleWeaponWheel => on_off("Weapon Wheel (Tab)", settings.weapon_wheel),
+        MenuButtonAction::ToggleMagnetCubes => on_off("Weapon Cube Magnet", settings.magnet_cubes),
+        MenuButtonAction::ToggleSlowShots => on_off("Slow Shots Near Targets", settings.slow_shots),
+        MenuButtonAction::CycleScreenShake => {
+            format!("Screen Shake: {:.0}%", settings.screen_shake * 100.)
+        }
+        MenuButtonAction::ToggleFireOnRightClick => {
+            on_off("Fire With Right Click", settings.fire_on_right_click)
+        }
+        MenuButtonAction::CycleNumberPreset => {
+            format!("Numbers: {}", educator_config.preset.name())
+        }
+        MenuButtonAction::ToggleFractionColors => {
+            on_off("Color Equivalent Numbers", settings.fraction_colors)
+        }
+        MenuButtonAction::CycleDifficulty => {
+            format!("Difficulty: {}", settings.difficulty.name())
+        }
+        MenuButtonAction::CycleNumberBase => {
+            format!("Number Base: {}", settings.number_base.name())
+        }
+        MenuButtonAction::CycleNumberDisplay => {
+            format!("Fractions: {}", settings.number_display.name())
+        }
+        MenuButtonAction::Start
+        | MenuButtonAction::Settings
+        | MenuButtonAction::Continue
+        | MenuButtonAction::LevelSelect
+        | MenuButtonAction::NextStage
+        | MenuButtonAction::NextDecisions
+        | MenuButtonAction::Warp
+        | MenuButtonAction::Exit
+        | MenuButtonAction::NextSettingsPage
+        | MenuButtonAction::BackToMainMenu => return None,
+    };
+    Some(label)
+}
+
+/// system to spawn the settings menu UI.
+///
+/// Also runs when the settings page changes,
+/// replacing the previous page.
+fn settings_menu_setup(
     mut cmd: Commands,
     default_font: Res<DefaultFont>,
     sizes: Res<Sizes>,
     game_settings: Res<GameSettings>,
     audio_handles: Res<AudioHandles>,
+    educator_config: Res<EducatorConfig>,
+    settings_page: Res<SettingsPage>,
+    previous_q: Query<Entity, With<OnSettingsMenu>>,
 ) {
+    for entity in &previous_q {
+        cmd.entity(entity).despawn_recursive();
+    }
+
     let font = &default_font.0;
     // division for main buttons
     cmd.spawn((
@@ -272,44 +606,105 @@ pub fn settings_menu_setup(
         },
     ))
     .with_children(|cmd| {
-        let timer_msg = if game_settings.show_timer {
-            "Show Timer: ON"
-        } else {
-            "Show Timer: OFF"
-        };
+        for action in settings_page.actions() {
+            let label = setting_label(*action, &game_settings, &audio_handles, &educator_config)
+                .unwrap_or_default();
+            spawn_button(cmd, &sizes, font.clone(), label, *action);
+        }
+
         spawn_button(
             cmd,
             &sizes,
             font.clone(),
-            timer_msg,
-            MenuButtonAction::ToggleTimer,
+            format!("{} >", settings_page.next().name()),
+            MenuButtonAction::NextSettingsPage,
         );
-
-        let interludes_msg = if game_settings.skip_interludes {
-            "Skip Interludes: ON"
-        } else {
-            "Skip Interludes: OFF"
-        };
         spawn_button(
             cmd,
             &sizes,
             font.clone(),
-            interludes_msg,
-            MenuButtonAction::ToggleInterludes,
+            "Back",
+            MenuButtonAction::BackToMainMenu,
         );
+    });
+}
 
-        let sound_msg = if audio_handles.enabled {
-            "Sound: ON"
-        } else {
-            "Sound: OFF"
-        };
+#[derive(Debug, Component)]
+pub struct OnLevelSelectMenu;
+
+/// Resource for the level chosen in the level select screen
+#[derive(Debug, Default, Resource)]
+struct LevelSelection(LevelId);
+
+impl LevelSelection {
+    /// choose the next stage,
+    /// going back to the first one after the last
+    fn next_stage(&mut self) {
+        self.0 = LevelId::new(self.0.stage + 1, self.0.decisions).unwrap_or_default();
+    }
+
+    /// choose the next combination of decisions in the current stage
+    fn next_decisions(&mut self) {
+        let LevelId { stage, decisions } = self.0;
+        self.0 = LevelId::new(stage, decisions.wrapping_add(1)).unwrap_or(self.0);
+    }
+}
+
+fn level_select_setup(
+    mut cmd: Commands,
+    default_font: Res<DefaultFont>,
+    sizes: Res<Sizes>,
+    level_selection: Res<LevelSelection>,
+    previous_q: Query<Entity, With<OnLevelSelectMenu>>,
+) {
+    for entity in &previous_q {
+        cmd.entity(entity).despawn_recursive();
+    }
+
+    let font = &default_font.0;
+    cmd.spawn((
+        OnLevelSelectMenu,
+        NodeBundle {
+            style: Style {
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                width: Val::Percent(100.),
+                margin: UiRect {
+                    top: Val::Auto,
+                    bottom: Val::Auto,
+                    ..default()
+                },
+                ..default()
+            },
+            ..default()
+        },
+    ))
+    .with_children(|cmd| {
+        cmd.spawn(TextBundle::from_section(
+            format!("Level {}", level_selection.0),
+            TextStyle {
+                font: font.clone(),
+                font_size: sizes.button_font_size,
+                color: Color::WHITE,
+            },
+        ));
         spawn_button(
             cmd,
             &sizes,
             font.clone(),
-            sound_msg,
-            MenuButtonAction::ToggleSound,
+            "Next Stage",
+            MenuButtonAction::NextStage,
         );
+        spawn_button(
+            cmd,
+            &sizes,
+            font.clone(),
+            "Next Path",
+            MenuButtonAction::NextDecisions,
+        );
+        spawn_button(cmd, &sizes, font.clone(), "Warp", MenuButtonAction::Warp);
         spawn_button(
             cmd,
             &sizes,
@@ -330,9 +725,13 @@ fn menu_action(
     mut menu_state: ResMut<NextState<MenuState>>,
     mut game_state: ResMut<NextState<AppState>>,
     mut settings: ResMut<GameSettings>,
+    mut settings_page: ResMut<SettingsPage>,
     mut cheats: ResMut<Cheats>,
     mut audio_handles: ResMut<AudioHandles>,
+    mut educator_config: ResMut<EducatorConfig>,
     mut button_text_q: Query<&mut Text>,
+    (mut persistent_data, mut resume_run): (ResMut<PersistentData>, ResMut<ResumeRun>),
+    (mut level_selection, mut level_warp): (ResMut<LevelSelection>, ResMut<LevelWarp>),
 ) {
     for (interaction, menu_button_action, children) in &mut interaction_query {
         if *interaction == Interaction::Pressed {
@@ -348,52 +747,216 @@ fn menu_action(
                     game_state.set(AppState::Live);
                     menu_state.set(MenuState::Disabled);
                 }
+                MenuButtonAction::Continue => {
+                    let Some(state) = SaveState::load(&persistent_data) else {
+                        continue;
+                    };
+                    // the run is only continued once
+                    SaveState::clear(&mut persistent_data);
+                    resume_run.0 = Some(state);
+                    cheats.used_cheats = cheats.invulnerability;
+                    game_state.set(AppState::Live);
+                    menu_state.set(MenuState::Disabled);
+                }
                 MenuButtonAction::Settings => menu_state.set(MenuState::Settings),
+                MenuButtonAction::NextSettingsPage => *settings_page = settings_page.next(),
+                MenuButtonAction::LevelSelect => menu_state.set(MenuState::LevelSelect),
+                MenuButtonAction::NextStage => level_selection.next_stage(),
+                MenuButtonAction::NextDecisions => level_selection.next_decisions(),
+                MenuButtonAction::Warp => {
+                    info!("Warping to level {}", level_selection.0);
+                    level_warp.0 = Some(level_selection.0);
+                    // warping does not count as a fair run
+                    cheats.used_cheats = true;
+                    game_state.set(AppState::Live);
+                    menu_state.set(MenuState::Disabled);
+                }
                 MenuButtonAction::BackToMainMenu => menu_state.set(MenuState::Main),
 
-                MenuButtonAction::ToggleSound => {
-                    audio_handles.enabled = !audio_handles.enabled;
-                    let new_text = if audio_handles.enabled {
-                        "Sound: ON"
-                    } else {
-                        "Sound: OFF"
-                    };
-                    for child in children {
-                        if let Ok(mut text) = button_text_q.get_mut(*child) {
-                            text.sections[0].value = new_text.to_string();
-                        }
-                    }
+                MenuButtonAction::CycleAudioProfile => {
+                    audio_handles.profile = audio_handles.profile.next();
+                }
+                MenuButtonAction::CycleVolume => {
+                    audio_handles.volume = AudioHandles::next_volume(audio_handles.volume);
+                }
+                MenuButtonAction::CycleSfxVolume => {
+                    audio_handles.sfx_volume = AudioHandles::next_volume(audio_handles.sfx_volume);
+                }
+                MenuButtonAction::ToggleSpatialAudio => {
+                    audio_handles.spatial = !audio_handles.spatial;
+                }
+                MenuButtonAction::CycleMusicVolume => {
+                    audio_handles.music_volume =
+                        AudioHandles::next_volume(audio_handles.music_volume);
                 }
                 MenuButtonAction::ToggleTimer => {
                     settings.show_timer = !settings.show_timer;
-                    let new_text = if settings.show_timer {
-                        "Show Timer: ON"
-                    } else {
-                        "Show Timer: OFF"
-                    };
-                    for child in children {
-                        if let Ok(mut text) = button_text_q.get_mut(*child) {
-                            text.sections[0].value = new_text.to_string();
-                        }
-                    }
                 }
+                MenuButtonAction::CycleInterludeMode => {
+                    settings.interlude_mode = settings.interlude_mode.next();
+                }
+                MenuButtonAction::TogglePracticeMode => {
+                    settings.practice_mode = !settings.practice_mode;
+                }
+                MenuButtonAction::ToggleUrgentHighlight => {
+                    settings.highlight_urgent = !settings.highlight_urgent;
+                }
+                MenuButtonAction::ToggleFractionBar => {
+                    settings.fraction_bar = !settings.fraction_bar;
+                }
+                MenuButtonAction::ToggleAutoSwitchWeapon => {
+                    settings.auto_switch_weapon = !settings.auto_switch_weapon;
+                }
+                MenuButtonAction::ToggleAutoRetry => {
+                    settings.auto_retry = !settings.auto_retry;
+                }
+                MenuButtonAction::ToggleShuffleNumbers => {
+                    settings.shuffle_numbers = !settings.shuffle_numbers;
+                }
+                MenuButtonAction::ToggleGrade => {
+                    settings.show_grade = !settings.show_grade;
+                }
+                MenuButtonAction::ToggleConfineCursor => {
+                    settings.confine_cursor = !settings.confine_cursor;
+                }
+                MenuButtonAction::ToggleCalmStart => {
+                    settings.calm_start = !settings.calm_start;
+                }
+                MenuButtonAction::ToggleSimpleHotbar => {
+                    settings.simple_hotbar = !settings.simple_hotbar;
+                }
+                MenuButtonAction::ToggleDistance => {
+                    settings.show_distance = !settings.show_distance;
+                }
+                MenuButtonAction::ToggleAttackPreview => {
+                    settings.preview_attack = !settings.preview_attack;
+                }
+                MenuButtonAction::ToggleBonusRooms => {
+                    settings.bonus_rooms = !settings.bonus_rooms;
+                }
+                MenuButtonAction::ToggleAssistDrift => {
+                    settings.assist_drift = !settings.assist_drift;
+                }
+                MenuButtonAction::ToggleFocusMode => {
+                    settings.focus_mode = !settings.focus_mode;
+                }
+                MenuButtonAction::ToggleManualMovement => {
+                    settings.manual_movement = !settings.manual_movement;
+                }
+                MenuButtonAction::ToggleWeaponWheel => {
+                    settings.weapon_wheel = !settings.weapon_wheel;
+                }
+                MenuButtonAction::ToggleMagnetCubes => {
+                    settings.magnet_cubes = !settings.magnet_cubes;
+                }
+                MenuButtonAction::ToggleSlowShots => {
+                    settings.slow_shots = !settings.slow_shots;
+                }
+                MenuButtonAction::CycleScreenShake => {
+                    settings.screen_shake = CameraShake::next_magnitude(settings.screen_shake);
+                }
+                MenuButtonAction::ToggleFireOnRightClick => {
+                    settings.fire_on_right_click = !settings.fire_on_right_click;
+                }
+                MenuButtonAction::CycleNumberPreset => {
+                    educator_config.preset = educator_config.preset.next();
+                }
+                MenuButtonAction::ToggleFractionColors => {
+                    settings.fraction_colors = !settings.fraction_colors;
+                }
+                MenuButtonAction::CycleDifficulty => {
+                    settings.difficulty = settings.difficulty.next();
+                }
+                MenuButtonAction::CycleNumberBase => {
+                    settings.number_base = settings.number_base.next();
+                }
+                MenuButtonAction::CycleNumberDisplay => {
+                    settings.number_display = settings.number_display.next();
+                }
+            }
 
-                MenuButtonAction::ToggleInterludes => {
-                    settings.skip_interludes = !settings.skip_interludes;
-                    let new_text = if settings.skip_interludes {
-                        "Skip Interludes: ON"
-                    } else {
-                        "Skip Interludes: OFF"
-                    };
-                    for child in children {
-                        if let Ok(mut text) = button_text_q.get_mut(*child) {
-                            text.sections[0].value = new_text.to_string();
-                        }
+            // update the button text of settings
+            if let Some(new_text) = setting_label(
+                *menu_button_action,
+                &settings,
+                &audio_handles,
+                &educator_config,
+            ) {
+                for child in children {
+                    if let Ok(mut text) = button_text_q.get_mut(*child) {
+                        text.sections[0].value = new_text.clone();
                     }
                 }
             }
+
             // play sound
             audio_handles.play_zipclick(&mut cmd);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::state::app::StatesPlugin;
+
+    use crate::CameraMarker;
+
+    use super::*;
+
+    #[test]
+    fn menu_has_one_camera_which_is_not_the_main_camera() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default(), StatesPlugin))
+            .init_asset::<Image>()
+            .init_asset::<Font>()
+            .init_state::<AppState>()
+            .add_sub_state::<MenuState>()
+            .init_resource::<DefaultFont>()
+            .init_resource::<Sizes>()
+            .add_systems(OnEnter(AppState::Menu), menu_setup)
+            .add_systems(OnExit(AppState::Menu), despawn_all_at::<MenuScreen>);
+        app.update();
+
+        let world = app.world_mut();
+        assert_eq!(world.query::<&Camera>().iter(world).count(), 1);
+        // the live camera would be a second main camera otherwise
+        let main_cameras = world
+            .query_filtered::<(), With<CameraMarker>>()
+            .iter(world)
+            .count();
+        assert_eq!(main_cameras, 0);
+
+        // and it is gone once the game starts
+        world
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Live);
+        app.update();
+        let world = app.world_mut();
+        assert_eq!(world.query::<&Camera>().iter(world).count(), 0);
+    }
+
+    #[test]
+    fn level_select_picks_stage_and_decisions() {
+        let mut selection = LevelSelection::default();
+        selection.next_stage();
+        selection.next_stage();
+        selection.next_stage();
+        assert_eq!(selection.0, LevelId::new(3, 0).unwrap());
+
+        selection.next_decisions();
+        selection.next_decisions();
+        assert_eq!(selection.0, LevelId::new(3, 2).unwrap());
+
+        // the stage stays within the levels which exist
+        let mut stages = vec![selection.0.stage];
+        while selection.0.stage != 0 {
+            selection.next_stage();
+            assert_eq!(
+                LevelId::new(selection.0.stage, selection.0.decisions),
+                Some(selection.0)
+            );
+            stages.push(selection.0.stage);
+        }
+        assert_eq!(stages, [3, 4, 5, 0]);
+    }
+}
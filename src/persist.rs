@@ -0,0 +1,147 @@
+//! Module for data which persists across game sessions.
+//!
+//! Data is kept as simple `key=value` lines
+//! in a file in the user's data directory.
+//! Where there is no file system (such as on the web),
+//! the data only lasts for the session.
+use std::{collections::BTreeMap, path::PathBuf};
+
+use bevy::prelude::*;
+
+/// Global resource for all persisted data
+#[derive(Debug, Default, Resource)]
+pub struct PersistentData {
+    values: BTreeMap<String, String>,
+}
+
+impl PersistentData {
+    /// the name of the file containing the data
+    const FILE_NAME: &'static str = "save.txt";
+    /// the key for whether the first level was ever finished
+    const FIRST_LEVEL_DONE_KEY: &'static str = "first_level_done";
+
+    /// Load the persisted data,
+    /// starting anew if there is no data to load.
+    pub fn load() -> Self {
+        let Some(path) = data_file_path() else {
+            return Self::default();
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => Self::parse(&contents),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                warn!("Could not read saved data from {}: {e}", path.display());
+                Self::default()
+            }
+        }
+    }
+
+    fn parse(contents: &str) -> Self {
+        let values = contents
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect();
+        PersistentData { values }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Set a value, without saving it yet
+    pub fn set(&mut self, key: impl Into<String>, value: impl ToString) {
+        self.values.insert(key.into(), value.to_string());
+    }
+
+    /// Remove a value, without saving yet
+    pub fn remove(&mut self, key: &str) {
+        self.values.remove(key);
+    }
+
+    /// Whether the player has never finished the first level
+    pub fn is_first_run(&self) -> bool {
+        self.get(Self::FIRST_LEVEL_DONE_KEY).is_none()
+    }
+
+    /// Record that the player has finished the first level
+    pub fn mark_first_level_done(&mut self) {
+        if self.is_first_run() {
+            self.set(Self::FIRST_LEVEL_DONE_KEY, 1);
+            self.save();
+        }
+    }
+
+    /// Write all data to the file system (if possible)
+    pub fn save(&self) {
+        let Some(path) = data_file_path() else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                warn!("Could not create directory {}: {e}", dir.display());
+                return;
+            }
+        }
+        if let Err(e) = std::fs::write(&path, self.to_contents()) {
+            warn!("Could not save data to {}: {e}", path.display());
+        }
+    }
+
+    /// The contents of the data file, as read back by `parse`
+    fn to_contents(&self) -> String {
+        self.values
+            .iter()
+            .map(|(key, value)| format!("{key}={value}\n"))
+            .collect()
+    }
+}
+
+/// The path to the file with the persisted data,
+/// if the platform has one
+#[cfg(not(any(target_arch = "wasm32", test)))]
+fn data_file_path() -> Option<PathBuf> {
+    use std::env::var_os;
+
+    let base = if cfg!(target_os = "windows") {
+        PathBuf::from(var_os("APPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(var_os("HOME")?).join("Library/Application Support")
+    } else if let Some(data_home) = var_os("XDG_DATA_HOME") {
+        PathBuf::from(data_home)
+    } else {
+        PathBuf::from(var_os("HOME")?).join(".local/share")
+    };
+    Some(base.join("the-fortress").join(PersistentData::FILE_NAME))
+}
+
+// tests never touch the player's data
+#[cfg(any(target_arch = "wasm32", test))]
+fn data_file_path() -> Option<PathBuf> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_survives_a_round_trip() {
+        let mut data = PersistentData::default();
+        data.set("best_time.circle", 123.5);
+        data.set("endings", "circle,mirror");
+        data.mark_first_level_done();
+
+        let loaded = PersistentData::parse(&data.to_contents());
+        assert_eq!(loaded.values, data.values);
+        assert_eq!(loaded.get("best_time.circle"), Some("123.5"));
+        assert!(!loaded.is_first_run());
+    }
+
+    #[test]
+    fn corrupt_lines_are_skipped() {
+        let data = PersistentData::parse("\u{0}garbage\n\nbest_time.circle=99\n=\n");
+        assert_eq!(data.get("best_time.circle"), Some("99"));
+        assert!(data.is_first_run());
+    }
+}
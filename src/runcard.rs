@@ -0,0 +1,236 @@
+//! The run card,
+//! a shareable picture summarizing the last run which reached an ending.
+//!
+//! The card is laid out with the regular UI on top of everything else,
+//! captured with a screenshot of the window,
+//! and taken down once the picture is saved.
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    prelude::*, render::view::screenshot::ScreenshotManager, utils::SystemTime,
+    window::PrimaryWindow,
+};
+
+use crate::{
+    live::{format_time, Achievement, RunSummary},
+    persist::PersistentData,
+    ui::{Sizes, Toasts},
+};
+
+/// the file name for a run card saved at the given time
+/// (in seconds since the Unix epoch),
+/// so that saving another card does not replace the previous one
+fn run_card_file_name(timestamp: u64) -> String {
+    format!("run_card_{timestamp}.png")
+}
+
+/// Resource for the outcomes of saving run cards,
+/// written by the screenshot callback
+/// and shown as toasts by [`capture_run_card`]
+#[derive(Debug, Default, Resource)]
+pub struct RunCardOutcomes(Arc<Mutex<Vec<String>>>);
+
+impl RunCardOutcomes {
+    fn report(&self, message: String) {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(message);
+    }
+
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+/// Component for the root UI node of the run card,
+/// counting the frames since it was spawned
+#[derive(Debug, Default, Component)]
+pub struct RunCard {
+    frames: u32,
+}
+
+/// how many frames to wait for the card to be laid out
+/// before capturing it
+const LAYOUT_FRAMES: u32 = 2;
+
+/// spawn the run card for the given run,
+/// to be captured by [`capture_run_card`]
+pub fn spawn_run_card(
+    cmd: &mut Commands,
+    summary: &RunSummary,
+    font: Handle<Font>,
+    sizes: &Sizes,
+    persistent_data: &PersistentData,
+) {
+    let mut lines = vec![
+        format!("Ending: {}", summary.ending.name()),
+        format!("Time: {}", format_time(summary.time)),
+        format!("Accuracy: {:.0}%", summary.accuracy * 100.),
+    ];
+    let unlocked = Achievement::unlocked(persistent_data);
+    lines.push(format!(
+        "Achievements: {}/{}",
+        unlocked.len(),
+        Achievement::ALL.len()
+    ));
+    for achievement in unlocked {
+        lines.push(format!("  {}", achievement.name()));
+    }
+    if summary.used_cheats {
+        lines.push("(with cheats)".to_string());
+    }
+
+    cmd.spawn((
+        RunCard::default(),
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(16.),
+                ..default()
+            },
+            background_color: BackgroundColor(Color::srgb(0.08, 0.06, 0.12)),
+            z_index: ZIndex::Global(50),
+            ..default()
+        },
+    ))
+    .with_children(|cmd| {
+        cmd.spawn(TextBundle::from_section(
+            "The Fortress",
+            TextStyle {
+                font: font.clone(),
+                font_size: sizes.title_font_size,
+                color: Color::srgb(1., 0.85, 0.3),
+            },
+        ));
+        cmd.spawn(
+            TextBundle::from_section(
+                lines.join("\n"),
+                TextStyle {
+                    font,
+                    font_size: sizes.interlude_font_size,
+                    color: Color::WHITE,
+                },
+            )
+            .with_text_justify(JustifyText::Center),
+        );
+    });
+}
+
+/// system that captures the run card once it is laid out,
+/// saving it as a PNG image,
+/// then takes it down.
+///
+/// The outcome of saving is only known later,
+/// once the screenshot callback is done,
+/// so it is shown here as a toast whenever it arrives.
+pub fn capture_run_card(
+    mut cmd: Commands,
+    mut card_q: Query<(Entity, &mut RunCard)>,
+    window_q: Query<Entity, With<PrimaryWindow>>,
+    mut screenshot_manager: ResMut<ScreenshotManager>,
+    outcomes: Res<RunCardOutcomes>,
+    mut toasts: ResMut<Toasts>,
+) {
+    for message in outcomes.take() {
+        toasts.push_toast(message, 3.);
+    }
+
+    let Ok((entity, mut card)) = card_q.get_single_mut() else {
+        return;
+    };
+    card.frames += 1;
+    if card.frames < LAYOUT_FRAMES {
+        return;
+    }
+    if card.frames > LAYOUT_FRAMES {
+        // the screenshot was taken in the previous frame
+        cmd.entity(entity).despawn_recursive();
+        return;
+    }
+
+    let Ok(window) = window_q.get_single() else {
+        cmd.entity(entity).despawn_recursive();
+        return;
+    };
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default();
+    let file_name = run_card_file_name(timestamp);
+    let outcomes = RunCardOutcomes(outcomes.0.clone());
+    let requested = screenshot_manager.take_screenshot(window, move |image| {
+        outcomes.report(save_run_card(image, &file_name));
+    });
+    if let Err(e) = requested {
+        warn!("Could not save run card: {e}");
+        cmd.entity(entity).despawn_recursive();
+    }
+}
+
+/// save the captured run card to a file,
+/// returning the message to show to the player
+#[cfg(not(target_arch = "wasm32"))]
+fn save_run_card(image: Image, file_name: &str) -> String {
+    let saved = image
+        .try_into_dynamic()
+        .map_err(|e| e.to_string())
+        // drop the alpha channel, which does not hold opacity with HDR
+        .and_then(|image| image.to_rgb8().save(file_name).map_err(|e| e.to_string()));
+    match saved {
+        Ok(()) => {
+            info!("Run card saved to {file_name}");
+            format!("Run card saved to {file_name}")
+        }
+        Err(e) => {
+            warn!("Could not save run card: {e}");
+            "Could not save the run card".to_string()
+        }
+    }
+}
+
+/// there is no file system to save the run card to
+#[cfg(target_arch = "wasm32")]
+fn save_run_card(_image: Image, _file_name: &str) -> String {
+    "Run cards cannot be saved here".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn run_card_file_names_are_timestamped() {
+        assert_eq!(run_card_file_name(1_700_000_000), "run_card_1700000000.png");
+        assert_ne!(run_card_file_name(1), run_card_file_name(2));
+    }
+
+    #[test]
+    fn save_outcomes_are_shown_once_they_arrive() {
+        let mut world = World::new();
+        world.init_resource::<ScreenshotManager>();
+        world.init_resource::<RunCardOutcomes>();
+        world.init_resource::<Toasts>();
+
+        // nothing to show before the callback reports back
+        world.run_system_once(capture_run_card);
+        assert!(!format!("{:?}", world.resource::<Toasts>()).contains("run_card_"));
+
+        // as done by the screenshot callback, on another thread
+        let outcomes = RunCardOutcomes(world.resource::<RunCardOutcomes>().0.clone());
+        std::thread::spawn(move || outcomes.report("Run card saved to run_card_7.png".into()))
+            .join()
+            .unwrap();
+
+        world.run_system_once(capture_run_card);
+        assert!(format!("{:?}", world.resource::<Toasts>()).contains("run_card_7.png"));
+        assert!(world.resource::<RunCardOutcomes>().take().is_empty());
+    }
+}
@@ -7,7 +7,10 @@ use bevy_mod_picking::{
     PickableBundle,
 };
 
-use crate::live::{callback_on_click, collision::CollidableBox, OnLive};
+use crate::{
+    live::{callback_on_click, collision::CollidableBox, OnLive},
+    GameSettings,
+};
 
 fn new_wall(
     meshes: &mut ResMut<Assets<Mesh>>,
@@ -31,9 +34,44 @@ fn new_wall(
         CollidableBox::new(Vec3::new(0.1, dim.x, dim.y)),
         PickableBundle::default(),
         On::<Pointer<Click>>::run(callback_on_click),
+        Scenery,
     )
 }
 
+/// Marker component for the static surfaces of the scene,
+/// which can be dimmed in focus mode
+#[derive(Debug, Component)]
+pub struct Scenery;
+
+/// system that dims the scenery while focus mode is on,
+/// so that targets, cubes, and projectiles stand out
+pub fn apply_focus_mode(
+    game_settings: Res<GameSettings>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    scenery_q: Query<&Handle<StandardMaterial>, With<Scenery>>,
+    added_scenery_q: Query<(), Added<Scenery>>,
+) {
+    if !game_settings.is_changed() && added_scenery_q.is_empty() {
+        return;
+    }
+    let base_color = if game_settings.focus_mode {
+        Color::srgb(0.3, 0.3, 0.3)
+    } else {
+        Color::WHITE
+    };
+    for handle in &scenery_q {
+        // avoid touching materials which are already right
+        if materials
+            .get(handle)
+            .is_some_and(|material| material.base_color != base_color)
+        {
+            if let Some(material) = materials.get_mut(handle) {
+                material.base_color = base_color;
+            }
+        }
+    }
+}
+
 /// Marker component identifying a corridor
 #[derive(Debug, Component)]
 pub struct Corridor;
@@ -80,6 +118,7 @@ pub fn spawn_corridor<'a>(
             CollidableBox::new(Vec3::new(dim.x, 0.25, dim.z)),
             PickableBundle::default(),
             On::<Pointer<Click>>::run(callback_on_click),
+            Scenery,
         ));
 
         // add ceiling
@@ -98,6 +137,7 @@ pub fn spawn_corridor<'a>(
             CollidableBox::new(Vec3::new(dim.x, 0.125, dim.z)),
             PickableBundle::default(),
             On::<Pointer<Click>>::run(callback_on_click),
+            Scenery,
         ));
 
         // add some walls around the floor
@@ -174,6 +214,7 @@ pub fn spawn_fork<'a>(
             CollidableBox::new(Vec3::new(dim.x * 2., 0.125, dim.z)),
             PickableBundle::default(),
             On::<Pointer<Click>>::run(callback_on_click),
+            Scenery,
         ));
 
         // add ceiling
@@ -192,6 +233,7 @@ pub fn spawn_fork<'a>(
             CollidableBox::new(Vec3::new(dim.x, 0.25, dim.z)),
             PickableBundle::default(),
             On::<Pointer<Click>>::run(callback_on_click),
+            Scenery,
         ));
 
         // add front wall
@@ -1,5 +1,7 @@
 //! Module for various common UI components
-use bevy::{ecs::system::EntityCommands, prelude::*};
+use bevy::{ecs::system::EntityCommands, prelude::*, ui::FocusPolicy};
+
+use crate::assets::DefaultFont;
 
 /// Resource for the sizes to use in most common UI components.
 #[derive(Debug, Resource)]
@@ -238,3 +240,217 @@ pub fn update_buttons_on_window_resize(
         }
     }
 }
+
+// toasts
+
+/// Resource for short messages waiting to be shown on screen.
+///
+/// Toasts are stacked at the bottom right corner
+/// and fade away on their own.
+#[derive(Debug, Default, Resource)]
+pub struct Toasts {
+    /// messages not shown yet, with their duration in seconds
+    pending: Vec<(String, f32)>,
+}
+
+impl Toasts {
+    /// Queue a message to be shown for the given time, in seconds
+    pub fn push_toast(&mut self, text: impl Into<String>, duration: f32) {
+        self.pending.push((text.into(), duration));
+    }
+}
+
+/// Component for a message currently on screen
+#[derive(Debug, Component)]
+pub struct Toast {
+    /// the time left until it disappears, in seconds
+    remaining: f32,
+}
+
+/// Marker for the node containing all toasts
+#[derive(Debug, Component)]
+pub struct ToastContainer;
+
+/// the most toasts on screen at once
+const MAX_TOASTS: usize = 4;
+
+/// how long a toast takes to fade away, in seconds
+const TOAST_FADE_TIME: f32 = 0.5;
+
+/// system that shows the toasts waiting in the queue
+pub fn show_toasts(
+    mut cmd: Commands,
+    mut toasts: ResMut<Toasts>,
+    default_font: Res<DefaultFont>,
+    container_q: Query<Entity, With<ToastContainer>>,
+    mut toast_q: Query<&mut Toast>,
+) {
+    if toasts.pending.is_empty() {
+        return;
+    }
+
+    let container = container_q.get_single().unwrap_or_else(|_| {
+        cmd.spawn((
+            ToastContainer,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(16.),
+                    bottom: Val::Px(16.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::FlexEnd,
+                    row_gap: Val::Px(6.),
+                    ..default()
+                },
+                focus_policy: FocusPolicy::Pass,
+                z_index: ZIndex::Global(13),
+                ..default()
+            },
+        ))
+        .id()
+    });
+
+    // make room for the new ones by fading out the oldest
+    // (entity order is not guaranteed,
+    // so the ones closest to expiring go first)
+    let pending = std::mem::take(&mut toasts.pending);
+    let mut on_screen: Vec<_> = toast_q
+        .iter_mut()
+        .filter(|t| t.remaining > TOAST_FADE_TIME)
+        .collect();
+    let excess = (on_screen.len() + pending.len()).saturating_sub(MAX_TOASTS);
+    on_screen.sort_by(|a, b| a.remaining.total_cmp(&b.remaining));
+    for toast in on_screen.iter_mut().take(excess) {
+        toast.remaining = TOAST_FADE_TIME;
+    }
+
+    cmd.entity(container).with_children(|cmd| {
+        for (text, duration) in pending {
+            cmd.spawn((
+                Toast {
+                    remaining: duration,
+                },
+                TextBundle {
+                    text: Text::from_section(
+                        text,
+                        TextStyle {
+                            font: default_font.0.clone(),
+                            font_size: 20.,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    style: Style {
+                        padding: UiRect::axes(Val::Px(10.), Val::Px(4.)),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(Color::BLACK.with_alpha(0.6)),
+                    focus_policy: FocusPolicy::Pass,
+                    ..default()
+                },
+            ));
+        }
+    });
+}
+
+/// system that fades away toasts and removes them once expired
+/// (uses real time, so that toasts also go away while paused)
+pub fn update_toasts(
+    mut cmd: Commands,
+    time: Res<Time<Real>>,
+    mut toast_q: Query<(Entity, &mut Toast, &mut Text, &mut BackgroundColor)>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, mut toast, mut text, mut background_color) in &mut toast_q {
+        toast.remaining -= delta;
+        if toast.remaining <= 0. {
+            cmd.entity(entity).despawn_recursive();
+            continue;
+        }
+        let alpha = (toast.remaining / TOAST_FADE_TIME).min(1.);
+        for section in &mut text.sections {
+            section.style.color.set_alpha(alpha);
+        }
+        background_color.0.set_alpha(alpha * 0.6);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    fn toast_world() -> World {
+        let mut world = World::new();
+        world.init_resource::<Toasts>();
+        world.insert_resource(DefaultFont(Handle::default()));
+        world.init_resource::<Time<Real>>();
+        world
+    }
+
+    fn pass_real_time(world: &mut World, seconds: f32) {
+        world
+            .resource_mut::<Time<Real>>()
+            .advance_by(Duration::from_secs_f32(seconds));
+        world.run_system_once(update_toasts);
+    }
+
+    fn toasts_remaining(world: &mut World) -> Vec<f32> {
+        world
+            .query::<&Toast>()
+            .iter(world)
+            .map(|toast| toast.remaining)
+            .collect()
+    }
+
+    #[test]
+    fn toasts_stack_in_one_corner_and_expire() {
+        let mut world = toast_world();
+        world.resource_mut::<Toasts>().push_toast("first", 1.);
+        world.resource_mut::<Toasts>().push_toast("second", 3.);
+        world.run_system_once(show_toasts);
+
+        assert_eq!(toasts_remaining(&mut world).len(), 2);
+        let container = world
+            .query_filtered::<Entity, With<ToastContainer>>()
+            .single(&world);
+        assert_eq!(world.get::<Children>(container).unwrap().len(), 2);
+
+        // the first one goes away on its own
+        pass_real_time(&mut world, 1.5);
+        assert_eq!(toasts_remaining(&mut world), [1.5]);
+        pass_real_time(&mut world, 1.5);
+        assert!(toasts_remaining(&mut world).is_empty());
+    }
+
+    #[test]
+    fn older_toasts_make_room_for_new_ones() {
+        let mut world = toast_world();
+        for i in 0..MAX_TOASTS {
+            world
+                .resource_mut::<Toasts>()
+                .push_toast(format!("toast {i}"), 5. + i as f32);
+        }
+        world.run_system_once(show_toasts);
+        world.resource_mut::<Toasts>().push_toast("newest", 5.);
+        world.run_system_once(show_toasts);
+
+        // still one container, with the one closest to expiring fading out
+        assert_eq!(
+            world
+                .query_filtered::<(), With<ToastContainer>>()
+                .iter(&world)
+                .count(),
+            1
+        );
+        let remaining = toasts_remaining(&mut world);
+        assert_eq!(remaining.len(), MAX_TOASTS + 1);
+        let fading = remaining
+            .iter()
+            .filter(|remaining| **remaining <= TOAST_FADE_TIME)
+            .count();
+        assert_eq!(fading, 1);
+    }
+}